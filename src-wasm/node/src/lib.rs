@@ -0,0 +1,214 @@
+//! `FilterEngine` 의 Node(N-API) 바인딩 - Electron main 프로세스 / server 에서 sdb·serial 스트림 사전 필터링용
+//!
+//! 메서드 이름과 인자 순서는 WASM 빌드(`src/lib.rs`)와 같습니다. (`filter_chunk(data, offsets, base)` 등)
+//! 차이점:
+//! - 바이트 입력은 `Buffer`, 결과는 `Int32Array` / `Uint32Array` / `Buffer` 입니다.
+//! - WASM 선형 메모리 전용 API(`get_buffer_ptr`, `reserve_buffer`, `check_match_ptr`, `filter_buffer_ptr`)는 없습니다.
+//!   Node 에서는 `Buffer` 를 그대로 넘겨도 복사 없이 참조됩니다.
+//! - `TimeIndexer` 가 없으므로 `filter_chunk_timed` 도 없습니다. 시간 범위는 `time_seed` (`last_time()`) 로 이어 읽습니다.
+//! - 룰 에러는 `Error` 로 throw 됩니다. (쿼리 파싱 에러는 WASM 과 같은 `{ message, start, end }` 객체)
+
+use napi::bindgen_prelude::*;
+use napi::{Env, JsUnknown};
+use napi_derive::napi;
+use serde_json::Value;
+
+use happy_filter::error::FilterError;
+use happy_filter::highlight::HighlightSpec;
+use happy_filter::query::{self, QueryError};
+use happy_filter::quick::QuickFilter;
+use happy_filter::rule::RuleSpec;
+use happy_filter::shape::ShapeSpec;
+use happy_filter::time::NO_TIME;
+use happy_filter::timeline::{LineTimes, TimeRange};
+
+#[napi(js_name = "FilterEngine")]
+pub struct NodeFilterEngine {
+    inner: happy_filter::FilterEngine,
+}
+
+#[napi]
+impl NodeFilterEngine {
+    #[napi(constructor)]
+    pub fn new(case_sensitive: bool) -> Self {
+        NodeFilterEngine { inner: happy_filter::FilterEngine::new(case_sensitive) }
+    }
+
+    #[napi(js_name = "update_keywords")]
+    pub fn update_keywords(&mut self, keywords: Vec<String>) -> Result<()> {
+        let groups: Vec<Vec<String>> = keywords.into_iter().map(|k| vec![k]).collect();
+        self.inner.set_groups(groups).map_err(filter_error)
+    }
+
+    #[napi(js_name = "update_groups")]
+    pub fn update_groups(&mut self, groups: Vec<Vec<String>>) -> Result<()> {
+        self.inner.set_groups(groups).map_err(filter_error)
+    }
+
+    #[napi(js_name = "update_query")]
+    pub fn update_query(&mut self, env: Env, query: String) -> Result<()> {
+        let compiled = query::compile_query(&query).map_err(|e| query_error(&env, e))?;
+        self.inner.set_groups(compiled.include_groups).map_err(filter_error)
+    }
+
+    #[napi(js_name = "update_excludes")]
+    pub fn update_excludes(&mut self, excludes: Vec<String>, case_sensitive: bool) -> Result<()> {
+        self.inner.set_excludes(excludes, case_sensitive).map_err(filter_error)
+    }
+
+    #[napi(js_name = "update_highlights")]
+    pub fn update_highlights(&mut self, highlights: Value, case_sensitive: bool) -> Result<()> {
+        let highlights: Vec<HighlightSpec> = from_json(highlights)?;
+        self.inner.set_highlights(highlights, case_sensitive).map_err(filter_error)
+    }
+
+    #[napi(js_name = "update_quick_filter")]
+    pub fn update_quick_filter(&mut self, mode: String) -> Result<()> {
+        let mode = QuickFilter::parse(&mode).ok_or_else(|| Error::from_reason(format!("Unknown quick filter: {}", mode)))?;
+        self.inner.set_quick_filter(mode).map_err(filter_error)
+    }
+
+    #[napi(js_name = "update_shell_bypass")]
+    pub fn update_shell_bypass(&mut self, enabled: bool) -> Result<()> {
+        self.inner.set_shell_bypass(enabled).map_err(filter_error)
+    }
+
+    #[napi(js_name = "update_time_range")]
+    pub fn update_time_range(&mut self, range: Option<Value>) -> Result<()> {
+        let range: Option<TimeRange> = match range {
+            None | Some(Value::Null) => None,
+            Some(range) => Some(from_json(range)?),
+        };
+        self.inner.set_time_range(range).map_err(filter_error)
+    }
+
+    #[napi(js_name = "update_line_shapes")]
+    pub fn update_line_shapes(&mut self, config: Value) -> Result<()> {
+        let shapes: ShapeSpec = from_json(config)?;
+        self.inner.set_line_shapes(shapes).map_err(filter_error)
+    }
+
+    #[napi(js_name = "update_rule")]
+    pub fn update_rule(&mut self, rule: Value) -> Result<()> {
+        let spec: RuleSpec = from_json(rule)?;
+        self.inner.set_rule(spec).map_err(filter_error)
+    }
+
+    #[napi(js_name = "set_collect_stats")]
+    pub fn set_collect_stats(&mut self, enabled: bool) {
+        self.inner.set_collect_stats(enabled);
+    }
+
+    #[napi(js_name = "take_stats")]
+    pub fn take_stats(&mut self) -> Result<Value> {
+        let stats = self.inner.take_rule_stats().unwrap_or_default();
+        to_json(&stats)
+    }
+
+    #[napi(js_name = "update_rules")]
+    pub fn update_rules(&mut self, rules: Value) -> Result<()> {
+        let specs: Vec<RuleSpec> = from_json(rules)?;
+        self.inner.set_rules(&specs).map_err(filter_error)
+    }
+
+    #[napi(js_name = "match_mask")]
+    pub fn match_mask(&mut self, text: String) -> u32 {
+        self.inner.match_mask(&text)
+    }
+
+    #[napi(js_name = "filter_chunk_masks")]
+    pub fn filter_chunk_masks(&mut self, data: Buffer, line_offsets: Option<Uint32Array>, time_seed: Option<f64>) -> Uint32Array {
+        Uint32Array::new(self.inner.mask_lines(&data, line_offsets.as_deref(), time_seed_micros(time_seed)))
+    }
+
+    #[napi(js_name = "export_rule")]
+    pub fn export_rule(&self) -> Result<Buffer> {
+        Ok(self.inner.rule_blob().map_err(filter_error)?.into())
+    }
+
+    #[napi(factory, js_name = "from_blob")]
+    pub fn from_blob(blob: Buffer) -> Result<Self> {
+        let mut engine = NodeFilterEngine::new(false);
+        engine.load_rule(blob)?;
+        Ok(engine)
+    }
+
+    #[napi(js_name = "load_rule")]
+    pub fn load_rule(&mut self, blob: Buffer) -> Result<()> {
+        self.inner.set_rule_blob(&blob).map_err(filter_error)
+    }
+
+    #[napi(js_name = "is_empty")]
+    pub fn is_empty(&self) -> bool {
+        self.inner.is_empty()
+    }
+
+    #[napi(js_name = "check_match")]
+    pub fn check_match(&mut self, text: String) -> bool {
+        self.inner.check_match(&text)
+    }
+
+    #[napi(js_name = "filter_chunk")]
+    pub fn filter_chunk(&mut self, data: Buffer, line_offsets: Option<Uint32Array>, base_index: i32, time_seed: Option<f64>) -> Int32Array {
+        let times = LineTimes::Parse(time_seed_micros(time_seed));
+        Int32Array::new(self.inner.filter_lines_with(&data, line_offsets.as_deref(), base_index, times))
+    }
+
+    #[napi(js_name = "last_time")]
+    pub fn last_time(&self) -> Option<f64> {
+        match self.inner.last_line_time() {
+            NO_TIME => None,
+            micros => Some(micros as f64),
+        }
+    }
+
+    #[napi(js_name = "highlight_spans")]
+    pub fn highlight_spans(&mut self, text: String) -> Uint32Array {
+        Uint32Array::new(self.inner.highlight_spans(&text))
+    }
+
+    #[napi(js_name = "highlight_spans_batch")]
+    pub fn highlight_spans_batch(&mut self, data: Buffer, line_offsets: Option<Uint32Array>) -> Uint32Array {
+        let line_offsets = line_offsets.map(|o| o.to_vec().into_boxed_slice());
+        Uint32Array::new(self.inner.highlight_spans_batch(&data, line_offsets))
+    }
+
+    #[napi(js_name = "last_line_count")]
+    pub fn last_line_count(&self) -> u32 {
+        self.inner.last_line_count() as u32
+    }
+}
+
+/// 쿼리 문자열을 `{ includeGroups }` 로 컴파일
+#[napi(js_name = "compile_query")]
+pub fn compile_query(env: Env, query: String) -> Result<Value> {
+    let compiled = query::compile_query(&query).map_err(|e| query_error(&env, e))?;
+    to_json(&compiled)
+}
+
+fn from_json<T: serde::de::DeserializeOwned>(value: Value) -> Result<T> {
+    serde_json::from_value(value).map_err(|e| Error::new(Status::InvalidArg, e.to_string()))
+}
+
+fn to_json<T: serde::Serialize>(value: &T) -> Result<Value> {
+    serde_json::to_value(value).map_err(|e| Error::from_reason(e.to_string()))
+}
+
+/// WASM 빌드와 같은 `{ message, start, end }` 객체를 throw (UI 에서 밑줄 표시용)
+fn query_error(env: &Env, e: QueryError) -> Error {
+    let thrown = env.to_js_value(&e).and_then(|value: JsUnknown| env.throw(value));
+    match thrown {
+        Ok(()) => Error::from_status(Status::PendingException),
+        Err(_) => Error::new(Status::InvalidArg, e.to_string()),
+    }
+}
+
+fn filter_error(e: FilterError) -> Error {
+    Error::new(Status::InvalidArg, e.to_string())
+}
+
+/// WASM 빌드와 같은 `time_seed` (epoch 마이크로초, `undefined` = 모름)
+fn time_seed_micros(seed: Option<f64>) -> i64 {
+    seed.map_or(NO_TIME, |micros| micros as i64)
+}
+
@@ -0,0 +1,304 @@
+use memchr::memchr;
+use serde::{Deserialize, Serialize};
+
+use crate::line;
+
+/// ANSI 처리 방식
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum AnsiMode {
+    /// 이스케이프만 제거 (매칭용 텍스트)
+    #[default]
+    Strip,
+    /// 제거하면서 SGR 상태를 따라가 스타일 구간을 만듦
+    Style,
+}
+
+impl AnsiMode {
+    pub fn parse(mode: &str) -> Option<Self> {
+        match mode {
+            "strip" => Some(AnsiMode::Strip),
+            "style" => Some(AnsiMode::Style),
+            _ => None,
+        }
+    }
+}
+
+/// 색상. JS 로는 `u32` 로 넘깁니다: 0 = 기본색, `0x100 | n` = 256색 팔레트 n, `0x1000000 | rrggbb` = RGB
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Color {
+    #[default]
+    Default,
+    /// 0~15 = 기본 16색 (8~15 는 밝은 색), 16~255 = xterm 256색
+    Indexed(u8),
+    Rgb(u8, u8, u8),
+}
+
+impl Color {
+    pub fn to_u32(self) -> u32 {
+        match self {
+            Color::Default => 0,
+            Color::Indexed(n) => 0x100 | n as u32,
+            Color::Rgb(r, g, b) => 0x100_0000 | (r as u32) << 16 | (g as u32) << 8 | b as u32,
+        }
+    }
+}
+
+/// SGR(`\x1B[...m`) 로 바뀌는 글자 스타일
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Style {
+    pub fg: Color,
+    pub bg: Color,
+    pub bold: bool,
+}
+
+impl Style {
+    pub fn is_default(&self) -> bool {
+        *self == Style::default()
+    }
+
+    /// SGR 파라미터 적용 (`ESC[` 와 `m` 사이, 빈 파라미터는 0)
+    fn apply_sgr(&mut self, params: &[u8]) {
+        let mut codes = params.split(|&b| b == b';' || b == b':').map(|p| {
+            std::str::from_utf8(p).ok().and_then(|s| s.parse::<u32>().ok()).unwrap_or(0)
+        });
+        while let Some(code) = codes.next() {
+            match code {
+                0 => *self = Style::default(),
+                1 => self.bold = true,
+                22 => self.bold = false,
+                30..=37 => self.fg = Color::Indexed((code - 30) as u8),
+                39 => self.fg = Color::Default,
+                40..=47 => self.bg = Color::Indexed((code - 40) as u8),
+                49 => self.bg = Color::Default,
+                90..=97 => self.fg = Color::Indexed((code - 90 + 8) as u8),
+                100..=107 => self.bg = Color::Indexed((code - 100 + 8) as u8),
+                38 | 48 => {
+                    let color = match codes.next() {
+                        Some(5) => codes.next().map(|n| Color::Indexed(n.min(255) as u8)),
+                        Some(2) => {
+                            let mut c = || codes.next().unwrap_or(0).min(255) as u8;
+                            Some(Color::Rgb(c(), c(), c()))
+                        }
+                        _ => None,
+                    };
+                    if let Some(color) = color {
+                        if code == 38 {
+                            self.fg = color;
+                        } else {
+                            self.bg = color;
+                        }
+                    }
+                }
+                // 밑줄/기울임 등은 무시
+                _ => {}
+            }
+        }
+    }
+}
+
+/// 스타일 구간 (정리된 텍스트 기준 UTF-16 오프셋, 기본 스타일 구간은 만들지 않음)
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct StyleSpan {
+    pub start: usize,
+    pub end: usize,
+    pub style: Style,
+}
+
+/// ESC 로 시작하는 이스케이프 시퀀스 종류와 길이
+enum Escape<'a> {
+    /// SGR 파라미터
+    Sgr(&'a [u8]),
+    /// 그 밖의 CSI / OSC / 2바이트 시퀀스 (커서 이동, 창 제목 등) - 버림
+    Other,
+}
+
+/// `s[0] == ESC` 일 때 시퀀스 해석. 완결되지 않은 시퀀스면 `None` (ESC 를 일반 문자로 취급)
+fn parse_escape(s: &[u8]) -> Option<(Escape<'_>, usize)> {
+    match s.get(1)? {
+        // CSI: 파라미터(0x30~0x3F)* 중간(0x20~0x2F)* 종결(0x40~0x7E)
+        b'[' => {
+            let mut i = 2;
+            while i < s.len() && (0x30..=0x3F).contains(&s[i]) {
+                i += 1;
+            }
+            let params_end = i;
+            while i < s.len() && (0x20..=0x2F).contains(&s[i]) {
+                i += 1;
+            }
+            match s.get(i) {
+                Some(b'm') if params_end == i => Some((Escape::Sgr(&s[2..params_end]), i + 1)),
+                Some(0x40..=0x7E) => Some((Escape::Other, i + 1)),
+                _ => None,
+            }
+        }
+        // OSC / DCS / SOS / PM / APC: 내용까지 BEL 또는 ST(`ESC \`) 까지
+        b']' | b'P' | b'X' | b'^' | b'_' => {
+            let mut i = 2;
+            while i < s.len() {
+                match s[i] {
+                    0x07 => return Some((Escape::Other, i + 1)),
+                    0x1B if s.get(i + 1) == Some(&b'\\') => return Some((Escape::Other, i + 2)),
+                    _ => i += 1,
+                }
+            }
+            None
+        }
+        // 문자셋 지정 `ESC ( B` 등
+        b'(' | b')' | b'#' => s.get(2).map(|_| (Escape::Other, 3)),
+        // 2바이트 시퀀스 (`ESC 7` / `ESC 8` 커서 저장/복원, `ESC =` 키패드 모드 등 포함)
+        0x30..=0x7E => Some((Escape::Other, 2)),
+        _ => None,
+    }
+}
+
+/// 라인에서 ANSI 이스케이프 제거 (ESC 가 없으면 복사 없이 원본 그대로)
+///
+/// `\x1B[...m` 뿐 아니라 커서 이동 CSI(`\x1B[?25l`, `\x1B[2K`), OSC(`\x1B]0;title\x07`), DCS(`\x1BP...\x1B\\`) 도 지웁니다.
+pub fn strip<'a>(line: &'a [u8], buf: &'a mut Vec<u8>) -> &'a [u8] {
+    if memchr(0x1B, line).is_none() {
+        return line;
+    }
+    buf.clear();
+    let mut rest = line;
+    while let Some(pos) = memchr(0x1B, rest) {
+        buf.extend_from_slice(&rest[..pos]);
+        match parse_escape(&rest[pos..]) {
+            Some((_, len)) => rest = &rest[pos + len..],
+            None => {
+                buf.push(0x1B);
+                rest = &rest[pos + 1..];
+            }
+        }
+    }
+    buf.extend_from_slice(rest);
+    buf
+}
+
+/// 라인을 넘어 SGR 상태를 이어가며 스타일 구간을 만드는 처리기
+///
+/// 쉘 도구는 색을 켠 채 여러 줄을 찍고 나중에 끄는 경우가 많아서, 상태는 라인(청크) 사이에서 유지됩니다.
+/// 파일 중간부터 다시 읽을 때는 `reset` 후 사용하세요.
+#[derive(Default)]
+pub struct AnsiStyler {
+    style: Style,
+}
+
+impl AnsiStyler {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn reset(&mut self) {
+        self.style = Style::default();
+    }
+
+    /// 이스케이프를 지운 텍스트를 `text` 뒤에 붙이고, 그 라인의 스타일 구간을 `spans` 에 채웁니다.
+    pub fn style_line(&mut self, line: &[u8], text: &mut Vec<u8>, spans: &mut Vec<StyleSpan>) {
+        spans.clear();
+        let mut units = 0;
+        let mut rest = line;
+        loop {
+            let pos = memchr(0x1B, rest);
+            let run = &rest[..pos.unwrap_or(rest.len())];
+            if !run.is_empty() {
+                let end = units + line::utf16_len(run);
+                if !self.style.is_default() {
+                    match spans.last_mut() {
+                        Some(last) if last.end == units && last.style == self.style => last.end = end,
+                        _ => spans.push(StyleSpan { start: units, end, style: self.style }),
+                    }
+                }
+                text.extend_from_slice(run);
+                units = end;
+            }
+            let Some(pos) = pos else { break };
+            rest = &rest[pos..];
+            match parse_escape(rest) {
+                Some((escape, len)) => {
+                    if let Escape::Sgr(params) = escape {
+                        self.style.apply_sgr(params);
+                    }
+                    rest = &rest[len..];
+                }
+                None => {
+                    // 완결되지 않은 ESC 는 글자로 남김 (`strip` 과 동일)
+                    if !self.style.is_default() {
+                        spans.push(StyleSpan { start: units, end: units + 1, style: self.style });
+                    }
+                    text.push(0x1B);
+                    units += 1;
+                    rest = &rest[1..];
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stripped(line: &str) -> String {
+        let mut buf = Vec::new();
+        String::from_utf8(strip(line.as_bytes(), &mut buf).to_vec()).unwrap()
+    }
+
+    #[test]
+    fn strip_removes_sgr_csi_osc_and_charset() {
+        assert_eq!(stripped("\x1B[1;31mE/Tag\x1B[0m: fail"), "E/Tag: fail");
+        assert_eq!(stripped("\x1B[?25l\x1B[2Kprogress\x1B[?25h"), "progress");
+        assert_eq!(stripped("\x1B]0;title\x07a\x1B]8;;url\x1B\\b"), "ab");
+        assert_eq!(stripped("\x1B(Bx\x1B=y\x1B7z\x1B8"), "xyz");
+        assert_eq!(stripped("a\x1BP1$r0m\x1B\\b\x1B_apc\x07c\x1BXsos\x1B\\d\x1B^pm\x1B\\e"), "abcde");
+        // 끝나지 않은 시퀀스는 그대로
+        assert_eq!(stripped("tail \x1B[31"), "tail \x1B[31");
+        assert_eq!(stripped("lone \x1B"), "lone \x1B");
+        assert_eq!(stripped("dcs \x1BPq"), "dcs \x1BPq");
+    }
+
+    #[test]
+    fn strip_without_escape_borrows_the_line() {
+        let line = b"plain".as_slice();
+        let mut buf = Vec::new();
+        assert!(std::ptr::eq(strip(line, &mut buf), line));
+    }
+
+    #[test]
+    fn styles_carry_across_lines_until_reset() {
+        let mut styler = AnsiStyler::new();
+        let (mut text, mut spans) = (Vec::new(), Vec::new());
+        styler.style_line(b"ok \x1B[1;32mgreen", &mut text, &mut spans);
+        let green = Style { fg: Color::Indexed(2), bold: true, ..Style::default() };
+        assert_eq!(spans, [StyleSpan { start: 3, end: 8, style: green }]);
+
+        text.clear();
+        styler.style_line(b"still\x1B[22;39m plain", &mut text, &mut spans);
+        assert_eq!(text, b"still plain");
+        assert_eq!(spans, [StyleSpan { start: 0, end: 5, style: green }]);
+
+        styler.style_line(b"\x1B[44mblue bg", &mut text, &mut spans);
+        styler.reset();
+        styler.style_line(b"after reset", &mut text, &mut spans);
+        assert!(spans.is_empty());
+    }
+
+    #[test]
+    fn extended_colors_and_utf16_offsets() {
+        let mut style = Style::default();
+        style.apply_sgr(b"38;5;208;48;2;1;2;3");
+        assert_eq!(style.fg.to_u32(), 0x100 | 208);
+        assert_eq!(style.bg.to_u32(), 0x100_0000 | 0x010203);
+        style.apply_sgr(b"");
+        assert!(style.is_default());
+        style.apply_sgr(b"91;4");
+        assert_eq!(style.fg, Color::Indexed(9));
+
+        let mut styler = AnsiStyler::new();
+        let (mut text, mut spans) = (Vec::new(), Vec::new());
+        styler.style_line("한글😀 \x1B[31mred\x1B[0m".as_bytes(), &mut text, &mut spans);
+        assert_eq!((spans[0].start, spans[0].end), (5, 8));
+        assert_eq!(AnsiMode::parse("style"), Some(AnsiMode::Style));
+        assert_eq!(AnsiMode::parse("color"), None);
+    }
+}
@@ -0,0 +1,675 @@
+use flate2::write::{DeflateDecoder, MultiGzDecoder};
+use serde::Serialize;
+use std::io::{self, Read, Write};
+
+use crate::error::FilterError;
+
+/// 매직 바이트로 판별한 입력 형식
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+pub enum ArchiveKind {
+    #[serde(rename = "plain")]
+    Plain,
+    #[serde(rename = "gzip")]
+    Gzip,
+    #[serde(rename = "tar")]
+    Tar,
+    #[serde(rename = "tar.gz")]
+    TarGz,
+    #[serde(rename = "zip")]
+    Zip,
+}
+
+impl ArchiveKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ArchiveKind::Plain => "plain",
+            ArchiveKind::Gzip => "gzip",
+            ArchiveKind::Tar => "tar",
+            ArchiveKind::TarGz => "tar.gz",
+            ArchiveKind::Zip => "zip",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<ArchiveKind> {
+        match s {
+            "plain" => Some(ArchiveKind::Plain),
+            "gzip" | "gz" => Some(ArchiveKind::Gzip),
+            "tar" => Some(ArchiveKind::Tar),
+            "tar.gz" | "tgz" => Some(ArchiveKind::TarGz),
+            "zip" => Some(ArchiveKind::Zip),
+            _ => None,
+        }
+    }
+
+    /// 여러 파일이 들어 있을 수 있는 형식 (`list_members` 로 골라야 함)
+    pub fn is_multi(self) -> bool {
+        matches!(self, ArchiveKind::Tar | ArchiveKind::TarGz | ArchiveKind::Zip)
+    }
+}
+
+/// 아카이브 안의 파일 하나 (`index` 는 `extract` / `ArchiveDecoder` 에 넘기는 번호)
+#[derive(Clone, Debug, Serialize)]
+pub struct Member {
+    pub index: usize,
+    pub name: String,
+    pub size: u64,
+}
+
+const GZIP_MAGIC: &[u8] = &[0x1F, 0x8B];
+const ZIP_MAGIC: &[u8] = b"PK\x03\x04";
+const ZIP_EMPTY_MAGIC: &[u8] = b"PK\x05\x06";
+const TAR_BLOCK: usize = 512;
+/// `sink` 로 한 번에 넘기는 최대 크기 (풀린 내용 전체를 메모리에 들고 있지 않도록)
+const OUTPUT_CHUNK: usize = 64 << 10;
+
+/// 파일 앞부분(512바이트 이상 권장)으로 형식 판별. gzip 은 앞부분을 풀어서 tar 인지까지 봅니다.
+pub fn detect(head: &[u8]) -> ArchiveKind {
+    if head.starts_with(ZIP_MAGIC) || head.starts_with(ZIP_EMPTY_MAGIC) {
+        return ArchiveKind::Zip;
+    }
+    if head.starts_with(GZIP_MAGIC) {
+        // 잘린 입력이라 끝에서 에러가 나는 건 정상 - 풀린 만큼만 확인
+        let mut inflated = Vec::with_capacity(TAR_BLOCK);
+        let _ = flate2::read::GzDecoder::new(head).take(TAR_BLOCK as u64).read_to_end(&mut inflated);
+        return if is_tar_header(&inflated) { ArchiveKind::TarGz } else { ArchiveKind::Gzip };
+    }
+    if is_tar_header(head) {
+        return ArchiveKind::Tar;
+    }
+    ArchiveKind::Plain
+}
+
+/// ustar / GNU tar 헤더 (`ustar` 매직 + 헤더 체크섬)
+fn is_tar_header(block: &[u8]) -> bool {
+    if block.len() < TAR_BLOCK || &block[257..262] != b"ustar" {
+        return false;
+    }
+    let stored = match parse_octal(&block[148..156]) {
+        Some(v) => v,
+        None => return false,
+    };
+    let sum: u64 = block[..TAR_BLOCK]
+        .iter()
+        .enumerate()
+        .map(|(i, &b)| if (148..156).contains(&i) { b' ' as u64 } else { b as u64 })
+        .sum();
+    sum == stored
+}
+
+/// 아카이브 전체를 받아 파일 목록 (일반 파일만). gzip/plain 은 파일 하나짜리 목록입니다.
+pub fn list_members(data: &[u8]) -> Result<Vec<Member>, FilterError> {
+    match detect(data) {
+        ArchiveKind::Zip => Ok(zip_entries(data)?.into_iter().map(|e| e.member).collect()),
+        kind @ (ArchiveKind::Tar | ArchiveKind::TarGz) => {
+            let mut decoder = ArchiveDecoder::new(kind, None)?;
+            decoder.push(data, &mut |_, _| {})?;
+            decoder.finish(&mut |_, _| {})?;
+            Ok(decoder.members)
+        }
+        ArchiveKind::Gzip => Ok(vec![Member { index: 0, name: gzip_name(data).unwrap_or_default(), size: gzip_size(data) }]),
+        ArchiveKind::Plain => Ok(vec![Member { index: 0, name: String::new(), size: data.len() as u64 }]),
+    }
+}
+
+/// 아카이브 전체에서 `member` 번 파일을 풀어 `sink` 로 조금씩 넘깁니다.
+///
+/// zip 은 중앙 디렉터리가 끝에 있어서 `ArchiveDecoder` 로 스트리밍할 수 없고 이 함수로만 풉니다.
+/// 아카이브 전체가 메모리에 있어야 하지만, 풀린 내용은 형식과 상관없이 64KB 이하 청크로 `sink` 에 나옵니다.
+pub fn extract(data: &[u8], member: usize, sink: &mut dyn FnMut(&[u8])) -> Result<(), FilterError> {
+    match detect(data) {
+        ArchiveKind::Zip => {
+            let entries = zip_entries(data)?;
+            let entry = entries.get(member).ok_or_else(|| no_member(member))?;
+            zip_extract(data, entry, sink)
+        }
+        kind => {
+            let mut decoder = ArchiveDecoder::new(kind, Some(member))?;
+            decoder.push(data, &mut |_, chunk| sink(chunk))?;
+            decoder.finish(&mut |_, chunk| sink(chunk))?;
+            if kind.is_multi() && !decoder.found {
+                return Err(no_member(member));
+            }
+            Ok(())
+        }
+    }
+}
+
+fn no_member(index: usize) -> FilterError {
+    FilterError::Archive(format!("No archive member #{}", index))
+}
+
+/// 스트리밍 압축 해제기 (gzip / tar / tar.gz / plain)
+///
+/// 파일을 청크 단위로 `push` 하면 풀린 내용이 `sink(파일, 바이트)` 로 나옵니다. (인덱서 / 필터에 그대로 연결)
+/// tar 는 목록이 앞에 없어서, 스트림을 끝까지 통과시키면 `members()` 에 전체 목록이 쌓입니다.
+/// gzip / plain 은 이름 없는 파일 하나(`index` 0)로 취급합니다.
+pub struct ArchiveDecoder {
+    gz: Option<MultiGzDecoder<Vec<u8>>>,
+    tar: Option<TarState>,
+    member: Option<usize>,
+    members: Vec<Member>,
+    found: bool,
+}
+
+/// tar 스트림 파서 상태
+#[derive(Default)]
+struct TarState {
+    header: Vec<u8>,
+    /// 현재 엔트리의 남은 데이터 / 패딩 바이트
+    remaining: u64,
+    padding: u64,
+    current: Option<TarEntry>,
+    /// GNU `L` / pax `path` 로 다음 엔트리 이름을 덮어쓰기
+    long_name: Option<String>,
+    meta: Vec<u8>,
+    ended: bool,
+}
+
+enum TarEntry {
+    /// 일반 파일 (`emit` = 고른 파일이라 sink 로 내보냄)
+    File { emit: bool },
+    /// 이름 확장 메타데이터 (`L` / `x`)
+    Meta { pax: bool },
+    Skip,
+}
+
+impl ArchiveDecoder {
+    /// `member`: tar / tar.gz 에서 풀 파일 번호 (`None` 이면 모든 파일). gzip / plain 은 무시됩니다.
+    ///
+    /// zip 은 스트리밍할 수 없으므로 에러입니다. (`extract` 사용)
+    pub fn new(kind: ArchiveKind, member: Option<usize>) -> Result<Self, FilterError> {
+        if kind == ArchiveKind::Zip {
+            return Err(FilterError::Archive(
+                "zip archives cannot be streamed (central directory is at the end), extract the member from the whole archive".to_string(),
+            ));
+        }
+        let gz = matches!(kind, ArchiveKind::Gzip | ArchiveKind::TarGz).then(|| MultiGzDecoder::new(Vec::new()));
+        let tar = matches!(kind, ArchiveKind::Tar | ArchiveKind::TarGz).then(TarState::default);
+        let members = if tar.is_some() { Vec::new() } else { vec![Member { index: 0, name: String::new(), size: 0 }] };
+        Ok(ArchiveDecoder { gz, tar, member, members, found: false })
+    }
+
+    pub fn members(&self) -> &[Member] {
+        &self.members
+    }
+
+    /// 큰 청크도 조금씩 풀어서 64KB 이하로 나눠 `sink` 에 넘깁니다.
+    pub fn push(&mut self, chunk: &[u8], sink: &mut dyn FnMut(&Member, &[u8])) -> Result<(), FilterError> {
+        let mut gz = match self.gz.take() {
+            Some(gz) => gz,
+            None => {
+                chunk.chunks(OUTPUT_CHUNK).for_each(|c| self.emit(c, sink));
+                return Ok(());
+            }
+        };
+        let result = inflate_in_steps(&mut gz, chunk, |gz| {
+            let out = gz.get_mut();
+            self.emit(out, sink);
+            // 출력 버퍼 재사용
+            out.clear();
+        });
+        self.gz = Some(gz);
+        result.map_err(|e| FilterError::Archive(format!("gzip: {}", e)))
+    }
+
+    /// 스트림 끝. 잘린 gzip 은 에러입니다.
+    pub fn finish(&mut self, sink: &mut dyn FnMut(&Member, &[u8])) -> Result<(), FilterError> {
+        if let Some(gz) = self.gz.as_mut() {
+            gz.try_finish().map_err(|e| FilterError::Archive(format!("gzip: {}", e)))?;
+            let out = std::mem::take(gz.get_mut());
+            out.chunks(OUTPUT_CHUNK).for_each(|c| self.emit(c, sink));
+        }
+        Ok(())
+    }
+
+    fn emit(&mut self, mut data: &[u8], sink: &mut dyn FnMut(&Member, &[u8])) {
+        let tar = match self.tar.as_mut() {
+            Some(tar) => tar,
+            None => {
+                if !data.is_empty() {
+                    self.members[0].size += data.len() as u64;
+                    sink(&self.members[0], data);
+                }
+                return;
+            }
+        };
+
+        while !data.is_empty() && !tar.ended {
+            if tar.remaining > 0 {
+                let n = (tar.remaining.min(data.len() as u64)) as usize;
+                match &tar.current {
+                    Some(TarEntry::File { emit: true }) => sink(self.members.last().expect("file entry"), &data[..n]),
+                    Some(TarEntry::Meta { .. }) => tar.meta.extend_from_slice(&data[..n]),
+                    _ => {}
+                }
+                tar.remaining -= n as u64;
+                data = &data[n..];
+                if tar.remaining == 0 {
+                    if let Some(TarEntry::Meta { pax }) = tar.current {
+                        tar.long_name = meta_name(&tar.meta, pax);
+                        tar.meta.clear();
+                    }
+                }
+                continue;
+            }
+            if tar.padding > 0 {
+                let n = (tar.padding.min(data.len() as u64)) as usize;
+                tar.padding -= n as u64;
+                data = &data[n..];
+                continue;
+            }
+
+            let need = TAR_BLOCK - tar.header.len();
+            let n = need.min(data.len());
+            tar.header.extend_from_slice(&data[..n]);
+            data = &data[n..];
+            if tar.header.len() < TAR_BLOCK {
+                break;
+            }
+            let header = std::mem::take(&mut tar.header);
+            // 0 으로 채운 블록 = 아카이브 끝
+            if header.iter().all(|&b| b == 0) {
+                tar.ended = true;
+                break;
+            }
+            let size = parse_size(&header[124..136]).unwrap_or(0);
+            tar.remaining = size;
+            tar.padding = (TAR_BLOCK as u64 - size % TAR_BLOCK as u64) % TAR_BLOCK as u64;
+            tar.current = Some(match header[156] {
+                b'0' | 0 | b'7' => {
+                    let name = tar.long_name.take().unwrap_or_else(|| header_name(&header));
+                    let index = self.members.len();
+                    let emit = self.member.is_none_or(|m| m == index);
+                    self.found |= emit;
+                    self.members.push(Member { index, name, size });
+                    TarEntry::File { emit }
+                }
+                b'L' => TarEntry::Meta { pax: false },
+                b'x' => TarEntry::Meta { pax: true },
+                _ => {
+                    tar.long_name = None;
+                    TarEntry::Skip
+                }
+            });
+            if size == 0 {
+                tar.current = None;
+            }
+        }
+    }
+}
+
+/// ustar `prefix/name`
+fn header_name(header: &[u8]) -> String {
+    let name = cstr(&header[..100]);
+    let prefix = cstr(&header[345..500]);
+    if prefix.is_empty() { name } else { format!("{}/{}", prefix, name) }
+}
+
+/// GNU long name (`L`) 본문 또는 pax 헤더의 `path=` 레코드
+fn meta_name(meta: &[u8], pax: bool) -> Option<String> {
+    if !pax {
+        return Some(cstr(meta));
+    }
+    // "<len> key=value\n" 레코드들
+    let text = String::from_utf8_lossy(meta);
+    text.lines().find_map(|record| {
+        let (_, kv) = record.split_once(' ')?;
+        kv.strip_prefix("path=").map(str::to_string)
+    })
+}
+
+fn cstr(bytes: &[u8]) -> String {
+    let end = memchr::memchr(0, bytes).unwrap_or(bytes.len());
+    String::from_utf8_lossy(&bytes[..end]).into_owned()
+}
+
+fn parse_octal(field: &[u8]) -> Option<u64> {
+    let s = std::str::from_utf8(field).ok()?;
+    let s = s.trim_matches(|c: char| c == '\0' || c == ' ');
+    if s.is_empty() {
+        return Some(0);
+    }
+    u64::from_str_radix(s, 8).ok()
+}
+
+/// 크기 필드: 8진수, 8GB 를 넘으면 GNU base-256 (첫 바이트 최상위 비트)
+fn parse_size(field: &[u8]) -> Option<u64> {
+    if field[0] & 0x80 != 0 {
+        let mut v: u64 = (field[0] & 0x7F) as u64;
+        for &b in &field[1..] {
+            v = v.checked_mul(256)? | b as u64;
+        }
+        return Some(v);
+    }
+    parse_octal(field)
+}
+
+/// gzip 헤더의 원래 파일 이름 (FNAME)
+fn gzip_name(data: &[u8]) -> Option<String> {
+    let flags = *data.get(3)?;
+    if flags & 0x08 == 0 {
+        return None;
+    }
+    let mut pos = 10;
+    if flags & 0x04 != 0 {
+        let xlen = u16::from_le_bytes([*data.get(10)?, *data.get(11)?]) as usize;
+        pos += 2 + xlen;
+    }
+    let rest = data.get(pos..)?;
+    let end = memchr::memchr(0, rest)?;
+    Some(String::from_utf8_lossy(&rest[..end]).into_owned())
+}
+
+/// gzip 트레일러의 원본 크기 (4GB 로 나눈 나머지라 참고용)
+fn gzip_size(data: &[u8]) -> u64 {
+    match data.len() {
+        n if n >= 18 => u32::from_le_bytes(data[n - 4..].try_into().expect("4 bytes")) as u64,
+        _ => 0,
+    }
+}
+
+struct ZipEntry {
+    member: Member,
+    method: u16,
+    compressed_size: u64,
+    header_offset: u64,
+}
+
+/// 중앙 디렉터리 읽기 (zip64 포함). 디렉터리 엔트리는 빠집니다.
+fn zip_entries(data: &[u8]) -> Result<Vec<ZipEntry>, FilterError> {
+    let corrupt = |what: &str| FilterError::Archive(format!("Corrupt zip: {}", what));
+    let le16 = |p: usize| data.get(p..p.checked_add(2)?).map(|b| u16::from_le_bytes([b[0], b[1]]));
+    let le32 = |p: usize| data.get(p..p.checked_add(4)?).map(|b| u32::from_le_bytes(b.try_into().expect("4 bytes")));
+    let le64 = |p: usize| data.get(p..p.checked_add(8)?).map(|b| u64::from_le_bytes(b.try_into().expect("8 bytes")));
+
+    // 끝에서 EOCD 찾기 (주석 최대 64KB)
+    let search_from = data.len().saturating_sub(22 + 0xFFFF);
+    let eocd = memchr::memmem::rfind(&data[search_from..], b"PK\x05\x06")
+        .map(|p| search_from + p)
+        .ok_or_else(|| corrupt("end of central directory not found"))?;
+    let mut count = le16(eocd + 10).ok_or_else(|| corrupt("EOCD"))? as u64;
+    let mut cd_offset = le32(eocd + 16).ok_or_else(|| corrupt("EOCD"))? as u64;
+
+    // zip64 locator 는 EOCD 바로 앞
+    if eocd >= 20 && data[eocd - 20..].starts_with(b"PK\x06\x07") {
+        let z64 = le64(eocd - 20 + 8).ok_or_else(|| corrupt("zip64 locator"))? as usize;
+        if data.get(z64..z64.saturating_add(4)) != Some(b"PK\x06\x06") {
+            return Err(corrupt("zip64 end of central directory"));
+        }
+        count = le64(z64 + 32).ok_or_else(|| corrupt("zip64 EOCD"))?;
+        cd_offset = le64(z64 + 48).ok_or_else(|| corrupt("zip64 EOCD"))?;
+    }
+
+    let mut entries = Vec::new();
+    let mut pos = cd_offset as usize;
+    for _ in 0..count {
+        if data.get(pos..pos.saturating_add(4)) != Some(b"PK\x01\x02") {
+            return Err(corrupt("central directory entry"));
+        }
+        let field = |off: usize| le32(pos + off).ok_or_else(|| corrupt("central directory entry"));
+        let method = le16(pos + 10).ok_or_else(|| corrupt("central directory entry"))?;
+        let mut compressed_size = field(20)? as u64;
+        let mut size = field(24)? as u64;
+        let name_len = le16(pos + 28).ok_or_else(|| corrupt("central directory entry"))? as usize;
+        let extra_len = le16(pos + 30).ok_or_else(|| corrupt("central directory entry"))? as usize;
+        let comment_len = le16(pos + 32).ok_or_else(|| corrupt("central directory entry"))? as usize;
+        let mut header_offset = field(42)? as u64;
+        let name_bytes = data.get(pos + 46..pos + 46 + name_len).ok_or_else(|| corrupt("entry name"))?;
+        let name = String::from_utf8_lossy(name_bytes).into_owned();
+
+        // zip64 extra (0x0001): 0xFFFFFFFF 인 필드만 순서대로 들어 있음
+        let mut extra = pos + 46 + name_len;
+        let extra_end = extra + extra_len;
+        while extra + 4 <= extra_end {
+            let id = le16(extra).ok_or_else(|| corrupt("extra field"))?;
+            let len = le16(extra + 2).ok_or_else(|| corrupt("extra field"))? as usize;
+            if id == 0x0001 {
+                let mut p = extra + 4;
+                for value in [&mut size, &mut compressed_size, &mut header_offset] {
+                    if *value == 0xFFFF_FFFF {
+                        *value = le64(p).ok_or_else(|| corrupt("zip64 extra field"))?;
+                        p += 8;
+                    }
+                }
+            }
+            extra += 4 + len;
+        }
+
+        if !name.ends_with('/') {
+            let index = entries.len();
+            entries.push(ZipEntry { member: Member { index, name, size }, method, compressed_size, header_offset });
+        }
+        pos = extra_end + comment_len;
+    }
+    Ok(entries)
+}
+
+/// 압축 해제기에 `input` 을 나눠 넣으면서 한 번 넣을 때마다 `drain` 으로 풀린 내용을 비웁니다.
+///
+/// flate2 의 `write` 는 한 번에 내부 버퍼(32KB)만큼만 풀므로 `write_all` 처럼 입력 전체를 한 `Vec` 에 풀지 않습니다.
+fn inflate_in_steps<D: Write>(decoder: &mut D, mut input: &[u8], mut drain: impl FnMut(&mut D)) -> io::Result<()> {
+    while !input.is_empty() {
+        match decoder.write(input)? {
+            0 => return Err(io::ErrorKind::WriteZero.into()),
+            n => input = &input[n..],
+        }
+        drain(decoder);
+    }
+    Ok(())
+}
+
+fn zip_extract(data: &[u8], entry: &ZipEntry, sink: &mut dyn FnMut(&[u8])) -> Result<(), FilterError> {
+    let corrupt = || FilterError::Archive(format!("Corrupt zip entry `{}`", entry.member.name));
+    let local = usize::try_from(entry.header_offset).map_err(|_| corrupt())?;
+    let header = local.checked_add(30).and_then(|end| data.get(local..end)).ok_or_else(corrupt)?;
+    if &header[..4] != b"PK\x03\x04" {
+        return Err(corrupt());
+    }
+    let name_len = u16::from_le_bytes([header[26], header[27]]) as usize;
+    let extra_len = u16::from_le_bytes([header[28], header[29]]) as usize;
+    let start = local + 30 + name_len + extra_len;
+    let body = usize::try_from(entry.compressed_size)
+        .ok()
+        .and_then(|len| start.checked_add(len))
+        .and_then(|end| data.get(start..end))
+        .ok_or_else(corrupt)?;
+
+    match entry.method {
+        0 => body.chunks(OUTPUT_CHUNK).for_each(sink),
+        8 => {
+            let deflate_error = |e: io::Error| FilterError::Archive(format!("deflate: {}", e));
+            let mut decoder = DeflateDecoder::new(Vec::with_capacity(OUTPUT_CHUNK));
+            inflate_in_steps(&mut decoder, body, |decoder| {
+                if !decoder.get_ref().is_empty() {
+                    sink(decoder.get_ref());
+                    decoder.get_mut().clear();
+                }
+            })
+            .map_err(deflate_error)?;
+            let rest = decoder.finish().map_err(deflate_error)?;
+            rest.chunks(OUTPUT_CHUNK).for_each(sink);
+        }
+        m => return Err(FilterError::Archive(format!("Unsupported zip compression method {} (`{}`)", m, entry.member.name))),
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::write::{DeflateEncoder, GzEncoder};
+    use flate2::Compression;
+
+    /// (이름, 내용, deflate 여부) 로 zip 을 만듭니다. 이름이 `/` 로 끝나면 디렉터리
+    fn zip(files: &[(&str, &[u8], bool)]) -> Vec<u8> {
+        let mut out = Vec::new();
+        let mut directory = Vec::new();
+        for &(name, content, deflate) in files {
+            let body = if deflate {
+                let mut e = DeflateEncoder::new(Vec::new(), Compression::default());
+                e.write_all(content).unwrap();
+                e.finish().unwrap()
+            } else {
+                content.to_vec()
+            };
+            let method: u16 = if deflate { 8 } else { 0 };
+            let offset = out.len() as u32;
+            out.extend_from_slice(b"PK\x03\x04");
+            out.extend_from_slice(&[20, 0, 0, 0]);
+            out.extend_from_slice(&method.to_le_bytes());
+            out.extend_from_slice(&[0; 8]);
+            out.extend_from_slice(&(body.len() as u32).to_le_bytes());
+            out.extend_from_slice(&(content.len() as u32).to_le_bytes());
+            out.extend_from_slice(&(name.len() as u16).to_le_bytes());
+            out.extend_from_slice(&[0, 0]);
+            out.extend_from_slice(name.as_bytes());
+            out.extend_from_slice(&body);
+
+            directory.extend_from_slice(b"PK\x01\x02");
+            directory.extend_from_slice(&[20, 0, 20, 0, 0, 0]);
+            directory.extend_from_slice(&method.to_le_bytes());
+            directory.extend_from_slice(&[0; 8]);
+            directory.extend_from_slice(&(body.len() as u32).to_le_bytes());
+            directory.extend_from_slice(&(content.len() as u32).to_le_bytes());
+            directory.extend_from_slice(&(name.len() as u16).to_le_bytes());
+            directory.extend_from_slice(&[0; 12]);
+            directory.extend_from_slice(&offset.to_le_bytes());
+            directory.extend_from_slice(name.as_bytes());
+        }
+        let cd_offset = out.len() as u32;
+        out.extend_from_slice(&directory);
+        out.extend_from_slice(b"PK\x05\x06");
+        out.extend_from_slice(&[0; 4]);
+        out.extend_from_slice(&(files.len() as u16).to_le_bytes());
+        out.extend_from_slice(&(files.len() as u16).to_le_bytes());
+        out.extend_from_slice(&(directory.len() as u32).to_le_bytes());
+        out.extend_from_slice(&cd_offset.to_le_bytes());
+        out.extend_from_slice(&[0, 0]);
+        out
+    }
+
+    fn tar(files: &[(&str, &[u8])]) -> Vec<u8> {
+        let mut out = Vec::new();
+        for &(name, content) in files {
+            let mut header = [0u8; TAR_BLOCK];
+            header[..name.len()].copy_from_slice(name.as_bytes());
+            header[124..135].copy_from_slice(format!("{:011o}", content.len()).as_bytes());
+            header[156] = b'0';
+            header[257..263].copy_from_slice(b"ustar\0");
+            header[148..156].fill(b' ');
+            let sum: u32 = header.iter().map(|&b| b as u32).sum();
+            header[148..155].copy_from_slice(format!("{:06o}\0", sum).as_bytes());
+            out.extend_from_slice(&header);
+            out.extend_from_slice(content);
+            out.resize(out.len().div_ceil(TAR_BLOCK) * TAR_BLOCK, 0);
+        }
+        out.resize(out.len() + 2 * TAR_BLOCK, 0);
+        out
+    }
+
+    fn gzip(data: &[u8]) -> Vec<u8> {
+        let mut e = GzEncoder::new(Vec::new(), Compression::default());
+        e.write_all(data).unwrap();
+        e.finish().unwrap()
+    }
+
+    fn extracted(data: &[u8], member: usize) -> Result<Vec<u8>, FilterError> {
+        let mut out = Vec::new();
+        extract(data, member, &mut |chunk| out.extend_from_slice(chunk))?;
+        Ok(out)
+    }
+
+    #[test]
+    fn zip_members_are_listed_and_extracted() {
+        let data = zip(&[("logs/", b"", false), ("logs/a.log", b"stored line\n", false), ("b.log", b"deflated line\n", true)]);
+        assert_eq!(detect(&data), ArchiveKind::Zip);
+        let names: Vec<String> = list_members(&data).unwrap().into_iter().map(|m| m.name).collect();
+        assert_eq!(names, ["logs/a.log", "b.log"]);
+        assert_eq!(extracted(&data, 0).unwrap(), b"stored line\n");
+        assert_eq!(extracted(&data, 1).unwrap(), b"deflated line\n");
+        assert!(extracted(&data, 2).is_err());
+    }
+
+    #[test]
+    fn damaged_zip_is_an_error_not_a_panic() {
+        let data = zip(&[("a.log", b"hello\n", true)]);
+        // 로컬 헤더가 잘림
+        let cd = data.len() - 22 - (46 + 5);
+        for cut in [4, 20, 29] {
+            let mut damaged = data[..cut].to_vec();
+            damaged.extend_from_slice(&data[cd..]);
+            let shift = (data.len() - damaged.len()) as u32;
+            let at = damaged.len() - 6;
+            let offset = u32::from_le_bytes(damaged[at..at + 4].try_into().unwrap()) - shift;
+            damaged[at..at + 4].copy_from_slice(&offset.to_le_bytes());
+            assert!(extracted(&damaged, 0).is_err(), "cut at {}", cut);
+        }
+        // 헤더 오프셋 / 크기가 파일 밖
+        let mut far = data.clone();
+        far[cd + 42..cd + 46].copy_from_slice(&u32::MAX.to_le_bytes());
+        assert!(extracted(&far, 0).is_err());
+        // 매직만 있고 30바이트 헤더가 잘린 로컬 헤더
+        let mut short = data.clone();
+        short[cd + 42..cd + 46].copy_from_slice(&(data.len() as u32).to_le_bytes());
+        short.extend_from_slice(b"PK\x03\x04");
+        assert!(extracted(&short, 0).is_err());
+        let mut huge = data.clone();
+        huge[cd + 20..cd + 24].copy_from_slice(&0xFFFF_FFF0u32.to_le_bytes());
+        assert!(extracted(&huge, 0).is_err());
+    }
+
+    #[test]
+    fn zip_cannot_be_streamed() {
+        assert!(ArchiveDecoder::new(ArchiveKind::Zip, Some(0)).is_err());
+    }
+
+    #[test]
+    fn tar_gz_streams_the_chosen_member_in_any_chunking() {
+        let data = gzip(&tar(&[("a.log", b"first\n"), ("b.log", &[b'x'; 700])]));
+        assert_eq!(detect(&data), ArchiveKind::TarGz);
+        let mut decoder = ArchiveDecoder::new(ArchiveKind::TarGz, Some(1)).unwrap();
+        let mut out = Vec::new();
+        for chunk in data.chunks(7) {
+            decoder.push(chunk, &mut |m, bytes| {
+                assert_eq!(m.name, "b.log");
+                out.extend_from_slice(bytes);
+            }).unwrap();
+        }
+        decoder.finish(&mut |_, bytes| out.extend_from_slice(bytes)).unwrap();
+        assert_eq!(out, [b'x'; 700]);
+        let sizes: Vec<u64> = decoder.members().iter().map(|m| m.size).collect();
+        assert_eq!(sizes, [6, 700]);
+        assert!(extracted(&data, 2).is_err());
+    }
+
+    #[test]
+    fn inflated_output_comes_out_in_bounded_chunks() {
+        let log: Vec<u8> = b"01-01 10:00:00.000 I/Tag: the same line again\n".repeat(100_000);
+        let check = |label: &str, data: &[u8]| {
+            let mut total = 0;
+            extract(data, 0, &mut |chunk| {
+                assert!(chunk.len() <= OUTPUT_CHUNK, "{}: {}", label, chunk.len());
+                total += chunk.len();
+            })
+            .unwrap();
+            assert_eq!(total, log.len(), "{}", label);
+        };
+        check("gzip", &gzip(&log));
+        check("tar.gz", &gzip(&tar(&[("big.log", &log)])));
+        check("zip deflate", &zip(&[("big.log", &log, true)]));
+        check("zip stored", &zip(&[("big.log", &log, false)]));
+        check("tar", &tar(&[("big.log", &log)]));
+        check("plain", &log);
+    }
+
+    #[test]
+    fn gzip_and_plain_are_single_members() {
+        let data = gzip(b"one\ntwo\n");
+        assert_eq!(detect(&data), ArchiveKind::Gzip);
+        assert_eq!(extracted(&data, 0).unwrap(), b"one\ntwo\n");
+        assert_eq!(list_members(&data).unwrap()[0].size, 8);
+        assert!(extracted(&data[..data.len() - 4], 0).is_err());
+        assert_eq!(detect(b"plain text"), ArchiveKind::Plain);
+        assert_eq!(extracted(b"plain text", 0).unwrap(), b"plain text");
+    }
+}
@@ -0,0 +1,436 @@
+use aho_corasick::{AhoCorasick, MatchKind};
+use regex::bytes::{Regex, RegexSet, RegexSetBuilder};
+use regex_automata::dfa::{dense, regex as dfa_regex, Automaton, OverlappingState, StartKind};
+use regex_automata::nfa::thompson;
+use regex_automata::util::syntax;
+use regex_automata::Input;
+
+use crate::error::FilterError;
+use crate::term::{build_literals, compile_regex, REGEX_SIZE_LIMIT};
+
+/// 블롭에 싣는 dense DFA 하나의 최대 크기. 넘으면 그 항목만 받는 쪽에서 다시 빌드합니다.
+const DFA_SIZE_LIMIT: usize = 4 << 20;
+
+/// 블롭 항목 종류: 받는 쪽에서 원래 방식(aho-corasick / regex)으로 다시 빌드
+/// (DFA 로 만들 수 없는 패턴: 유니코드 `\b`, 크기 제한 초과 등)
+const ENTRY_REBUILD: u8 = 0;
+/// 블롭 항목 종류: 직렬화된 dense DFA 하나 (리터럴 집합, `is_match` 용 정규식)
+const ENTRY_DFA: u8 = 1;
+/// 블롭 항목 종류: 정방향 + 역방향 dense DFA (구간이 필요한 하이라이트 정규식)
+const ENTRY_DFA_PAIR: u8 = 2;
+
+type Dfa = dense::DFA<Vec<u32>>;
+
+/// 리터럴 단어 집합. 평소에는 Aho-Corasick, 블롭에서 읽은 룰은 역직렬화한 DFA 입니다.
+pub enum LiteralSet {
+    Ac(AhoCorasick),
+    /// `MatchKind::All` DFA (패턴 id = 단어 id). 리터럴은 길이가 고정이라 시작 위치는 끝 - 길이
+    Dfa { dfa: Box<Dfa>, lens: Vec<usize> },
+}
+
+impl LiteralSet {
+    pub fn is_match(&self, haystack: &[u8]) -> bool {
+        match self {
+            LiteralSet::Ac(ac) => ac.is_match(haystack),
+            LiteralSet::Dfa { dfa, .. } => dfa_is_match(dfa, haystack),
+        }
+    }
+
+    /// 겹치는 것까지 모든 출현을 끝 위치 순으로 `f(단어 id, 시작, 끝)` 에 넘깁니다.
+    /// `f` 가 true 를 돌려주면 멈추고 true. (`Ac` 는 `MatchKind::Standard` 로 빌드된 경우만)
+    pub fn for_each_overlapping(&self, haystack: &[u8], mut f: impl FnMut(usize, usize, usize) -> bool) -> bool {
+        match self {
+            LiteralSet::Ac(ac) => ac.find_overlapping_iter(haystack).any(|m| f(m.pattern().as_usize(), m.start(), m.end())),
+            LiteralSet::Dfa { dfa, lens } => {
+                let input = Input::new(haystack);
+                let mut state = OverlappingState::start();
+                loop {
+                    dfa.try_search_overlapping_fwd(&input, &mut state).expect("DFA without quit bytes");
+                    let Some(m) = state.get_match() else { return false };
+                    let id = m.pattern().as_usize();
+                    if f(id, m.offset() - lens[id], m.offset()) {
+                        return true;
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// 적중 여부만 필요한 정규식 (Happy Combo / Block List 단어, 라인 형태)
+pub enum RegexMatcher {
+    Regex(Regex),
+    Set(RegexSet),
+    Dfa(Box<Dfa>),
+}
+
+impl RegexMatcher {
+    pub fn is_match(&self, haystack: &[u8]) -> bool {
+        match self {
+            RegexMatcher::Regex(re) => re.is_match(haystack),
+            RegexMatcher::Set(set) => set.is_match(haystack),
+            RegexMatcher::Dfa(dfa) => dfa_is_match(dfa, haystack),
+        }
+    }
+}
+
+/// 구간이 필요한 정규식 (하이라이트)
+pub enum SpanRegex {
+    Regex(Regex),
+    Dfa(Box<dfa_regex::Regex<Dfa>>),
+}
+
+impl SpanRegex {
+    /// 겹치지 않는 매치를 앞에서부터 `f(시작, 끝)` 에 넘깁니다.
+    pub fn for_each_match(&self, haystack: &[u8], mut f: impl FnMut(usize, usize)) {
+        match self {
+            SpanRegex::Regex(re) => re.find_iter(haystack).for_each(|m| f(m.start(), m.end())),
+            SpanRegex::Dfa(re) => re.find_iter(haystack).for_each(|m| f(m.start(), m.end())),
+        }
+    }
+}
+
+fn dfa_is_match(dfa: &Dfa, haystack: &[u8]) -> bool {
+    dfa.try_search_fwd(&Input::new(haystack).earliest(true)).expect("DFA without quit bytes").is_some()
+}
+
+enum Mode<'a> {
+    Compile,
+    Export(Vec<u8>),
+    Load(&'a [u8]),
+}
+
+/// 룰 컴파일 때 오토마톤을 어디서 얻을지
+///
+/// - `compile`: 평소처럼 aho-corasick / regex 로 빌드
+/// - `export`: dense DFA 로 빌드하면서 직렬화한 바이트를 모읍니다. (`blob::encode_rule`)
+/// - `load`: `export` 가 모은 바이트에서 같은 순서로 DFA 를 꺼냅니다. (`blob::decode_rule`, 결정화 없음)
+///
+/// 룰 컴파일은 같은 `RuleSpec` 이면 항상 같은 순서로 오토마톤을 요청하므로 항목에 이름은 없습니다.
+pub struct Automata<'a> {
+    mode: Mode<'a>,
+}
+
+impl<'a> Automata<'a> {
+    pub fn compile() -> Self {
+        Automata { mode: Mode::Compile }
+    }
+
+    pub fn export() -> Self {
+        Automata { mode: Mode::Export(Vec::new()) }
+    }
+
+    pub fn load(entries: &'a [u8]) -> Self {
+        Automata { mode: Mode::Load(entries) }
+    }
+
+    /// `export` 로 모은 항목 바이트
+    pub fn into_entries(self) -> Vec<u8> {
+        match self.mode {
+            Mode::Export(out) => out,
+            _ => Vec::new(),
+        }
+    }
+
+    /// `load` 에서 남은 항목이 있으면 블롭이 룰과 맞지 않는 것
+    pub fn finish(&self) -> Result<(), FilterError> {
+        match self.mode {
+            Mode::Load(rest) if !rest.is_empty() => Err(corrupt("trailing automata")),
+            _ => Ok(()),
+        }
+    }
+
+    /// 리터럴 단어 집합 (`term::build_literals` 와 같은 의미: 대소문자 무시는 ASCII 만)
+    pub fn literals(&mut self, literals: &[String], kind: MatchKind, case_sensitive: bool) -> Result<Option<LiteralSet>, FilterError> {
+        if literals.is_empty() {
+            return Ok(None);
+        }
+        let lens = || literals.iter().map(String::len).collect();
+        let rebuild = || Ok(build_literals(literals, kind, case_sensitive)?.map(LiteralSet::Ac));
+        match &mut self.mode {
+            Mode::Compile => rebuild(),
+            Mode::Export(out) => {
+                let patterns: Vec<String> = literals.iter().map(|s| literal_pattern(s, case_sensitive)).collect();
+                let built = dense::Builder::new()
+                    .configure(dfa_config().match_kind(regex_automata::MatchKind::All))
+                    .syntax(syntax::Config::new().utf8(false))
+                    .thompson(thompson_config())
+                    .build_many(&patterns);
+                match built {
+                    Ok(dfa) => {
+                        write_dfas(out, &[&dfa]);
+                        Ok(Some(LiteralSet::Dfa { dfa: Box::new(dfa), lens: lens() }))
+                    }
+                    Err(_) => {
+                        out.push(ENTRY_REBUILD);
+                        rebuild()
+                    }
+                }
+            }
+            Mode::Load(rest) => match read_dfas(rest, literals.len())? {
+                Some([dfa]) => Ok(Some(LiteralSet::Dfa { dfa: Box::new(dfa), lens: lens() })),
+                None => rebuild(),
+            },
+        }
+    }
+
+    /// `term::compile_regex` 와 같은 플래그의 정규식 (적중 여부만)
+    pub fn regex(&mut self, pattern: &str, case_sensitive: bool) -> Result<RegexMatcher, FilterError> {
+        match &mut self.mode {
+            Mode::Compile => Ok(RegexMatcher::Regex(compile_regex(pattern, case_sensitive)?)),
+            Mode::Export(out) => {
+                // 에러 메시지는 평소 컴파일과 같아야 하므로 먼저 검사
+                let re = compile_regex(pattern, case_sensitive)?;
+                match regex_dfa_builder(case_sensitive).build(pattern) {
+                    Ok(dfa) => {
+                        write_dfas(out, &[&dfa]);
+                        Ok(RegexMatcher::Dfa(Box::new(dfa)))
+                    }
+                    Err(_) => {
+                        out.push(ENTRY_REBUILD);
+                        Ok(RegexMatcher::Regex(re))
+                    }
+                }
+            }
+            Mode::Load(rest) => match read_dfas(rest, 1)? {
+                Some([dfa]) => Ok(RegexMatcher::Dfa(Box::new(dfa))),
+                None => Ok(RegexMatcher::Regex(compile_regex(pattern, case_sensitive)?)),
+            },
+        }
+    }
+
+    /// 대소문자 구분 정규식 여러 개 중 하나라도 맞는지 (라인 형태 판별)
+    pub fn regex_set(&mut self, patterns: &[&str]) -> Result<RegexMatcher, FilterError> {
+        let rebuild = || {
+            // 어느 패턴이 잘못됐는지 알려주기 위해 하나씩 먼저 검사
+            for p in patterns {
+                compile_regex(p, true)?;
+            }
+            RegexSetBuilder::new(patterns)
+                .build()
+                .map(RegexMatcher::Set)
+                .map_err(|e| FilterError::Regex { pattern: patterns.join(" | "), message: e.to_string() })
+        };
+        match &mut self.mode {
+            Mode::Compile => rebuild(),
+            Mode::Export(out) => {
+                let set = rebuild()?;
+                match regex_dfa_builder(true).build_many(patterns) {
+                    Ok(dfa) => {
+                        write_dfas(out, &[&dfa]);
+                        Ok(RegexMatcher::Dfa(Box::new(dfa)))
+                    }
+                    Err(_) => {
+                        out.push(ENTRY_REBUILD);
+                        Ok(set)
+                    }
+                }
+            }
+            Mode::Load(rest) => match read_dfas(rest, patterns.len())? {
+                Some([dfa]) => Ok(RegexMatcher::Dfa(Box::new(dfa))),
+                None => rebuild(),
+            },
+        }
+    }
+
+    /// 매치 구간까지 필요한 정규식 (정방향 + 역방향 DFA)
+    pub fn span_regex(&mut self, pattern: &str, case_sensitive: bool) -> Result<SpanRegex, FilterError> {
+        match &mut self.mode {
+            Mode::Compile => Ok(SpanRegex::Regex(compile_regex(pattern, case_sensitive)?)),
+            Mode::Export(out) => {
+                let re = compile_regex(pattern, case_sensitive)?;
+                let built = dfa_regex::Builder::new()
+                    .dense(dfa_config())
+                    .syntax(regex_syntax_config(case_sensitive))
+                    .thompson(thompson_config())
+                    .build(pattern);
+                match built {
+                    Ok(dfa) => {
+                        write_dfas(out, &[dfa.forward(), dfa.reverse()]);
+                        Ok(SpanRegex::Dfa(Box::new(dfa)))
+                    }
+                    Err(_) => {
+                        out.push(ENTRY_REBUILD);
+                        Ok(SpanRegex::Regex(re))
+                    }
+                }
+            }
+            Mode::Load(rest) => match read_dfas(rest, 1)? {
+                Some([forward, reverse]) => Ok(SpanRegex::Dfa(Box::new(dfa_regex::Builder::new().build_from_dfas(forward, reverse)))),
+                None => Ok(SpanRegex::Regex(compile_regex(pattern, case_sensitive)?)),
+            },
+        }
+    }
+}
+
+fn dfa_config() -> dense::Config {
+    dense::Config::new()
+        .start_kind(StartKind::Unanchored)
+        .dfa_size_limit(Some(DFA_SIZE_LIMIT))
+        .determinize_size_limit(Some(DFA_SIZE_LIMIT))
+}
+
+/// regex::bytes 와 같은 설정: 유니코드 클래스는 켜고, 라인은 UTF-8 이 아니어도 됨
+fn thompson_config() -> thompson::Config {
+    thompson::Config::new().utf8(false).nfa_size_limit(Some(REGEX_SIZE_LIMIT))
+}
+
+fn regex_syntax_config(case_sensitive: bool) -> syntax::Config {
+    syntax::Config::new().case_insensitive(!case_sensitive).utf8(false)
+}
+
+fn regex_dfa_builder(case_sensitive: bool) -> dense::Builder {
+    let mut builder = dense::Builder::new();
+    builder.configure(dfa_config()).syntax(regex_syntax_config(case_sensitive)).thompson(thompson_config());
+    builder
+}
+
+/// aho-corasick 의 `ascii_case_insensitive` 와 같은 의미의 패턴: 바이트 그대로, 대소문자는 ASCII 만 접음
+fn literal_pattern(literal: &str, case_sensitive: bool) -> String {
+    let mut pattern = String::from(if case_sensitive { "(?-u:" } else { "(?i-u:" });
+    for &b in literal.as_bytes() {
+        if b.is_ascii_alphanumeric() {
+            pattern.push(b as char);
+        } else {
+            pattern.push_str(&format!(r"\x{:02X}", b));
+        }
+    }
+    pattern.push(')');
+    pattern
+}
+
+fn corrupt(what: &str) -> FilterError {
+    FilterError::Blob(format!("Corrupt rule blob: {}", what))
+}
+
+/// 항목 하나: 종류 1바이트 + (u32 길이 + DFA 바이트) * N. DFA 바이트는 앞쪽 정렬 패딩을 뺀 리틀 엔디언 형식
+fn write_dfas(out: &mut Vec<u8>, dfas: &[&Dfa]) {
+    out.push(if dfas.len() == 2 { ENTRY_DFA_PAIR } else { ENTRY_DFA });
+    for dfa in dfas {
+        let (bytes, padding) = dfa.to_bytes_little_endian();
+        let bytes = &bytes[padding..];
+        out.extend_from_slice(&(bytes.len() as u32).to_le_bytes());
+        out.extend_from_slice(bytes);
+    }
+}
+
+/// 항목 하나를 읽습니다. 다시 빌드해야 하는 항목이면 `None`
+///
+/// `N` 은 기대하는 DFA 개수(종류), `patterns` 는 기대하는 패턴 수 (다른 룰의 블롭이면 에러)
+fn read_dfas<const N: usize>(rest: &mut &[u8], patterns: usize) -> Result<Option<[Dfa; N]>, FilterError> {
+    let (&kind, tail) = rest.split_first().ok_or_else(|| corrupt("missing automaton"))?;
+    *rest = tail;
+    let expected = if N == 2 { ENTRY_DFA_PAIR } else { ENTRY_DFA };
+    if kind == ENTRY_REBUILD {
+        return Ok(None);
+    }
+    if kind != expected {
+        return Err(corrupt("unexpected automaton kind"));
+    }
+    let mut dfas = Vec::with_capacity(N);
+    for _ in 0..N {
+        let len = rest.get(..4).ok_or_else(|| corrupt("truncated automaton"))?;
+        let len = u32::from_le_bytes([len[0], len[1], len[2], len[3]]) as usize;
+        let bytes = rest.get(4..4 + len).ok_or_else(|| corrupt("truncated automaton"))?;
+        *rest = &rest[4 + len..];
+        let dfa = deserialize(bytes)?;
+        if dfa.pattern_len() != patterns {
+            return Err(corrupt("automaton does not match the rule"));
+        }
+        dfas.push(dfa);
+    }
+    Ok(Some(dfas.try_into().unwrap_or_else(|_| unreachable!())))
+}
+
+/// `DFA::from_bytes` 는 4바이트 정렬된 슬라이스가 필요하므로 정렬된 위치에 복사한 뒤 검증/소유 복사합니다.
+fn deserialize(bytes: &[u8]) -> Result<Dfa, FilterError> {
+    let mut buf = vec![0u8; bytes.len() + 3];
+    let skip = (4 - buf.as_ptr() as usize % 4) % 4;
+    buf[skip..skip + bytes.len()].copy_from_slice(bytes);
+    let (dfa, _) = dense::DFA::from_bytes(&buf[skip..skip + bytes.len()])
+        .map_err(|e| FilterError::Blob(format!("Incompatible automaton in rule blob: {}", e)))?;
+    Ok(dfa.to_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn strings(items: &[&str]) -> Vec<String> {
+        items.iter().map(|s| s.to_string()).collect()
+    }
+
+    fn overlapping(set: &LiteralSet, haystack: &str) -> Vec<(usize, usize, usize)> {
+        let mut out = Vec::new();
+        set.for_each_overlapping(haystack.as_bytes(), |id, start, end| {
+            out.push((id, start, end));
+            false
+        });
+        out.sort_unstable();
+        out
+    }
+
+    #[test]
+    fn literal_dfa_reports_the_same_matches_as_aho_corasick() {
+        let literals = strings(&["fail", "ail", "a.b", "연결", "x"]);
+        for case_sensitive in [true, false] {
+            let mut export = Automata::export();
+            let dfa = export.literals(&literals, MatchKind::Standard, case_sensitive).unwrap().unwrap();
+            let entries = export.into_entries();
+            let mut load = Automata::load(&entries);
+            let loaded = load.literals(&literals, MatchKind::Standard, case_sensitive).unwrap().unwrap();
+            load.finish().unwrap();
+            assert!(matches!(loaded, LiteralSet::Dfa { .. }));
+
+            let ac = Automata::compile().literals(&literals, MatchKind::Standard, case_sensitive).unwrap().unwrap();
+            for haystack in ["FAIL fail a.b aXb", "연결 실패 X", "nothing"] {
+                assert_eq!(overlapping(&loaded, haystack), overlapping(&ac, haystack), "{}", haystack);
+                assert_eq!(overlapping(&dfa, haystack), overlapping(&ac, haystack), "{}", haystack);
+                assert_eq!(loaded.is_match(haystack.as_bytes()), ac.is_match(haystack.as_bytes()));
+            }
+        }
+    }
+
+    #[test]
+    fn regexes_load_without_rebuilding() {
+        let mut export = Automata::export();
+        export.regex(r"pid=\d+", false).unwrap();
+        export.span_regex(r"[a-z]+\d", true).unwrap();
+        export.regex_set(&["^a", "b$"]).unwrap();
+        let entries = export.into_entries();
+
+        let mut load = Automata::load(&entries);
+        let re = load.regex(r"pid=\d+", false).unwrap();
+        let span = load.span_regex(r"[a-z]+\d", true).unwrap();
+        let set = load.regex_set(&["^a", "b$"]).unwrap();
+        load.finish().unwrap();
+        assert!(matches!(re, RegexMatcher::Dfa(_)) && matches!(span, SpanRegex::Dfa(_)) && matches!(set, RegexMatcher::Dfa(_)));
+
+        assert!(re.is_match(b"PID=42"));
+        assert!(!re.is_match(b"pid="));
+        let mut spans = Vec::new();
+        span.for_each_match(b"ab1 c2 3", |s, e| spans.push((s, e)));
+        assert_eq!(spans, [(0, 3), (4, 6)]);
+        assert!(set.is_match(b"xb") && !set.is_match(b"ba"));
+    }
+
+    #[test]
+    fn unsupported_patterns_fall_back_to_rebuilding() {
+        // 유니코드 단어 경계는 dense DFA 로 만들 수 없음
+        let mut export = Automata::export();
+        export.regex(r"\bwifi\b", true).unwrap();
+        let entries = export.into_entries();
+        assert_eq!(entries, [ENTRY_REBUILD]);
+
+        let re = Automata::load(&entries).regex(r"\bwifi\b", true).unwrap();
+        assert!(matches!(re, RegexMatcher::Regex(_)));
+        assert!(re.is_match("é wifi".as_bytes()));
+        assert!(!re.is_match("éwifi".as_bytes()));
+    }
+
+    #[test]
+    fn export_reports_invalid_regexes_like_compile() {
+        let err = Automata::export().regex("(", true).err().expect("invalid regex");
+        assert!(matches!(err, FilterError::Regex { .. }));
+    }
+}
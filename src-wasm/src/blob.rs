@@ -0,0 +1,141 @@
+use crate::automata::Automata;
+use crate::error::FilterError;
+use crate::rule::{CompiledRule, RuleSpec};
+
+/// 블롭 매직 (`HFLT`)
+const MAGIC: &[u8; 4] = b"HFLT";
+
+/// 호환되지 않는 변경이 생기면 올립니다. (필드 추가는 serde default 로 흡수되므로 올릴 필요 없음)
+///
+/// 2: 룰 본문 뒤에 컴파일된 오토마톤(dense DFA) 을 싣습니다. 항목 배치는 `automata` 모듈이 정하고,
+/// DFA 바이트 자체의 형식/엔디언은 regex-automata 가 읽을 때 다시 검사합니다.
+pub const BLOB_VERSION: u16 = 2;
+
+const HEADER_LEN: usize = MAGIC.len() + 2 + 4;
+
+/// 룰을 워커 간 전달용 바이트 블롭으로 직렬화
+///
+/// `MAGIC` + 버전 + 본문 길이(u32) + MessagePack 본문 + 오토마톤 항목들.
+/// 리터럴 집합과 정규식은 dense DFA 로 결정화해서 실으므로 받는 쪽은 다시 빌드하지 않고 검증/복사만 합니다.
+/// (DFA 로 만들 수 없거나 너무 큰 패턴만 받는 쪽에서 원래 방식으로 다시 빌드)
+pub fn encode_rule(spec: &RuleSpec) -> Result<Vec<u8>, FilterError> {
+    let mut automata = Automata::export();
+    CompiledRule::compile_with(spec, &mut automata)?;
+
+    let mut out = Vec::with_capacity(256);
+    out.extend_from_slice(MAGIC);
+    out.extend_from_slice(&BLOB_VERSION.to_le_bytes());
+    out.extend_from_slice(&[0; 4]);
+    rmp_serde::encode::write_named(&mut out, spec).expect("writing to Vec cannot fail");
+    let spec_len = (out.len() - HEADER_LEN) as u32;
+    out[HEADER_LEN - 4..HEADER_LEN].copy_from_slice(&spec_len.to_le_bytes());
+    out.extend_from_slice(&automata.into_entries());
+    Ok(out)
+}
+
+/// 헤더(매직/버전)를 확인하고 룰과 컴파일된 룰을 복원합니다. 버전이 다르면 거부합니다.
+pub fn decode_rule(blob: &[u8]) -> Result<(RuleSpec, CompiledRule), FilterError> {
+    if blob.len() < HEADER_LEN || &blob[..MAGIC.len()] != MAGIC {
+        return Err(FilterError::Blob("Not a happy-filter rule blob".to_string()));
+    }
+    let version = u16::from_le_bytes([blob[4], blob[5]]);
+    if version != BLOB_VERSION {
+        return Err(FilterError::Blob(format!(
+            "Incompatible rule blob version {} (expected {})",
+            version, BLOB_VERSION
+        )));
+    }
+    let spec_len = u32::from_le_bytes([blob[6], blob[7], blob[8], blob[9]]) as usize;
+    let body = HEADER_LEN.checked_add(spec_len)
+        .and_then(|end| blob.get(HEADER_LEN..end))
+        .ok_or_else(|| FilterError::Blob("Corrupt rule blob: truncated rule".to_string()))?;
+    let spec: RuleSpec = rmp_serde::from_slice(body).map_err(|e| FilterError::Blob(format!("Corrupt rule blob: {}", e)))?;
+
+    let mut automata = Automata::load(&blob[HEADER_LEN + spec_len..]);
+    let rule = CompiledRule::compile_with(&spec, &mut automata)?;
+    automata.finish()?;
+    Ok((spec, rule))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::combo::ComboScratch;
+    use crate::highlight::HighlightSpec;
+
+    fn strings(items: &[&str]) -> Vec<String> {
+        items.iter().map(|s| s.to_string()).collect()
+    }
+
+    fn spec() -> RuleSpec {
+        RuleSpec {
+            include_groups: vec![
+                strings(&["wifi", "fail", "not:retry"]),
+                strings(&[r"re:pid=\d+", "field:level>=E"]),
+                strings(&["Ärger"]),
+            ],
+            excludes: strings(&["Noise", r"re:^\s*$"]),
+            highlights: ["ai", "fail", r"re:\d+", r"re:\bwifi\b"]
+                .iter()
+                .map(|k| HighlightSpec { keyword: k.to_string() })
+                .collect(),
+            bypass_shell_filter: true,
+            ..RuleSpec::default()
+        }
+    }
+
+    const LINES: [&str; 9] = [
+        "01-01 10:00:00.000 I/Net: WIFI connect FAIL",
+        "01-01 10:00:00.000 I/Net: wifi fail, retry",
+        "01-01 10:00:00.000 E/Net: pid=42 crashed",
+        "01-01 10:00:00.000 I/Net: pid=42 ok",
+        "01-01 10:00:00.000 W/Net: grosser ÄRGER",
+        "01-01 10:00:00.000 I/Net: noise wifi fail",
+        "$ shell output",
+        "   ",
+        "01-01 10:00:00.000 I/Net: nothing here",
+    ];
+
+    #[test]
+    fn decoded_rule_matches_like_the_original() {
+        let spec = spec();
+        let mut original = CompiledRule::compile(&spec).unwrap();
+        let (decoded_spec, mut decoded) = decode_rule(&encode_rule(&spec).unwrap()).unwrap();
+        assert_eq!(decoded_spec.include_groups, spec.include_groups);
+
+        let mut scratch = ComboScratch::default();
+        let (mut a, mut b) = (Vec::new(), Vec::new());
+        for line in LINES {
+            let line = line.as_bytes();
+            assert_eq!(original.is_match(line, &mut scratch), decoded.is_match(line, &mut scratch), "{:?}", line);
+            original.highlights().unwrap().find_spans(line, &mut a);
+            decoded.highlights().unwrap().find_spans(line, &mut b);
+            assert_eq!(a, b);
+        }
+        let matched: Vec<bool> = LINES.iter().map(|l| decoded.is_match(l.as_bytes(), &mut scratch)).collect();
+        assert_eq!(matched, [true, false, true, false, true, false, true, true, false]);
+    }
+
+    #[test]
+    fn rejects_foreign_and_damaged_blobs() {
+        let blob = encode_rule(&spec()).unwrap();
+        assert!(decode_rule(b"nope").is_err());
+
+        let mut old = blob.clone();
+        old[4..6].copy_from_slice(&1u16.to_le_bytes());
+        assert!(decode_rule(&old).err().expect("old version").to_string().contains("version 1"));
+
+        assert!(decode_rule(&blob[..blob.len() - 3]).is_err());
+        let mut trailing = blob.clone();
+        trailing.push(0);
+        assert!(decode_rule(&trailing).is_err());
+
+        // 다른 룰의 오토마톤은 패턴 수가 달라 거부
+        let other = encode_rule(&RuleSpec { include_groups: vec![strings(&["a", "b"])], ..RuleSpec::default() }).unwrap();
+        let mine = encode_rule(&RuleSpec { include_groups: vec![strings(&["a"])], ..RuleSpec::default() }).unwrap();
+        let spec_end = |b: &[u8]| HEADER_LEN + u32::from_le_bytes([b[6], b[7], b[8], b[9]]) as usize;
+        let mut mixed = mine[..spec_end(&mine)].to_vec();
+        mixed.extend_from_slice(&other[spec_end(&other)..]);
+        assert!(decode_rule(&mixed).is_err());
+    }
+}
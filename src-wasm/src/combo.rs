@@ -0,0 +1,401 @@
+use aho_corasick::MatchKind;
+use std::collections::HashMap;
+
+use crate::automata::{Automata, LiteralSet, RegexMatcher};
+use crate::error::FilterError;
+use crate::fields::{self, LineFields};
+use crate::predicate::FieldPredicate;
+use crate::stats::{GroupStat, HitStat, TermStat};
+use crate::term::{split_negation, Term, NOT_PREFIX};
+
+/// Happy Combo (OR-of-AND) 평가기
+///
+/// 모든 그룹의 리터럴 단어를 하나의 Aho-Corasick 오토마톤으로 합쳐서 한 번만 스캔하고,
+/// 어떤 단어가 나왔는지 기록한 뒤 그룹 단위(AND)로 판정합니다.
+/// 정규식 단어는 같은 그룹의 리터럴이 모두 나온 경우에만 평가합니다. (리터럴 = 프리필터)
+/// `not:` 단어는 해당 그룹 안에서만 "나오면 안 되는" 단어입니다.
+/// `field:` 단어(레벨/태그/PID ...)는 정규식과 같은 단계에서 평가하며, 라인 헤더는 필요할 때 한 번만 파싱합니다.
+pub struct ComboMatcher {
+    ac: Option<LiteralSet>,
+    /// 리터럴 단어 (id 순서, 대소문자 무시면 소문자) - `RuleSet` 이 여러 룰의 단어를 합칠 때 사용
+    literals: Vec<String>,
+    regexes: Vec<RegexMatcher>,
+    fields: Vec<FieldPredicate>,
+    case_sensitive: bool,
+    groups: Vec<Group>,
+    /// 리터럴 단어 id -> 해당 단어를 (긍정으로) 포함하는 그룹 목록
+    term_groups: Vec<Vec<usize>>,
+    /// 통계용 표시 이름: 리터럴 id 순서 다음에 정규식 id, 필드 조건 id 순서
+    labels: Vec<String>,
+    group_labels: Vec<Vec<String>>,
+}
+
+/// 그룹별 단어 id 목록 (중복 제거됨)
+#[derive(Default)]
+struct Group {
+    literals: Vec<usize>,
+    regexes: Vec<usize>,
+    not_literals: Vec<usize>,
+    not_regexes: Vec<usize>,
+    fields: Vec<usize>,
+    not_fields: Vec<usize>,
+}
+
+impl Group {
+    fn is_empty(&self) -> bool {
+        self.literals.is_empty() && self.is_literal_only()
+    }
+
+    /// 리터럴만으로 판정이 끝나는 그룹 (스캔 도중 조기 종료 가능)
+    fn is_literal_only(&self) -> bool {
+        self.regexes.is_empty()
+            && self.not_literals.is_empty()
+            && self.not_regexes.is_empty()
+            && self.fields.is_empty()
+            && self.not_fields.is_empty()
+    }
+}
+
+/// 라인마다 재사용하는 스크래치 버퍼 (라인당 할당 방지)
+#[derive(Default)]
+pub struct ComboScratch {
+    hits: Vec<bool>,
+    remaining: Vec<usize>,
+    regex_hits: Vec<Option<bool>>,
+    field_hits: Vec<Option<bool>>,
+    /// 라인 헤더 파싱 결과 (필드 조건이 처음 필요할 때 채움)
+    parsed: Option<LineFields>,
+}
+
+impl ComboMatcher {
+    /// 정규화(trim, 빈 단어 제거, 대소문자 처리) 후 빌드합니다.
+    /// 유효한 그룹이 하나도 없으면 `None` (= 필터 없음).
+    pub fn build(groups: &[Vec<String>], case_sensitive: bool, automata: &mut Automata) -> Result<Option<Self>, FilterError> {
+        let mut literals: Vec<String> = Vec::new();
+        let mut regex_patterns: Vec<String> = Vec::new();
+        let mut fields: Vec<FieldPredicate> = Vec::new();
+        let mut ids: HashMap<Term, usize> = HashMap::new();
+        let mut compiled_groups: Vec<Group> = Vec::new();
+        let mut literal_labels: Vec<String> = Vec::new();
+        let mut regex_labels: Vec<String> = Vec::new();
+        let mut field_labels: Vec<String> = Vec::new();
+        let mut group_labels: Vec<Vec<String>> = Vec::new();
+
+        for group in groups {
+            let mut compiled = Group::default();
+            let mut labels: Vec<String> = Vec::new();
+            for raw in group {
+                let (negated, raw) = split_negation(raw);
+                let term = match Term::parse(raw, case_sensitive) {
+                    Some(term) => term,
+                    None => continue,
+                };
+                let id = match ids.get(&term) {
+                    Some(&id) => id,
+                    None => {
+                        let id = match &term {
+                            Term::Literal(s) => {
+                                literals.push(s.clone());
+                                literal_labels.push(raw.trim().to_string());
+                                literals.len() - 1
+                            }
+                            Term::Regex(pattern) => {
+                                regex_patterns.push(pattern.clone());
+                                regex_labels.push(raw.trim().to_string());
+                                regex_patterns.len() - 1
+                            }
+                            Term::Field(text) => {
+                                let predicate = FieldPredicate::parse(text, case_sensitive)
+                                    .map_err(|message| FilterError::Field { term: text.clone(), message })?;
+                                fields.push(predicate);
+                                field_labels.push(raw.trim().to_string());
+                                fields.len() - 1
+                            }
+                        };
+                        ids.insert(term.clone(), id);
+                        id
+                    }
+                };
+                let target = match (term, negated) {
+                    (Term::Literal(_), false) => &mut compiled.literals,
+                    (Term::Regex(_), false) => &mut compiled.regexes,
+                    (Term::Literal(_), true) => &mut compiled.not_literals,
+                    (Term::Regex(_), true) => &mut compiled.not_regexes,
+                    (Term::Field(_), false) => &mut compiled.fields,
+                    (Term::Field(_), true) => &mut compiled.not_fields,
+                };
+                if !target.contains(&id) {
+                    target.push(id);
+                    labels.push(if negated { format!("{}{}", NOT_PREFIX, raw.trim()) } else { raw.trim().to_string() });
+                }
+            }
+            if !compiled.is_empty() {
+                compiled_groups.push(compiled);
+                group_labels.push(labels);
+            }
+        }
+
+        // 오토마톤은 그룹 정리가 끝난 뒤 만듭니다. (블롭에서는 이 순서대로 꺼냄)
+        let regexes = regex_patterns.iter()
+            .map(|pattern| automata.regex(pattern, case_sensitive))
+            .collect::<Result<Vec<_>, _>>()?;
+        if compiled_groups.is_empty() {
+            return Ok(None);
+        }
+
+        let mut term_groups = vec![Vec::new(); literals.len()];
+        for (gi, group) in compiled_groups.iter().enumerate() {
+            for &id in &group.literals {
+                term_groups[id].push(gi);
+            }
+        }
+
+        // 겹치는 단어(예: "fail" / "ail")도 모두 기록해야 하므로 Standard + overlapping 검색
+        let ac = automata.literals(&literals, MatchKind::Standard, case_sensitive)?;
+
+        literal_labels.extend(regex_labels);
+        literal_labels.extend(field_labels);
+        Ok(Some(ComboMatcher {
+            ac,
+            literals,
+            regexes,
+            fields,
+            case_sensitive,
+            groups: compiled_groups,
+            term_groups,
+            labels: literal_labels,
+            group_labels,
+        }))
+    }
+
+    pub fn term_count(&self) -> usize {
+        self.term_groups.len() + self.regexes.len() + self.fields.len()
+    }
+
+    pub fn group_count(&self) -> usize {
+        self.groups.len()
+    }
+
+    pub fn literals(&self) -> &[String] {
+        &self.literals
+    }
+
+    pub fn case_sensitive(&self) -> bool {
+        self.case_sensitive
+    }
+
+    /// 한 번의 스캔으로 단어 적중을 기록하고, 어떤 그룹이든 모든 단어가 나오면 즉시 true
+    pub fn is_match(&self, haystack: &[u8], scratch: &mut ComboScratch) -> bool {
+        self.begin(scratch);
+        if let Some(ac) = &self.ac {
+            if ac.for_each_overlapping(haystack, |id, _, _| self.record_hit(id, scratch)) {
+                return true;
+            }
+        }
+        self.finish(haystack, scratch)
+    }
+
+    /// 라인 하나의 리터럴 적중 기록을 시작합니다. (`record_hit` 전에 호출)
+    pub fn begin(&self, scratch: &mut ComboScratch) {
+        scratch.hits.clear();
+        scratch.hits.resize(self.term_groups.len(), false);
+        scratch.remaining.clear();
+        scratch.remaining.extend(self.groups.iter().map(|g| g.literals.len()));
+    }
+
+    /// 리터럴 id 하나가 나왔다고 기록합니다. 리터럴만으로 판정되는 그룹이 채워지면 true (= 매칭 확정)
+    ///
+    /// 스캔을 밖에서 하는 `RuleSet` 도 이 함수로 적중을 넘깁니다.
+    pub fn record_hit(&self, id: usize, scratch: &mut ComboScratch) -> bool {
+        if scratch.hits[id] {
+            return false;
+        }
+        scratch.hits[id] = true;
+        self.term_groups[id].iter().any(|&gi| {
+            scratch.remaining[gi] -= 1;
+            scratch.remaining[gi] == 0 && self.groups[gi].is_literal_only()
+        })
+    }
+
+    /// 리터럴 스캔이 끝난 뒤: 리터럴이 모두 충족된 그룹만 나머지 조건 평가 (같은 정규식/필드 조건은 라인당 한 번만)
+    pub fn finish(&self, haystack: &[u8], scratch: &mut ComboScratch) -> bool {
+        let ComboScratch { hits, remaining, regex_hits, field_hits, parsed } = scratch;
+        regex_hits.clear();
+        regex_hits.resize(self.regexes.len(), None);
+        field_hits.clear();
+        field_hits.resize(self.fields.len(), None);
+        *parsed = None;
+        for (gi, group) in self.groups.iter().enumerate() {
+            if remaining[gi] != 0 || group.is_literal_only() {
+                continue;
+            }
+            if group.not_literals.iter().any(|&id| hits[id]) {
+                continue;
+            }
+            let mut regex_hit = |ri: usize| *regex_hits[ri].get_or_insert_with(|| self.regexes[ri].is_match(haystack));
+            if !group.regexes.iter().all(|&ri| regex_hit(ri)) || group.not_regexes.iter().any(|&ri| regex_hit(ri)) {
+                continue;
+            }
+            let mut field_hit = |fi: usize| {
+                *field_hits[fi].get_or_insert_with(|| {
+                    let line_fields = parsed.get_or_insert_with(|| fields::parse_fields(haystack));
+                    self.fields[fi].is_match(haystack, line_fields, self.case_sensitive)
+                })
+            };
+            if group.fields.iter().all(|&fi| field_hit(fi)) && !group.not_fields.iter().any(|&fi| field_hit(fi)) {
+                return true;
+            }
+        }
+        false
+    }
+
+    /// 통계 수집용 빈 단어/그룹 통계
+    pub fn new_stats(&self) -> (Vec<TermStat>, Vec<GroupStat>) {
+        let terms = self.labels.iter()
+            .map(|label| TermStat { term: label.clone(), stat: HitStat::default() })
+            .collect();
+        let groups = self.group_labels.iter()
+            .map(|labels| GroupStat { terms: labels.clone(), stat: HitStat::default() })
+            .collect();
+        (terms, groups)
+    }
+
+    /// 통계 모드: 조기 종료 없이 모든 단어/그룹을 평가해서 기록합니다. (`is_match` 와 같은 판정)
+    pub fn is_match_recording(
+        &self,
+        haystack: &[u8],
+        scratch: &mut ComboScratch,
+        line_index: i32,
+        terms: &mut [TermStat],
+        groups: &mut [GroupStat],
+    ) -> bool {
+        let literal_count = self.term_groups.len();
+        scratch.hits.clear();
+        scratch.hits.resize(literal_count, false);
+        if let Some(ac) = &self.ac {
+            ac.for_each_overlapping(haystack, |id, _, _| {
+                scratch.hits[id] = true;
+                false
+            });
+        }
+        scratch.regex_hits.clear();
+        scratch.regex_hits.extend(self.regexes.iter().map(|re| Some(re.is_match(haystack))));
+        scratch.field_hits.clear();
+        if !self.fields.is_empty() {
+            let line_fields = fields::parse_fields(haystack);
+            scratch.field_hits.extend(self.fields.iter().map(|f| Some(f.is_match(haystack, &line_fields, self.case_sensitive))));
+        }
+
+        for (id, &hit) in scratch.hits.iter().enumerate() {
+            if hit {
+                terms[id].stat.record(line_index);
+            }
+        }
+        for (ri, hit) in scratch.regex_hits.iter().chain(&scratch.field_hits).enumerate() {
+            if *hit == Some(true) {
+                terms[literal_count + ri].stat.record(line_index);
+            }
+        }
+
+        let regex_hit = |ri: usize| scratch.regex_hits[ri] == Some(true);
+        let field_hit = |fi: usize| scratch.field_hits[fi] == Some(true);
+        let mut matched = false;
+        for (gi, group) in self.groups.iter().enumerate() {
+            let group_match = group.literals.iter().all(|&id| scratch.hits[id])
+                && !group.not_literals.iter().any(|&id| scratch.hits[id])
+                && group.regexes.iter().all(|&ri| regex_hit(ri))
+                && !group.not_regexes.iter().any(|&ri| regex_hit(ri))
+                && group.fields.iter().all(|&fi| field_hit(fi))
+                && !group.not_fields.iter().any(|&fi| field_hit(fi));
+            if group_match {
+                groups[gi].stat.record(line_index);
+                matched = true;
+            }
+        }
+        matched
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn groups(groups: &[&[&str]]) -> Vec<Vec<String>> {
+        groups.iter().map(|g| g.iter().map(|t| t.to_string()).collect()).collect()
+    }
+
+    fn matcher(g: &[&[&str]], case_sensitive: bool) -> ComboMatcher {
+        ComboMatcher::build(&groups(g), case_sensitive, &mut Automata::compile()).unwrap().expect("non-empty combo")
+    }
+
+    #[test]
+    fn or_of_and_groups() {
+        let m = matcher(&[&["wifi", "timeout"], &["ERROR"]], false);
+        let mut s = ComboScratch::default();
+        assert!(m.is_match(b"wifi connect timeout", &mut s));
+        assert!(m.is_match(b"some error here", &mut s));
+        assert!(!m.is_match(b"wifi connected", &mut s));
+        assert!(!m.is_match(b"timeout only", &mut s));
+    }
+
+    #[test]
+    fn empty_terms_and_groups_are_dropped() {
+        assert!(ComboMatcher::build(&groups(&[&["", "  "], &[]]), false, &mut Automata::compile()).unwrap().is_none());
+        let m = matcher(&[&["", "wifi"], &[]], false);
+        assert_eq!(m.group_count(), 1);
+        assert_eq!(m.term_count(), 1);
+    }
+
+    #[test]
+    fn overlapping_literals_are_all_recorded() {
+        let m = matcher(&[&["fail", "ail"]], true);
+        let mut s = ComboScratch::default();
+        assert!(m.is_match(b"failed", &mut s));
+        assert!(!m.is_match(b"Failed", &mut s));
+    }
+
+    #[test]
+    fn negation_and_regex_terms() {
+        let m = matcher(&[&["wifi", "not:ok"], &[r"re:pid=\d+", "not:re:pid=0\\b"]], false);
+        let mut s = ComboScratch::default();
+        assert!(m.is_match(b"wifi failed", &mut s));
+        assert!(!m.is_match(b"wifi ok", &mut s));
+        assert!(m.is_match(b"pid=12", &mut s));
+        assert!(!m.is_match(b"pid=0 done", &mut s));
+        assert!(!m.is_match(b"nothing", &mut s));
+    }
+
+    #[test]
+    fn negation_only_group_matches_everything_else() {
+        let m = matcher(&[&["not:debug"]], false);
+        let mut s = ComboScratch::default();
+        assert!(m.is_match(b"info line", &mut s));
+        assert!(!m.is_match(b"DEBUG line", &mut s));
+    }
+
+    #[test]
+    fn recording_matches_is_match_and_labels_terms() {
+        let m = matcher(&[&["Wifi", "re:time.ut"], &["error", "not:noise"]], false);
+        let (mut terms, mut group_stats) = m.new_stats();
+        let labels: Vec<&str> = terms.iter().map(|t| t.term.as_str()).collect();
+        assert_eq!(labels, ["Wifi", "error", "noise", "re:time.ut"]);
+
+        let lines: [&[u8]; 4] = [b"wifi timeout", b"error noise", b"error", b"wifi"];
+        let mut s = ComboScratch::default();
+        for (i, line) in lines.iter().enumerate() {
+            let expected = m.is_match(line, &mut s);
+            assert_eq!(m.is_match_recording(line, &mut s, i as i32, &mut terms, &mut group_stats), expected);
+        }
+        assert_eq!(terms[0].stat.hits, 2);
+        assert_eq!(terms[2].stat.first_line, Some(1));
+        assert_eq!(group_stats[0].stat.hits, 1);
+        assert_eq!(group_stats[1].stat.hits, 1);
+        assert_eq!(group_stats[1].terms, ["error", "not:noise"]);
+    }
+
+    #[test]
+    fn invalid_regex_is_reported() {
+        let err = ComboMatcher::build(&groups(&[&["re:(unclosed"]]), false, &mut Automata::compile()).err().expect("error");
+        assert!(matches!(err, FilterError::Regex { .. }));
+    }
+}
@@ -0,0 +1,243 @@
+use chardetng::EncodingDetector;
+use encoding_rs::{CoderResult, Decoder, EUC_KR, UTF_16BE, UTF_16LE, UTF_8};
+use serde::Serialize;
+
+pub use encoding_rs::Encoding;
+
+/// 판별에 쓰는 앞부분 최대 크기
+pub const DETECT_LEN: usize = 64 * 1024;
+
+/// 인코딩 판별 근거
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EncodingSource {
+    /// BOM (`EF BB BF`, `FF FE`, `FE FF`)
+    Bom,
+    /// BOM 없는 UTF-16 (ASCII 위주 로그의 0 바이트 위치)
+    Zeros,
+    /// 유효한 UTF-8
+    Utf8,
+    /// 바이트 통계 (chardetng)
+    Guess,
+    /// 사용자가 직접 지정
+    Manual,
+}
+
+#[derive(Clone, Copy, Debug, Serialize)]
+pub struct DetectedEncoding {
+    #[serde(serialize_with = "serialize_encoding")]
+    pub encoding: &'static Encoding,
+    pub source: EncodingSource,
+}
+
+fn serialize_encoding<S: serde::Serializer>(encoding: &&'static Encoding, s: S) -> Result<S::Ok, S::Error> {
+    s.serialize_str(encoding.name())
+}
+
+/// 인코딩 이름/별칭 (`utf-8`, `utf-16le`, `cp949`, `euc-kr`, `latin1`, ...). 모르는 이름은 `None`
+///
+/// WHATWG 라벨에 없는 Windows 쪽 이름(`cp949`, `utf16`)도 받습니다.
+pub fn encoding_for_label(label: &str) -> Option<&'static Encoding> {
+    let label = label.trim().to_ascii_lowercase();
+    match label.as_str() {
+        "cp949" | "uhc" | "ms949" => Some(EUC_KR),
+        "utf16" | "utf-16" | "ucs-2" => Some(UTF_16LE),
+        _ => Encoding::for_label(label.as_bytes()),
+    }
+}
+
+/// 파일 앞부분으로 인코딩 판별 (BOM → BOM 없는 UTF-16 → UTF-8 → 통계 순)
+pub fn detect_encoding(head: &[u8]) -> DetectedEncoding {
+    let head = &head[..head.len().min(DETECT_LEN)];
+    if let Some((encoding, _)) = Encoding::for_bom(head) {
+        return DetectedEncoding { encoding, source: EncodingSource::Bom };
+    }
+    if let Some(encoding) = utf16_without_bom(head) {
+        return DetectedEncoding { encoding, source: EncodingSource::Zeros };
+    }
+    detect_text(head)
+}
+
+/// ASCII 호환 인코딩 판별 (UTF-8 → 통계). BOM / UTF-16 은 파일 앞에서만 보므로 여기선 보지 않습니다.
+fn detect_text(head: &[u8]) -> DetectedEncoding {
+    let head = &head[..head.len().min(DETECT_LEN)];
+    if is_utf8_prefix(head) {
+        return DetectedEncoding { encoding: UTF_8, source: EncodingSource::Utf8 };
+    }
+    let mut detector = EncodingDetector::new();
+    detector.feed(head, false);
+    DetectedEncoding { encoding: detector.guess(None, true), source: EncodingSource::Guess }
+}
+
+/// 잘린 마지막 문자는 허용하는 UTF-8 검사 (청크 앞부분만 보므로)
+fn is_utf8_prefix(head: &[u8]) -> bool {
+    match std::str::from_utf8(head) {
+        Ok(_) => true,
+        Err(e) => e.error_len().is_none(),
+    }
+}
+
+/// 로그는 대부분 ASCII 라서 UTF-16 이면 한쪽 위치에만 0 바이트가 몰립니다.
+fn utf16_without_bom(head: &[u8]) -> Option<&'static Encoding> {
+    if head.len() < 16 {
+        return None;
+    }
+    let pairs = head.len() / 2;
+    let even_zeros = head.iter().step_by(2).filter(|&&b| b == 0).count();
+    let odd_zeros = head.iter().skip(1).step_by(2).filter(|&&b| b == 0).count();
+    let ratio = |n: usize| n as f64 / pairs as f64;
+    if ratio(odd_zeros) > 0.3 && ratio(even_zeros) < 0.05 {
+        Some(UTF_16LE)
+    } else if ratio(even_zeros) > 0.3 && ratio(odd_zeros) < 0.05 {
+        Some(UTF_16BE)
+    } else {
+        None
+    }
+}
+
+/// 청크 단위로 UTF-8 로 변환하는 디코더 (청크 경계에 걸친 문자도 처리)
+///
+/// 인코딩을 지정하지 않으면 앞부분으로 판별합니다. UTF-8 은 BOM 만 떼고 그대로 통과시킵니다.
+/// (잘못된 바이트는 이후 단계에서 lossy 로 처리되므로 여기서 검사하지 않음)
+///
+/// 앞부분이 ASCII 뿐이면 UTF-8 로 임시 판별하고, ASCII 가 아닌 바이트가 처음 나오는 청크에서 다시 판별합니다.
+/// (영어 로그 뒤에 CP949 한글이 나오는 경우. 그때까지 나온 ASCII 는 어느 인코딩이든 그대로라 다시 풀 필요 없음)
+pub struct LogDecoder {
+    manual: Option<&'static Encoding>,
+    detected: Option<DetectedEncoding>,
+    /// 지금까지 ASCII 만 봐서 판별이 확정되지 않음
+    provisional: bool,
+    decoder: Option<Decoder>,
+    /// UTF-8 통과 모드에서 BOM 을 떼었는지 (청크가 BOM 중간에서 잘리는 경우는 무시)
+    started: bool,
+}
+
+impl LogDecoder {
+    /// `encoding`: `None` 이면 자동 판별
+    pub fn new(encoding: Option<&'static Encoding>) -> Self {
+        LogDecoder { manual: encoding, detected: None, provisional: false, decoder: None, started: false }
+    }
+
+    /// 판별/지정된 인코딩 (첫 `decode` 전에는 `None`, ASCII 만 들어온 동안은 임시로 UTF-8)
+    pub fn detected(&self) -> Option<DetectedEncoding> {
+        self.detected
+    }
+
+    /// 청크를 UTF-8 로 변환해서 `out` 뒤에 붙입니다. 마지막 청크는 `last = true`
+    pub fn decode(&mut self, chunk: &[u8], last: bool, out: &mut Vec<u8>) {
+        if self.detect(chunk) == UTF_8 {
+            out.extend_from_slice(self.strip_utf8_bom(chunk));
+        } else {
+            self.decode_into(chunk, last, out);
+        }
+    }
+
+    /// `decode` 와 같지만 UTF-8 이면 복사하지 않고 `chunk` 를 그대로 돌려줍니다. (`buf` 는 비우고 씀)
+    pub fn decode_slice<'a>(&mut self, chunk: &'a [u8], last: bool, buf: &'a mut Vec<u8>) -> &'a [u8] {
+        if self.detect(chunk) == UTF_8 {
+            return self.strip_utf8_bom(chunk);
+        }
+        buf.clear();
+        self.decode_into(chunk, last, buf);
+        buf
+    }
+
+    fn detect(&mut self, chunk: &[u8]) -> &'static Encoding {
+        if self.detected.is_none() {
+            let detected = match self.manual {
+                Some(encoding) => DetectedEncoding { encoding, source: EncodingSource::Manual },
+                None => detect_encoding(chunk),
+            };
+            // ASCII 만 보고 UTF-8 로 판별했을 수 있으므로 아래에서 다시 확인
+            self.provisional = detected.source == EncodingSource::Utf8;
+            self.detected = Some(detected);
+        }
+        if self.provisional {
+            if let Some(start) = chunk.iter().position(|b| !b.is_ascii()) {
+                // 앞은 모두 ASCII 라 여기가 문자 경계
+                self.provisional = false;
+                self.detected = Some(detect_text(&chunk[start..]));
+            }
+        }
+        self.detected.expect("detected above").encoding
+    }
+
+    fn strip_utf8_bom<'a>(&mut self, chunk: &'a [u8]) -> &'a [u8] {
+        if std::mem::replace(&mut self.started, true) {
+            chunk
+        } else {
+            chunk.strip_prefix(b"\xEF\xBB\xBF").unwrap_or(chunk)
+        }
+    }
+
+    fn decode_into(&mut self, chunk: &[u8], last: bool, out: &mut Vec<u8>) {
+        let encoding = self.detect(chunk);
+        let decoder = self.decoder.get_or_insert_with(|| encoding.new_decoder_with_bom_removal());
+        let mut src = chunk;
+        loop {
+            let needed = decoder.max_utf8_buffer_length(src.len()).unwrap_or(src.len() * 3 + 16);
+            let start = out.len();
+            out.resize(start + needed, 0);
+            let (result, read, written, _) = decoder.decode_to_utf8(src, &mut out[start..], last);
+            out.truncate(start + written);
+            src = &src[read..];
+            if result == CoderResult::InputEmpty {
+                break;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn decode_all(decoder: &mut LogDecoder, chunks: &[&[u8]]) -> String {
+        let mut out = Vec::new();
+        for (i, chunk) in chunks.iter().enumerate() {
+            decoder.decode(chunk, i + 1 == chunks.len(), &mut out);
+        }
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn detects_bom_utf16_utf8_and_legacy() {
+        assert_eq!(detect_encoding(b"\xEF\xBB\xBFhello").source, EncodingSource::Bom);
+        let utf16: Vec<u8> = "plain ascii log line\n".encode_utf16().flat_map(u16::to_le_bytes).collect();
+        assert_eq!(detect_encoding(&utf16).encoding, UTF_16LE);
+        assert_eq!(detect_encoding("한글 로그".as_bytes()).encoding, UTF_8);
+        let (cp949, _, _) = EUC_KR.encode("연결 실패: 와이파이 모듈 응답 없음, 다시 시도합니다\n");
+        assert_eq!(detect_encoding(&cp949).encoding, EUC_KR);
+        assert_eq!(encoding_for_label(" CP949 "), Some(EUC_KR));
+        assert_eq!(encoding_for_label("nope"), None);
+    }
+
+    #[test]
+    fn ascii_head_keeps_detection_open() {
+        let (cp949, _, _) = EUC_KR.encode("연결 실패: 와이파이 모듈 응답 없음, 다시 시도합니다\n");
+        let mut decoder = LogDecoder::new(None);
+        let text = decode_all(&mut decoder, &[b"boot ok\n", b"init\n", &cp949]);
+        assert_eq!(text, "boot ok\ninit\n연결 실패: 와이파이 모듈 응답 없음, 다시 시도합니다\n");
+        assert_eq!(decoder.detected().unwrap().encoding, EUC_KR);
+
+        // 확정된 뒤에는 바뀌지 않음
+        let mut decoder = LogDecoder::new(None);
+        decode_all(&mut decoder, &["한글\n".as_bytes(), b"ascii\n"]);
+        assert_eq!(decoder.detected().unwrap().encoding, UTF_8);
+        let mut out = Vec::new();
+        decoder.decode(&cp949, true, &mut out);
+        assert_eq!(out, &cp949[..]);
+    }
+
+    #[test]
+    fn split_characters_and_bom_across_chunks() {
+        let utf16: Vec<u8> = "\u{FEFF}로그 line\n".encode_utf16().flat_map(u16::to_le_bytes).collect();
+        let chunks: Vec<&[u8]> = utf16.chunks(3).collect();
+        assert_eq!(decode_all(&mut LogDecoder::new(None), &chunks), "로그 line\n");
+
+        let mut decoder = LogDecoder::new(None);
+        let mut buf = Vec::new();
+        assert_eq!(decoder.decode_slice(b"\xEF\xBB\xBFabc", false, &mut buf), b"abc");
+        assert_eq!(decoder.decode_slice(b"\xEF\xBB\xBFabc", false, &mut buf), b"\xEF\xBB\xBFabc");
+    }
+}
@@ -0,0 +1,40 @@
+use aho_corasick::BuildError;
+use std::fmt;
+
+/// 룰 컴파일 에러 (JS 쪽에는 문자열로 전달됩니다)
+#[derive(Debug)]
+pub enum FilterError {
+    Automaton(BuildError),
+    Regex { pattern: String, message: String },
+    Blob(String),
+    Archive(String),
+    TimeFormat { format: String, message: String },
+    Field { term: String, message: String },
+    TooManyRules(usize),
+}
+
+impl fmt::Display for FilterError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FilterError::Automaton(e) => write!(f, "AC build error: {}", e),
+            FilterError::Regex { pattern, message } => write!(f, "Invalid regex `{}`: {}", pattern, message),
+            FilterError::Blob(message) | FilterError::Archive(message) => f.write_str(message),
+            FilterError::TimeFormat { format, message } => write!(f, "Invalid time format `{}`: {}", format, message),
+            FilterError::Field { term, message } => write!(f, "Invalid field condition `{}`: {}", term, message),
+            FilterError::TooManyRules(n) => write!(
+                f,
+                "Too many rules: {} (max {} per engine, one bit of the u32 match mask each; split them across engines)",
+                n,
+                crate::multi::MAX_RULES
+            ),
+        }
+    }
+}
+
+impl std::error::Error for FilterError {}
+
+impl From<BuildError> for FilterError {
+    fn from(e: BuildError) -> Self {
+        FilterError::Automaton(e)
+    }
+}
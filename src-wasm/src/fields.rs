@@ -0,0 +1,605 @@
+use memchr::memchr;
+use serde::Serialize;
+use std::ops::Range;
+
+use crate::level::LogLevel;
+use crate::line;
+
+/// 소스 메타(`File.cs: Func(12)>`) 의 `>` 를 찾는 범위 (perfAnalysis.ts 의 `extractSourceMetadata` 와 동일)
+const SOURCE_SCAN_LIMIT: usize = 400;
+
+/// 레벨/태그 같은 헤더를 찾는 범위
+const HEADER_SCAN_LIMIT: usize = 128;
+
+/// 소스 파일로 인정하는 확장자 (perfAnalysis.ts 의 `EXT_SET`)
+const SOURCE_EXTENSIONS: [&[u8]; 14] =
+    [b"cs", b"cpp", b"h", b"java", b"kt", b"js", b"ts", b"tsx", b"py", b"c", b"cc", b"hpp", b"m", b"mm"];
+
+/// 알아본 라인 형식
+#[derive(Serialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum LineFormat {
+    /// 형식을 모르는 라인 (레벨/PID 등은 일반 규칙으로 찾음)
+    #[default]
+    Unknown,
+    /// Tizen dlog / Android brief: `01-01 10:00:00.123+0900 I/Tag(P 1, T 2): msg`
+    Dlog,
+    /// Android threadtime: `01-01 10:00:00.123  1234  5678 I Tag  : msg`
+    Threadtime,
+    /// 커널 dmesg: `<6>[  123.456789] msg`
+    Kernel,
+}
+
+impl LineFormat {
+    pub fn code(self) -> u32 {
+        match self {
+            LineFormat::Unknown => 0,
+            LineFormat::Dlog => 1,
+            LineFormat::Threadtime => 2,
+            LineFormat::Kernel => 3,
+        }
+    }
+}
+
+/// 값과 그 값이 있던 바이트 구간
+#[derive(Serialize, Clone, Debug, PartialEq, Eq)]
+pub struct Field<T> {
+    pub value: T,
+    pub range: Range<usize>,
+}
+
+/// 라인 하나에서 뽑은 필드 (구간은 라인 기준 바이트 오프셋, `to_utf16` 후에는 UTF-16 오프셋)
+#[derive(Serialize, Clone, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct LineFields {
+    pub format: LineFormat,
+    pub time: Option<Range<usize>>,
+    pub level: Option<Field<LogLevel>>,
+    pub tag: Option<Range<usize>>,
+    pub pid: Option<Field<u32>>,
+    pub tid: Option<Field<u32>>,
+    /// `File.cs: Func(12)>` 의 `File.cs`
+    pub file: Option<Range<usize>>,
+    pub function: Option<Range<usize>>,
+    pub code_line: Option<Field<u32>>,
+    /// 헤더 뒤 본문 시작 (끝은 라인 끝)
+    pub message: usize,
+}
+
+/// 바이트 커서 (헤더 파싱용)
+#[derive(Clone, Copy)]
+struct Cursor<'a> {
+    s: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Cursor<'a> {
+    fn peek(&self) -> Option<u8> {
+        self.s.get(self.pos).copied()
+    }
+
+    fn eat(&mut self, b: u8) -> bool {
+        let ok = self.peek() == Some(b);
+        self.pos += ok as usize;
+        ok
+    }
+
+    /// 공백 개수
+    fn spaces(&mut self) -> usize {
+        let n = self.s[self.pos..].iter().take_while(|b| **b == b' ' || **b == b'\t').count();
+        self.pos += n;
+        n
+    }
+
+    /// 연속된 숫자 (최대 `max` 자리)
+    fn number(&mut self, max: usize) -> Option<Field<u32>> {
+        let n = self.s[self.pos..].iter().take_while(|b| b.is_ascii_digit()).count();
+        if n == 0 || n > max {
+            return None;
+        }
+        let start = self.pos;
+        self.pos += n;
+        let value = self.s[start..self.pos].iter().fold(0u32, |acc, &b| acc.wrapping_mul(10).wrapping_add((b - b'0') as u32));
+        Some(Field { value, range: start..self.pos })
+    }
+
+    /// 정확히 `n` 자리 숫자
+    fn digits(&mut self, n: usize) -> bool {
+        let ok = self.s.len() >= self.pos + n && self.s[self.pos..self.pos + n].iter().all(u8::is_ascii_digit);
+        self.pos += if ok { n } else { 0 };
+        ok
+    }
+}
+
+/// `(YYYY-)?MM-DD HH:mm:ss.fff(+zzzz)?`, `HH:mm:ss.fff`, `123.456` (모노토닉) 중 하나. 성공하면 구간
+fn parse_time(c: &mut Cursor) -> Option<Range<usize>> {
+    let start = c.pos;
+    let mut t = *c;
+
+    // 날짜
+    let mut d = t;
+    if d.digits(4) && d.eat(b'-') {
+        t = d;
+    }
+    let mut d = t;
+    if d.digits(2) && d.eat(b'-') && d.digits(2) && d.spaces() > 0 {
+        t = d;
+    }
+    // 시각
+    let mut h = t;
+    if h.digits(2) && h.eat(b':') && h.digits(2) && h.eat(b':') && h.digits(2) {
+        if h.eat(b'.') && !h.digits(3) {
+            return None;
+        }
+        while h.peek().is_some_and(|b| b.is_ascii_digit()) {
+            h.pos += 1;
+        }
+        // 타임존 `+0900`
+        let mut z = h;
+        if (z.eat(b'+') || z.eat(b'-')) && z.digits(4) {
+            h = z;
+        }
+        c.pos = h.pos;
+        return Some(start..c.pos);
+    }
+    if t.pos != start {
+        return None;
+    }
+
+    // 모노토닉 초
+    let mut m = *c;
+    m.number(12)?;
+    if !m.eat(b'.') {
+        return None;
+    }
+    m.number(9)?;
+    if !matches!(m.peek(), Some(b' ' | b'\t' | b']')) {
+        return None;
+    }
+    c.pos = m.pos;
+    Some(start..c.pos)
+}
+
+/// 커널: `<6>[  123.456789] ` (`<N>` 은 syslog 우선순위)
+fn parse_kernel(c: &mut Cursor, f: &mut LineFields) -> bool {
+    let mut k = *c;
+    let mut level = None;
+    if k.eat(b'<') {
+        let start = k.pos - 1;
+        let Some(priority) = k.number(2) else { return false };
+        if !k.eat(b'>') {
+            return false;
+        }
+        let value = match priority.value % 8 {
+            0..=2 => LogLevel::Fatal,
+            3 => LogLevel::Error,
+            4 => LogLevel::Warn,
+            5 | 6 => LogLevel::Info,
+            _ => LogLevel::Debug,
+        };
+        level = Some(Field { value, range: start..k.pos });
+    }
+    if !k.eat(b'[') {
+        return false;
+    }
+    k.spaces();
+    let start = k.pos;
+    if k.number(12).is_none() || !k.eat(b'.') || k.number(9).is_none() {
+        return false;
+    }
+    let end = k.pos;
+    k.spaces();
+    if !k.eat(b']') {
+        return false;
+    }
+    k.spaces();
+    f.format = LineFormat::Kernel;
+    f.time = Some(start..end);
+    f.level = level;
+    *c = k;
+    true
+}
+
+fn level_at(c: &Cursor) -> Option<Field<LogLevel>> {
+    let value = LogLevel::from_letter(c.peek()?)?;
+    Some(Field { value, range: c.pos..c.pos + 1 })
+}
+
+/// threadtime 의 시각 뒤: `  1234  5678 I Tag  : `
+fn parse_threadtime(c: &mut Cursor, f: &mut LineFields) -> bool {
+    let mut t = *c;
+    if t.spaces() == 0 {
+        return false;
+    }
+    let Some(pid) = t.number(10) else { return false };
+    if t.spaces() == 0 {
+        return false;
+    }
+    let Some(tid) = t.number(10) else { return false };
+    if t.spaces() == 0 {
+        return false;
+    }
+    let Some(level) = level_at(&t) else { return false };
+    t.pos += 1;
+    if t.spaces() == 0 {
+        return false;
+    }
+    let tag_start = t.pos;
+    let Some(colon) = find_colon(t.s, t.pos) else { return false };
+    let tag_end = tag_start + trim_end(&t.s[tag_start..colon]);
+    t.pos = colon + 1;
+    t.spaces();
+    f.format = LineFormat::Threadtime;
+    f.pid = Some(pid);
+    f.tid = Some(tid);
+    f.level = Some(level);
+    f.tag = (tag_end > tag_start).then_some(tag_start..tag_end);
+    *c = t;
+    true
+}
+
+/// dlog / brief: `I/Tag(P 1234, T 5678): `, `I/Tag( 1234): `, `I/Tag: `
+fn parse_dlog(c: &mut Cursor, f: &mut LineFields) -> bool {
+    let mut t = *c;
+    let Some(level) = level_at(&t) else { return false };
+    t.pos += 1;
+    if !t.eat(b'/') {
+        return false;
+    }
+    let tag_start = t.pos;
+    let tag_len = t.s[t.pos..].iter().take(HEADER_SCAN_LIMIT).take_while(|&&b| b != b'(' && b != b':').count();
+    t.pos += tag_len;
+    let tag_end = tag_start + trim_end(&t.s[tag_start..t.pos]);
+    let (mut pid, mut tid) = (None, None);
+    if t.peek() == Some(b'(') {
+        let mut ids = t;
+        ids.pos += 1;
+        if let Some((p, th)) = parse_id_pair(&mut ids) {
+            pid = Some(p);
+            tid = th;
+            t = ids;
+        } else {
+            // 괄호 안이 ID 가 아니면 태그 일부로 취급하지 않고 형식 불일치
+            return false;
+        }
+    }
+    if !t.eat(b':') {
+        return false;
+    }
+    t.spaces();
+    f.format = LineFormat::Dlog;
+    f.level = Some(level);
+    f.tag = (tag_end > tag_start).then_some(tag_start..tag_end);
+    f.pid = pid;
+    f.tid = tid;
+    *c = t;
+    true
+}
+
+/// `(` 다음부터 `P 1234, T 5678)`, ` 1234)`, `P 1 T 1)`, `1234:5678)` 를 읽습니다.
+fn parse_id_pair(c: &mut Cursor) -> Option<(Field<u32>, Option<Field<u32>>)> {
+    c.spaces();
+    let label = |c: &mut Cursor, letter: u8| {
+        if c.peek().is_some_and(|b| b.eq_ignore_ascii_case(&letter)) {
+            c.pos += 1;
+            c.spaces();
+        }
+    };
+    label(c, b'P');
+    let pid = c.number(10)?;
+    c.spaces();
+    if c.eat(b')') {
+        return Some((pid, None));
+    }
+    if !(c.eat(b',') || c.eat(b':') || c.eat(b'-') || c.s.get(c.pos - 1) == Some(&b' ')) {
+        return None;
+    }
+    c.spaces();
+    label(c, b'T');
+    let tid = c.number(10)?;
+    c.spaces();
+    c.eat(b')').then_some((pid, Some(tid)))
+}
+
+/// `pos` 이후 헤더 범위 안의 첫 `:`
+fn find_colon(s: &[u8], pos: usize) -> Option<usize> {
+    let end = s.len().min(pos + HEADER_SCAN_LIMIT);
+    memchr(b':', &s[pos..end]).map(|p| pos + p)
+}
+
+fn trim_end(s: &[u8]) -> usize {
+    s.len() - s.iter().rev().take_while(|b| b.is_ascii_whitespace()).count()
+}
+
+/// 형식을 모를 때: 헤더 안에서 `I/Tag(` 형태를 찾고, 없으면 레벨 글자와 `(P 1, T 2)` / `[1:2]` 형태 ID 를 찾습니다.
+fn parse_generic(c: &mut Cursor, f: &mut LineFields, header_end: usize) {
+    let end = header_end.min(c.pos + HEADER_SCAN_LIMIT);
+    for i in c.pos..end {
+        let before_ok = i == 0 || c.s[i - 1] == b' ' || c.s[i - 1] == b'\t' || c.s[i - 1] == b']';
+        if before_ok && c.s.get(i + 1) == Some(&b'/') && LogLevel::from_letter(c.s[i]).is_some() {
+            let mut d = Cursor { s: c.s, pos: i };
+            if parse_dlog(&mut d, f) {
+                // 바로 앞 토큰이 시각이면 (`ST_APP: 123.457 I/ST_APP(...)`) 그것도 사용
+                if f.time.is_none() {
+                    f.time = time_before(c.s, i);
+                }
+                *c = d;
+                return;
+            }
+        }
+    }
+
+    let header = &c.s[..end];
+    for i in c.pos..end {
+        let before_ok = i == 0 || matches!(header[i - 1], b' ' | b'\t' | b'[' | b'/');
+        let after_ok = matches!(header.get(i + 1), Some(b' ' | b'\t' | b'/' | b':' | b']'));
+        if before_ok && after_ok {
+            if let Some(level) = level_at(&Cursor { s: header, pos: i }) {
+                f.level = Some(level);
+                break;
+            }
+        }
+    }
+    for i in c.pos..end {
+        let close = match header[i] {
+            b'(' => b')',
+            b'[' => b']',
+            _ => continue,
+        };
+        let mut ids = Cursor { s: header, pos: i + 1 };
+        if close == b')' {
+            if let Some((pid, Some(tid))) = parse_id_pair(&mut ids) {
+                f.pid = Some(pid);
+                f.tid = Some(tid);
+                break;
+            }
+        } else {
+            ids.spaces();
+            let Some(pid) = ids.number(10) else { continue };
+            if !(ids.eat(b':') || ids.eat(b'-') || ids.spaces() > 0) {
+                continue;
+            }
+            ids.spaces();
+            let Some(tid) = ids.number(10) else { continue };
+            ids.spaces();
+            if ids.eat(close) {
+                f.pid = Some(pid);
+                f.tid = Some(tid);
+                break;
+            }
+        }
+    }
+}
+
+/// `s[..end]` 의 마지막 토큰이 시각/모노토닉 초면 그 구간
+fn time_before(s: &[u8], end: usize) -> Option<Range<usize>> {
+    let token_end = trim_end(&s[..end]);
+    let token_start = s[..token_end].iter().rposition(|b| b.is_ascii_whitespace()).map_or(0, |p| p + 1);
+    let mut c = Cursor { s, pos: token_start };
+    parse_time(&mut c).filter(|r| r.end == token_end)
+}
+
+/// `File.cs: Func(12)>` (perfAnalysis.ts 의 `extractSourceMetadata` 와 같은 규칙)
+fn parse_source(s: &[u8], from: usize, f: &mut LineFields) {
+    let limit = s.len().min(from + SOURCE_SCAN_LIMIT);
+    let Some(marker) = memchr(b'>', &s[from..limit]).map(|p| from + p) else { return };
+    let mut search = from;
+    while let Some(colon) = memchr(b':', &s[search..marker]).map(|p| search + p) {
+        search = colon + 1;
+        let name_end = trim_end(&s[from..colon]) + from;
+        let name_start = s[from..name_end]
+            .iter()
+            .rposition(|b| matches!(b, b' ' | b'\t' | b'[' | b'(' | b'<' | b'/' | b'|' | b','))
+            .map_or(from, |p| from + p + 1);
+        let name = &s[name_start..name_end];
+        let Some(dot) = name.iter().rposition(|&b| b == b'.') else { continue };
+        let ext = &name[dot + 1..];
+        if name.len() < 3 || !SOURCE_EXTENSIONS.iter().any(|e| e.eq_ignore_ascii_case(ext)) {
+            continue;
+        }
+
+        f.file = Some(name_start..name_end);
+        let rest_start = colon + 1 + s[colon + 1..marker].iter().take_while(|b| b.is_ascii_whitespace()).count();
+        let rest_end = rest_start + trim_end(&s[rest_start..marker]);
+        let rest = &s[rest_start..rest_end];
+        let mut function_end = rest_end;
+        if let Some(open) = rest.iter().rposition(|&b| b == b'(') {
+            if let Some(close) = memchr(b')', &rest[open..]).map(|p| open + p) {
+                let mut c = Cursor { s: &rest[..close], pos: open + 1 };
+                if let Some(number) = c.number(10).filter(|_| c.pos == close) {
+                    f.code_line = Some(Field { value: number.value, range: rest_start + open + 1..rest_start + close });
+                    function_end = rest_start + trim_end(&rest[..open]);
+                }
+            }
+        }
+        f.function = (function_end > rest_start).then_some(rest_start..function_end);
+        f.message = marker + 1 + s[marker + 1..].iter().take_while(|b| **b == b' ').count();
+        return;
+    }
+}
+
+/// 라인 맨 앞의 알려진 헤더(커널, 시각 + threadtime/dlog, 시각 없는 brief) 만 읽습니다.
+fn parse_known_header(c: &mut Cursor, f: &mut LineFields) {
+    c.spaces();
+    if parse_kernel(c, f) {
+        return;
+    }
+    let mut header = *c;
+    if header.eat(b'[') {
+        header.spaces();
+    }
+    if let Some(time) = parse_time(&mut header) {
+        f.time = Some(time);
+        header.eat(b']');
+        *c = header;
+        let mut t = *c;
+        if parse_threadtime(&mut t, f) {
+            *c = t;
+        } else {
+            c.spaces();
+            parse_dlog(c, f);
+        }
+    } else {
+        parse_dlog(c, f);
+    }
+}
+
+/// 알려진 로그 형식의 헤더에 있는 레벨만 돌려줍니다. (ANSI/`\r` 은 미리 지운 라인)
+///
+/// `parse_fields` 와 달리 형식을 모르는 라인에서 레벨 글자를 추측하지 않으므로,
+/// `sh: E failed` 나 본문 중간의 ` W ` 같은 글자는 레벨이 아닙니다.
+pub fn header_level(line: &[u8]) -> Option<LogLevel> {
+    let mut f = LineFields::default();
+    parse_known_header(&mut Cursor { s: line, pos: 0 }, &mut f);
+    f.level.map(|level| level.value)
+}
+
+/// 라인 하나를 필드로 나눕니다. (ANSI/`\r` 은 미리 지운 라인)
+pub fn parse_fields(line: &[u8]) -> LineFields {
+    let mut f = LineFields::default();
+    let mut c = Cursor { s: line, pos: 0 };
+    parse_known_header(&mut c, &mut f);
+    if f.format == LineFormat::Unknown {
+        let header_end = memchr(b'>', &line[c.pos..line.len().min(c.pos + SOURCE_SCAN_LIMIT)]).map_or(line.len(), |p| c.pos + p);
+        parse_generic(&mut c, &mut f, header_end);
+    }
+
+    f.message = c.pos;
+    parse_source(line, c.pos, &mut f);
+    f
+}
+
+impl LineFields {
+    /// 모든 구간을 UTF-16 오프셋으로 바꿉니다. (JS 문자열에서 바로 `slice` 할 수 있도록)
+    ///
+    /// `map` 은 재사용 버퍼입니다. ASCII 라인은 그대로 둡니다.
+    pub fn to_utf16(&mut self, line: &[u8], map: &mut Vec<usize>) {
+        if line.is_ascii() {
+            return;
+        }
+        line::utf16_offsets(line, map);
+        let conv = |r: &mut Range<usize>| *r = map[r.start]..map[r.end];
+        for range in [&mut self.time, &mut self.tag, &mut self.file, &mut self.function].into_iter().flatten() {
+            conv(range);
+        }
+        if let Some(level) = &mut self.level {
+            conv(&mut level.range);
+        }
+        for field in [&mut self.pid, &mut self.tid, &mut self.code_line].into_iter().flatten() {
+            conv(&mut field.range);
+        }
+        self.message = map[self.message];
+    }
+
+    /// `filter_chunk` 와 함께 쓰는 평탄화된 레코드 (`FIELD_STRIDE` 개, 없는 값은 `u32::MAX`)
+    ///
+    /// `[format, level(글자 코드), pid, tid, codeLine, time.start, time.end, level.start, level.end, tag.start, tag.end,
+    ///   pid.start, pid.end, tid.start, tid.end, file.start, file.end, function.start, function.end,
+    ///   codeLine.start, codeLine.end, message]`
+    pub fn push_flat(&self, out: &mut Vec<u32>) {
+        const NONE: u32 = u32::MAX;
+        let range = |r: Option<&Range<usize>>| r.map_or([NONE, NONE], |r| [r.start as u32, r.end as u32]);
+        let value = |f: &Option<Field<u32>>| f.as_ref().map_or(NONE, |f| f.value);
+        out.extend([
+            self.format.code(),
+            self.level.as_ref().map_or(NONE, |l| l.value.letter() as u32),
+            value(&self.pid),
+            value(&self.tid),
+            value(&self.code_line),
+        ]);
+        out.extend(range(self.time.as_ref()));
+        out.extend(range(self.level.as_ref().map(|l| &l.range)));
+        out.extend(range(self.tag.as_ref()));
+        out.extend(range(self.pid.as_ref().map(|f| &f.range)));
+        out.extend(range(self.tid.as_ref().map(|f| &f.range)));
+        out.extend(range(self.file.as_ref()));
+        out.extend(range(self.function.as_ref()));
+        out.extend(range(self.code_line.as_ref().map(|f| &f.range)));
+        out.push(self.message as u32);
+    }
+}
+
+/// `push_flat` 레코드 하나의 길이
+pub const FIELD_STRIDE: usize = 22;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn text(line: &str, range: &Option<Range<usize>>) -> Option<String> {
+        range.clone().map(|r| line[r].to_string())
+    }
+
+    #[test]
+    fn dlog_header() {
+        let line = "01-01 10:00:00.123+0900 I/WIFI_MGR(P 1234, T 5678): connect fail";
+        let f = parse_fields(line.as_bytes());
+        assert_eq!(f.format, LineFormat::Dlog);
+        assert_eq!(text(line, &f.time).as_deref(), Some("01-01 10:00:00.123+0900"));
+        assert_eq!(f.level.as_ref().map(|l| l.value), Some(LogLevel::Info));
+        assert_eq!(text(line, &f.tag).as_deref(), Some("WIFI_MGR"));
+        assert_eq!((f.pid.unwrap().value, f.tid.unwrap().value), (1234, 5678));
+        assert_eq!(&line[f.message..], "connect fail");
+    }
+
+    #[test]
+    fn threadtime_and_kernel_headers() {
+        let line = "01-01 10:00:00.123  1234  5678 E ActivityManager: ANR in app";
+        let f = parse_fields(line.as_bytes());
+        assert_eq!(f.format, LineFormat::Threadtime);
+        assert_eq!(f.level.map(|l| l.value), Some(LogLevel::Error));
+        assert_eq!(text(line, &f.tag).as_deref(), Some("ActivityManager"));
+        assert_eq!(f.pid.map(|p| p.value), Some(1234));
+        assert_eq!(&line[f.message..], "ANR in app");
+
+        let line = "<3>[  123.456789] usb 1-1: reset";
+        let f = parse_fields(line.as_bytes());
+        assert_eq!(f.format, LineFormat::Kernel);
+        assert_eq!(f.level.map(|l| l.value), Some(LogLevel::Error));
+        assert_eq!(text(line, &f.time).as_deref(), Some("123.456789"));
+    }
+
+    #[test]
+    fn generic_lines_and_source_metadata() {
+        let line = "[main] W [12:34] Loader.cs: LoadAll(42)> retry later";
+        let f = parse_fields(line.as_bytes());
+        assert_eq!(f.format, LineFormat::Unknown);
+        assert_eq!(f.level.map(|l| l.value), Some(LogLevel::Warn));
+        assert_eq!((f.pid.map(|p| p.value), f.tid.map(|t| t.value)), (Some(12), Some(34)));
+        assert_eq!(text(line, &f.file).as_deref(), Some("Loader.cs"));
+        assert_eq!(text(line, &f.function).as_deref(), Some("LoadAll"));
+        assert_eq!(f.code_line.map(|l| l.value), Some(42));
+        assert_eq!(&line[f.message..], "retry later");
+
+        let f = parse_fields(b"plain message without header");
+        assert_eq!((f.level, f.pid, f.file, f.message), (None, None, None, 0));
+    }
+
+    #[test]
+    fn header_level_only_trusts_known_formats() {
+        assert_eq!(header_level(b"01-01 10:00:00.123 E/Tag( 1): x"), Some(LogLevel::Error));
+        assert_eq!(header_level(b"W/Tag( 12): brief"), Some(LogLevel::Warn));
+        assert_eq!(header_level(b"<4>[ 1.000000] kernel"), Some(LogLevel::Warn));
+        assert_eq!(header_level(b"sh: E failed"), None);
+        assert_eq!(parse_fields(b"sh: E failed").level.map(|l| l.value), Some(LogLevel::Error));
+        assert_eq!(header_level(b"01-01 10:00:00.123 I/Tag( 1): E inside message"), Some(LogLevel::Info));
+    }
+
+    #[test]
+    fn utf16_offsets_and_flat_record() {
+        let line = "01-01 10:00:00.123 I/한글(P 1, T 2): 메시지";
+        let mut f = parse_fields(line.as_bytes());
+        f.to_utf16(line.as_bytes(), &mut Vec::new());
+        let units: Vec<u16> = line.encode_utf16().collect();
+        let tag = f.tag.clone().unwrap();
+        assert_eq!(String::from_utf16(&units[tag]).unwrap(), "한글");
+        assert_eq!(String::from_utf16(&units[f.message..]).unwrap(), "메시지");
+
+        let mut flat = Vec::new();
+        f.push_flat(&mut flat);
+        parse_fields(b"x").push_flat(&mut flat);
+        assert_eq!(flat.len(), 2 * FIELD_STRIDE);
+        assert_eq!(flat[..2], [LineFormat::Dlog.code(), 'I' as u32]);
+        assert_eq!(flat[FIELD_STRIDE + 1], u32::MAX);
+    }
+}
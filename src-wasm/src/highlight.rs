@@ -0,0 +1,150 @@
+use aho_corasick::MatchKind;
+use serde::{Deserialize, Serialize};
+
+use crate::automata::{Automata, LiteralSet, SpanRegex};
+use crate::error::FilterError;
+use crate::line;
+use crate::term::Term;
+
+/// JS `LogHighlight` 중 매칭에 필요한 필드 (id/color 는 UI 쪽에서 인덱스로 찾아 씁니다)
+#[derive(Serialize, Deserialize, Default, Clone, Debug)]
+#[serde(default)]
+pub struct HighlightSpec {
+    pub keyword: String,
+}
+
+/// 하이라이트 구간 (바이트 오프셋, `id` = highlights 배열 인덱스)
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+    pub id: u32,
+}
+
+/// 하이라이트 키워드 전체를 한 번에 찾는 매처
+///
+/// 겹치는 구간은 우선순위(= highlights 배열 순서, 앞쪽이 우선)로 정리합니다.
+/// 우선순위가 낮은 구간이 이미 잡힌 구간과 겹치면 통째로 버립니다.
+pub struct HighlightSet {
+    ac: Option<LiteralSet>,
+    /// AC 패턴 id -> highlight 인덱스
+    ac_ids: Vec<u32>,
+    regexes: Vec<(SpanRegex, u32)>,
+}
+
+impl HighlightSet {
+    pub fn build(highlights: &[HighlightSpec], case_sensitive: bool, automata: &mut Automata) -> Result<Option<Self>, FilterError> {
+        let mut literals: Vec<String> = Vec::new();
+        let mut ac_ids: Vec<u32> = Vec::new();
+        let mut regexes: Vec<(SpanRegex, u32)> = Vec::new();
+
+        for (i, h) in highlights.iter().enumerate() {
+            match Term::parse(&h.keyword, case_sensitive) {
+                Some(Term::Literal(s)) => {
+                    literals.push(s);
+                    ac_ids.push(i as u32);
+                }
+                Some(Term::Regex(pattern)) => regexes.push((automata.span_regex(&pattern, case_sensitive)?, i as u32)),
+                // 필드 조건은 구간이 아니라 판정용
+                Some(Term::Field(_)) | None => {}
+            }
+        }
+
+        if literals.is_empty() && regexes.is_empty() {
+            return Ok(None);
+        }
+
+        let ac = automata.literals(&literals, MatchKind::Standard, case_sensitive)?;
+        Ok(Some(HighlightSet { ac, ac_ids, regexes }))
+    }
+
+    /// 라인의 하이라이트 구간을 시작 위치 순으로 `out` 에 채웁니다. (바이트 오프셋)
+    pub fn find_spans(&self, line: &[u8], out: &mut Vec<Span>) {
+        out.clear();
+        let mut candidates: Vec<Span> = Vec::new();
+
+        if let Some(ac) = &self.ac {
+            ac.for_each_overlapping(line, |pattern, start, end| {
+                candidates.push(Span { start, end, id: self.ac_ids[pattern] });
+                false
+            });
+        }
+        for (re, id) in &self.regexes {
+            re.for_each_match(line, |start, end| {
+                if start < end {
+                    candidates.push(Span { start, end, id: *id });
+                }
+            });
+        }
+
+        // 우선순위 -> 앞쪽 -> 긴 것 순으로 채택
+        candidates.sort_unstable_by_key(|s| (s.id, s.start, std::cmp::Reverse(s.end)));
+        for c in candidates {
+            let pos = out.partition_point(|s| s.start < c.start);
+            let overlaps_prev = pos > 0 && out[pos - 1].end > c.start;
+            let overlaps_next = pos < out.len() && out[pos].start < c.end;
+            if !overlaps_prev && !overlaps_next {
+                out.insert(pos, c);
+            }
+        }
+    }
+}
+
+/// 정렬된 바이트 구간을 UTF-16 오프셋으로 변환 (React 에서 `text.slice()` 로 바로 쓰도록)
+///
+/// 규칙은 `line::utf16_offsets` 와 같습니다. (잘못된 UTF-8 바이트열은 U+FFFD 한 글자)
+pub fn spans_to_utf16(line: &[u8], spans: &mut [Span], map: &mut Vec<usize>) {
+    if line.is_ascii() {
+        return;
+    }
+    line::utf16_offsets(line, map);
+    for span in spans.iter_mut() {
+        span.start = map[span.start];
+        span.end = map[span.end];
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn set(keywords: &[&str], case_sensitive: bool) -> HighlightSet {
+        let specs: Vec<HighlightSpec> = keywords.iter().map(|k| HighlightSpec { keyword: k.to_string() }).collect();
+        HighlightSet::build(&specs, case_sensitive, &mut Automata::compile()).unwrap().expect("non-empty highlights")
+    }
+
+    fn spans(set: &HighlightSet, line: &str) -> Vec<(usize, usize, u32)> {
+        let mut out = Vec::new();
+        set.find_spans(line.as_bytes(), &mut out);
+        out.iter().map(|s| (s.start, s.end, s.id)).collect()
+    }
+
+    #[test]
+    fn earlier_highlight_wins_overlaps() {
+        let s = set(&["timeout", "time", "out"], false);
+        assert_eq!(spans(&s, "TIMEOUT and time"), [(0, 7, 0), (12, 16, 1)]);
+        let s = set(&["out", "timeout"], false);
+        assert_eq!(spans(&s, "timeout"), [(4, 7, 0)]);
+    }
+
+    #[test]
+    fn regex_and_literal_spans_are_sorted_by_start() {
+        let s = set(&[r"re:\d+", "pid", "re:x*"], true);
+        assert_eq!(spans(&s, "pid=42 pid"), [(0, 3, 1), (4, 6, 0), (7, 10, 1)]);
+        assert!(HighlightSet::build(&[HighlightSpec { keyword: "field:pid:1".into() }], true, &mut Automata::compile()).unwrap().is_none());
+    }
+
+    #[test]
+    fn utf16_offsets() {
+        let line = "가😀 err".as_bytes();
+        let mut out = vec![Span { start: 8, end: 11, id: 0 }];
+        spans_to_utf16(line, &mut out, &mut Vec::new());
+        assert_eq!((out[0].start, out[0].end), (4, 7));
+
+        // 잘못된 바이트열(`\xE2\x82`)은 lossy 변환처럼 한 글자
+        let line = b"\xE2\x82 err";
+        let mut out = vec![Span { start: 3, end: 6, id: 0 }];
+        spans_to_utf16(line, &mut out, &mut Vec::new());
+        assert_eq!((out[0].start, out[0].end), (2, 5));
+    }
+}
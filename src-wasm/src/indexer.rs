@@ -0,0 +1,157 @@
+use memchr::memchr_iter;
+use std::ops::Range;
+
+/// 라인 시작 오프셋 인덱스 (64-bit, 4GB 넘는 파일용)
+///
+/// `append` 로 청크를 순서대로 넣으면 SIMD(`memchr`) 로 `\n` 을 찾아 다음 라인 시작 오프셋을 기록합니다.
+/// 스트리밍처럼 청크가 계속 들어와도 되고, 청크 경계에 걸친 `\r\n` 도 처리합니다.
+/// `starts` 에는 아직 끝나지 않은 마지막 라인의 시작 오프셋도 들어 있습니다.
+#[derive(Clone, Debug)]
+pub struct LineIndex {
+    starts: Vec<u64>,
+    /// 라인 i 가 `\r\n` 으로 끝나면 bit i = 1
+    crlf: Vec<u64>,
+    byte_len: u64,
+    last_was_cr: bool,
+}
+
+impl Default for LineIndex {
+    fn default() -> Self {
+        LineIndex { starts: vec![0], crlf: Vec::new(), byte_len: 0, last_was_cr: false }
+    }
+}
+
+impl LineIndex {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 예상 라인 수만큼 미리 확보 (큰 파일을 열 때 재할당 방지)
+    pub fn reserve(&mut self, lines: usize) {
+        self.starts.reserve(lines);
+        self.crlf.reserve(lines / 64 + 1);
+    }
+
+    /// 파일의 다음 청크를 추가합니다.
+    pub fn append(&mut self, chunk: &[u8]) {
+        if chunk.is_empty() {
+            return;
+        }
+        for pos in memchr_iter(b'\n', chunk) {
+            let cr = if pos == 0 { self.last_was_cr } else { chunk[pos - 1] == b'\r' };
+            if cr {
+                let line = self.starts.len() - 1;
+                self.set_crlf(line);
+            }
+            self.starts.push(self.byte_len + pos as u64 + 1);
+        }
+        self.last_was_cr = chunk[chunk.len() - 1] == b'\r';
+        self.byte_len += chunk.len() as u64;
+    }
+
+    fn set_crlf(&mut self, line: usize) {
+        let word = line / 64;
+        if self.crlf.len() <= word {
+            self.crlf.resize(word + 1, 0);
+        }
+        self.crlf[word] |= 1 << (line % 64);
+    }
+
+    fn is_crlf(&self, line: usize) -> bool {
+        self.crlf.get(line / 64).is_some_and(|w| w & (1 << (line % 64)) != 0)
+    }
+
+    /// 라인 수. 파일이 `\n` 으로 끝나면 그 뒤의 빈 라인은 세지 않습니다. (`for_each_line` 과 동일)
+    pub fn line_count(&self) -> usize {
+        let open = *self.starts.last().expect("starts is never empty");
+        if open == self.byte_len { self.starts.len() - 1 } else { self.starts.len() }
+    }
+
+    pub fn byte_len(&self) -> u64 {
+        self.byte_len
+    }
+
+    /// 라인 시작 오프셋 (마지막 원소는 열려 있는 마지막 라인의 시작 = 끝났으면 `byte_len`)
+    pub fn starts(&self) -> &[u64] {
+        &self.starts
+    }
+
+    /// 라인 i 의 바이트 구간 (`\n` / `\r\n` 제외)
+    pub fn line_range(&self, line: usize) -> Option<Range<u64>> {
+        if line >= self.line_count() {
+            return None;
+        }
+        let start = self.starts[line];
+        let end = match self.starts.get(line + 1) {
+            Some(&next) => next - 1 - self.is_crlf(line) as u64,
+            // 아직 `\n` 이 안 나온 마지막 라인
+            None => self.byte_len - self.last_was_cr as u64,
+        };
+        Some(start..end)
+    }
+
+    /// `first` 부터 `count` 개 라인을 담은 구간 (끝 `\n` 포함) - 파일에서 잘라 `filter_chunk` 로 넘길 때 사용
+    pub fn chunk_range(&self, first: usize, count: usize) -> Range<u64> {
+        let last = first.saturating_add(count).min(self.line_count());
+        let first = first.min(last);
+        // 파일이 `\n` 으로 끝나지 않으면 `starts[line_count()]` 가 없으므로 `byte_len` 으로
+        let start = self.starts.get(first).copied().unwrap_or(self.byte_len);
+        let end = self.starts.get(last).copied().unwrap_or(self.byte_len);
+        start..end
+    }
+
+    /// `chunk_range` 청크 안에서의 라인 시작 오프셋 (`filter_chunk` 의 `line_offsets`)
+    ///
+    /// 청크 하나가 4GB 를 넘으면 안 됩니다.
+    pub fn chunk_offsets(&self, first: usize, count: usize) -> Vec<u32> {
+        let last = first.saturating_add(count).min(self.line_count());
+        if first >= last {
+            return Vec::new();
+        }
+        let base = self.starts[first];
+        self.starts[first..last].iter().map(|&s| (s - base) as u32).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn index(chunks: &[&[u8]]) -> LineIndex {
+        let mut index = LineIndex::new();
+        chunks.iter().for_each(|c| index.append(c));
+        index
+    }
+
+    #[test]
+    fn crlf_split_across_chunks() {
+        let index = index(&[b"a\r", b"\nbb\n", b"ccc\r\n"]);
+        assert_eq!(index.line_count(), 3);
+        assert_eq!(index.line_range(0), Some(0..1));
+        assert_eq!(index.line_range(1), Some(3..5));
+        assert_eq!(index.line_range(2), Some(6..9));
+        assert_eq!(index.line_range(3), None);
+    }
+
+    #[test]
+    fn unterminated_last_line_is_counted() {
+        let index = index(&[b"a\nbb\nccc"]);
+        assert_eq!(index.line_count(), 3);
+        assert_eq!(index.line_range(2), Some(5..8));
+        assert_eq!(index.chunk_range(1, 10), 2..8);
+        assert_eq!(index.chunk_offsets(1, 10), [0, 3]);
+    }
+
+    #[test]
+    fn chunks_past_the_end_are_empty() {
+        for index in [index(&[b"a\nbb\nccc"]), index(&[b"a\nbb\nccc\n"]), LineIndex::new()] {
+            let len = index.byte_len();
+            let lines = index.line_count();
+            assert_eq!(index.chunk_range(lines, 5), len..len);
+            assert_eq!(index.chunk_range(lines + 7, 5), len..len);
+            assert_eq!(index.chunk_range(0, usize::MAX).end, len);
+            assert!(index.chunk_offsets(lines, 5).is_empty());
+            assert!(index.chunk_offsets(lines + 7, usize::MAX).is_empty());
+        }
+    }
+}
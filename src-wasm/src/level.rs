@@ -0,0 +1,42 @@
+/// 로그 레벨 (순서 = 심각도)
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum LogLevel {
+    Verbose,
+    Debug,
+    Info,
+    Warn,
+    Error,
+    Fatal,
+}
+
+impl LogLevel {
+    pub fn from_letter(c: u8) -> Option<LogLevel> {
+        match c {
+            b'V' => Some(LogLevel::Verbose),
+            b'D' => Some(LogLevel::Debug),
+            b'I' => Some(LogLevel::Info),
+            b'W' => Some(LogLevel::Warn),
+            b'E' => Some(LogLevel::Error),
+            b'F' => Some(LogLevel::Fatal),
+            _ => None,
+        }
+    }
+
+    pub fn letter(self) -> char {
+        match self {
+            LogLevel::Verbose => 'V',
+            LogLevel::Debug => 'D',
+            LogLevel::Info => 'I',
+            LogLevel::Warn => 'W',
+            LogLevel::Error => 'E',
+            LogLevel::Fatal => 'F',
+        }
+    }
+}
+
+/// JS 로는 레벨 글자(`'E'`) 로 보냅니다.
+impl serde::Serialize for LogLevel {
+    fn serialize<S: serde::Serializer>(&self, s: S) -> Result<S::Ok, S::Error> {
+        s.serialize_char(self.letter())
+    }
+}
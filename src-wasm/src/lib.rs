@@ -1,21 +1,85 @@
 use wasm_bindgen::prelude::*;
-use aho_corasick::{AhoCorasick, AhoCorasickBuilder, MatchKind};
+
+pub mod ansi;
+pub mod archive;
+pub mod automata;
+pub mod blob;
+pub mod combo;
+pub mod encoding;
+pub mod error;
+pub mod fields;
+pub mod highlight;
+pub mod indexer;
+pub mod level;
+pub mod line;
+pub mod multi;
+pub mod ngram;
+pub mod predicate;
+pub mod query;
+pub mod quick;
+pub mod rule;
+pub mod shape;
+pub mod stats;
+pub mod store;
+pub mod term;
+pub mod time;
+pub mod timeline;
+
+use ansi::{AnsiMode, AnsiStyler, StyleSpan};
+use archive::{ArchiveDecoder, ArchiveKind};
+use combo::ComboScratch;
+use encoding::LogDecoder;
+use error::FilterError;
+use highlight::{HighlightSpec, Span};
+use indexer::LineIndex;
+use multi::RuleSet;
+use ngram::TrigramIndex;
+use query::QueryError;
+use quick::QuickFilter;
+use shape::ShapeSpec;
+use stats::RuleStats;
+use store::LineStore;
+use rule::{CompiledRule, RuleSpec};
+use time::{TimeParser, TimeSpec, NO_TIME};
+use timeline::{LineTimes, TimeColumn, TimeRange, TimeWindow};
 
 #[wasm_bindgen]
 pub struct FilterEngine {
-    ac: Option<AhoCorasick>,
-    case_sensitive: bool,
+    spec: RuleSpec,
+    rule: CompiledRule,
+    rule_set: RuleSet,
+    scratch: ComboScratch,
+    spans: Vec<Span>,
+    /// 하이라이트 구간의 UTF-16 변환 표 (재사용 버퍼)
+    utf16_map: Vec<usize>,
     shared_buffer: Vec<u8>,
+    clean_buffer: Vec<u8>,
+    last_line_count: usize,
+    /// `Some` 이면 batch 필터링 중에 통계를 같이 모읍니다.
+    stats: Option<RuleStats>,
 }
 
 #[wasm_bindgen]
 impl FilterEngine {
+    /// `case_sensitive` 는 Happy Combo 쪽 기본값입니다. (Block List 는 `update_excludes` / `update_rule` 에서 지정)
     #[wasm_bindgen(constructor)]
     pub fn new(case_sensitive: bool) -> Self {
+        let spec = RuleSpec {
+            happy_combos_case_sensitive: case_sensitive,
+            ..RuleSpec::default()
+        };
+        let rule = CompiledRule::compile(&spec).expect("empty rule always compiles");
         FilterEngine {
-            ac: None,
-            case_sensitive,
+            spec,
+            rule,
+            rule_set: RuleSet::build(&[]).expect("empty rule set always compiles"),
+            scratch: ComboScratch::default(),
+            spans: Vec::new(),
+            utf16_map: Vec::new(),
             shared_buffer: Vec::with_capacity(1024 * 1024), // 1MB 초기 버퍼
+            clean_buffer: Vec::new(),
+            last_line_count: 0,
+            stats: None,
         }
     }
 
@@ -30,61 +94,1034 @@ impl FilterEngine {
         unsafe { self.shared_buffer.set_len(size); }
     }
 
+    /// 단순 OR 키워드 목록 (각 키워드가 하나짜리 그룹)
+    ///
+    /// 모든 키워드/단어는 `re:` 접두어를 붙이면 정규식으로 취급됩니다. (예: `re:pid=\d+`)
+    /// `re:` 등 접두어로 시작하는 글자를 그대로 찾으려면 앞에 `\` 를 붙입니다. (예: `\re:x`, `term::ESCAPE_PREFIX`)
     pub fn update_keywords(&mut self, keywords: JsValue) -> Result<(), JsValue> {
         let raw_keywords: Vec<String> = serde_wasm_bindgen::from_value(keywords)?;
-        
-        let processed_keywords: Vec<String> = raw_keywords.into_iter()
-            .map(|s| s.trim().to_string())
-            .filter(|s| !s.is_empty())
-            .map(|s| if self.case_sensitive { s } else { s.to_lowercase() })
-            .collect();
-
-        if processed_keywords.is_empty() {
-            self.ac = None;
-            return Ok(());
+        let groups: Vec<Vec<String>> = raw_keywords.into_iter().map(|k| vec![k]).collect();
+        self.set_groups(groups).map_err(filter_error)
+    }
+
+    /// ✅ Happy Combo 전체 구조 (`string[][]`, 바깥 = OR, 안쪽 = AND)
+    pub fn update_groups(&mut self, groups: JsValue) -> Result<(), JsValue> {
+        let groups: Vec<Vec<String>> = serde_wasm_bindgen::from_value(groups)?;
+        self.set_groups(groups).map_err(filter_error)
+    }
+
+    /// ✅ 쿼리 문자열(`(tag:Wifi OR "conn fail") AND NOT level:D`)로 Happy Combo 설정
+    ///
+    /// 파싱 에러는 `{ message, start, end }` (UTF-16 오프셋) 객체로 throw 됩니다.
+    pub fn update_query(&mut self, query: &str) -> Result<(), JsValue> {
+        let compiled = query::compile_query(query).map_err(query_error)?;
+        self.set_groups(compiled.include_groups).map_err(filter_error)
+    }
+
+    /// ✅ Block List (excludes) + 별도의 대소문자 구분 플래그
+    pub fn update_excludes(&mut self, excludes: JsValue, case_sensitive: bool) -> Result<(), JsValue> {
+        let excludes: Vec<String> = serde_wasm_bindgen::from_value(excludes)?;
+        self.set_excludes(excludes, case_sensitive).map_err(filter_error)
+    }
+
+    /// ✅ `LogHighlight[]` (배열 순서 = 우선순위, 앞쪽이 우선)
+    pub fn update_highlights(&mut self, highlights: JsValue, case_sensitive: bool) -> Result<(), JsValue> {
+        let highlights: Vec<HighlightSpec> = serde_wasm_bindgen::from_value(highlights)?;
+        self.set_highlights(highlights, case_sensitive).map_err(filter_error)
+    }
+
+    /// ✅ Quick Filter (`'none' | 'error' | 'exception'`) - 룰과 AND 로 결합됩니다.
+    pub fn update_quick_filter(&mut self, mode: &str) -> Result<(), JsValue> {
+        let mode = QuickFilter::parse(mode).ok_or_else(|| JsValue::from_str(&format!("Unknown quick filter: {}", mode)))?;
+        self.set_quick_filter(mode).map_err(filter_error)
+    }
+
+    /// ✅ 스트림 모드의 쉘 출력 우회 (`checkIsMatch` 의 `bypassShellFilter`)
+    pub fn update_shell_bypass(&mut self, enabled: bool) -> Result<(), JsValue> {
+        self.set_shell_bypass(enabled).map_err(filter_error)
+    }
+
+    /// ✅ 시간 범위 (`{ start?: '10:32:00', end?: '10:35:00', timestamps?: TimestampParser 설정 }`, `undefined` 면 해제)
+    ///
+    /// 키워드 조건과 AND 이고, 타임스탬프 없는 라인은 앞 라인 시각을 따릅니다. 앞 라인 시각은 호출마다 새로 시작하므로
+    /// 청크 첫 라인 앞의 시각은 `filter_chunk` 의 `time_seed` 로 넘기거나(이어 읽을 때는 `last_time()`),
+    /// `TimeIndexer` 가 있으면 `filter_chunk_timed` 로 컬럼 시각을 그대로 쓰면 됩니다. (구간을 건너뛰어도 결과가 같음)
+    pub fn update_time_range(&mut self, range: JsValue) -> Result<(), JsValue> {
+        let range: Option<TimeRange> = if range.is_undefined() || range.is_null() {
+            None
+        } else {
+            Some(serde_wasm_bindgen::from_value(range)?)
+        };
+        self.set_time_range(range).map_err(filter_error)
+    }
+
+    /// 라인 형태 판별 설정: `{ formats?, extraFormats?, allowPrefixes? }`
+    pub fn update_line_shapes(&mut self, config: JsValue) -> Result<(), JsValue> {
+        let shapes: ShapeSpec = serde_wasm_bindgen::from_value(config)?;
+        self.set_line_shapes(shapes).map_err(filter_error)
+    }
+
+    /// ✅ `LogRule` 객체를 통째로 받아 콤보 + 블록리스트를 한 번에 컴파일
+    pub fn update_rule(&mut self, rule: JsValue) -> Result<(), JsValue> {
+        let spec: RuleSpec = serde_wasm_bindgen::from_value(rule)?;
+        self.set_rule(spec).map_err(filter_error)
+    }
+
+    /// ✅ 단어/그룹별 적중 통계 수집 on/off (켜면 이후 `filter_chunk` 들의 통계가 누적됩니다)
+    pub fn set_collect_stats(&mut self, enabled: bool) {
+        self.stats = if enabled { Some(self.rule.new_stats()) } else { None };
+    }
+
+    /// 누적된 통계를 가져오고 0 부터 다시 셉니다.
+    /// `{ lines, blocked, matched, terms: [{ term, hits, firstLine, lastLine }], groups: [{ terms, hits, ... }] }`
+    pub fn take_stats(&mut self) -> Result<JsValue, JsValue> {
+        let stats = self.take_rule_stats().unwrap_or_default();
+        to_js(&stats)
+    }
+
+    /// ✅ 여러 `LogRule[]` 를 동시에 보관 - `match_mask` / `filter_chunk_masks` 용
+    ///
+    /// 마스크가 u32 라서 최대 32개입니다. 넘으면 `Too many rules` 에러를 throw 하므로
+    /// 더 많은 룰은 엔진 여러 개에 32개씩 나눠 넣으세요. 모든 룰의 리터럴 단어는 라인당 한 번만 스캔합니다.
+    pub fn update_rules(&mut self, rules: JsValue) -> Result<(), JsValue> {
+        let specs: Vec<RuleSpec> = serde_wasm_bindgen::from_value(rules)?;
+        self.set_rules(&specs).map_err(filter_error)
+    }
+
+    /// 라인 하나에 대해 `update_rules` 의 룰별 매칭 비트마스크 (bit i = rules[i])
+    pub fn match_mask(&mut self, text: &str) -> u32 {
+        self.rule_set.begin_time(NO_TIME);
+        self.rule_set.mask(text.as_bytes())
+    }
+
+    /// ✅ 청크 한 번 스캔으로 라인별 비트마스크 (`Uint32Array`, 길이 = 라인 수, `time_seed` 는 `filter_chunk` 와 같음)
+    pub fn filter_chunk_masks(&mut self, data: &[u8], line_offsets: Option<Box<[u32]>>, time_seed: Option<f64>) -> Vec<u32> {
+        self.mask_lines(data, line_offsets.as_deref(), time_seed_micros(time_seed))
+    }
+
+    /// ✅ 현재 룰을 바이트 블롭으로 내보냅니다. (postMessage 로 transfer 해서 sub-worker 에서 `from_blob`)
+    ///
+    /// 블롭에는 결정화된 DFA 가 실려서 sub-worker 는 오토마톤을 다시 빌드하지 않습니다.
+    /// 내보내는 쪽에서 한 번 DFA 를 만드는 비용이 있으므로 룰이 바뀔 때만 호출하세요.
+    pub fn export_rule(&self) -> Result<Vec<u8>, JsValue> {
+        self.rule_blob().map_err(filter_error)
+    }
+
+    /// `export_rule` 로 만든 블롭으로 엔진 생성 (버전이 다른 블롭은 에러)
+    pub fn from_blob(blob: &[u8]) -> Result<FilterEngine, JsValue> {
+        let mut engine = FilterEngine::new(false);
+        engine.load_rule(blob)?;
+        Ok(engine)
+    }
+
+    /// 기존 엔진(버퍼 유지)에 블롭 룰을 적용
+    pub fn load_rule(&mut self, blob: &[u8]) -> Result<(), JsValue> {
+        self.set_rule_blob(blob).map_err(filter_error)
+    }
+
+    /// 콤보/블록리스트/Quick Filter/시간 범위가 모두 비어 있으면 true (모든 라인 통과)
+    pub fn is_empty(&self) -> bool {
+        self.rule.is_empty()
+    }
+
+    /// ✅ Zero-copy Match: 메모리 복사 없이 버퍼 직접 참조
+    pub fn check_match_ptr(&mut self, len: usize) -> bool {
+        let data = &self.shared_buffer[..len];
+        self.rule.begin_time(NO_TIME);
+        self.rule.is_match(data, &mut self.scratch)
+    }
+
+    /// 라인 하나만 판정 (시간 범위가 있으면 타임스탬프 없는 라인은 탈락)
+    pub fn check_match(&mut self, text: &str) -> bool {
+        self.rule.begin_time(NO_TIME);
+        self.rule.is_match(text.as_bytes(), &mut self.scratch)
+    }
+
+    /// ✅ Batch: 청크 통째로 넣고 매칭된 라인 인덱스(`base_index` + 청크 내 라인 번호)를 한 번에 받습니다.
+    ///
+    /// 라인 분리와 CR/ANSI 제거는 Rust 쪽에서 처리합니다. `line_offsets` 는 청크 내 라인 시작 오프셋(선택).
+    /// `time_seed` 는 시간 범위용으로 청크 바로 앞 라인의 시각 (epoch 마이크로초, 없으면 파일 처음처럼 봅니다)
+    pub fn filter_chunk(&mut self, data: &[u8], line_offsets: Option<Box<[u32]>>, base_index: i32, time_seed: Option<f64>) -> Vec<i32> {
+        self.filter_lines_with(data, line_offsets.as_deref(), base_index, LineTimes::Parse(time_seed_micros(time_seed)))
+    }
+
+    /// ✅ `filter_chunk` 와 같지만 라인 시각을 `times` 컬럼에서 라인 인덱스로 찾습니다. (타임스탬프를 다시 읽지 않음)
+    ///
+    /// `TrigramIndexer.candidates` / `window_ranges` 구간처럼 띄엄띄엄 넘겨도, sub-worker 가 나눠 맡아도 결과가 같습니다.
+    /// `times` 는 엔진 룰의 `timestamps` 와 같은 설정이어야 합니다.
+    pub fn filter_chunk_timed(&mut self, data: &[u8], line_offsets: Option<Box<[u32]>>, base_index: i32, times: &TimeIndexer) -> Vec<i32> {
+        self.filter_lines_with(data, line_offsets.as_deref(), base_index, LineTimes::Column(times.column.times()))
+    }
+
+    /// `filter_chunk` 의 Zero-copy 버전: `reserve_buffer` 로 받은 공유 버퍼의 앞 `len` 바이트를 사용
+    pub fn filter_buffer_ptr(&mut self, len: usize, line_offsets: Option<Box<[u32]>>, base_index: i32, time_seed: Option<f64>) -> Vec<i32> {
+        let data = std::mem::take(&mut self.shared_buffer);
+        let times = LineTimes::Parse(time_seed_micros(time_seed));
+        let matches = self.filter_lines_with(&data[..len], line_offsets.as_deref(), base_index, times);
+        self.shared_buffer = data;
+        matches
+    }
+
+    /// 마지막 `filter_chunk` 에서 판정한 마지막 라인의 시각 (다음 청크의 `time_seed`, 시간 범위가 없거나 모르면 `undefined`)
+    pub fn last_time(&self) -> Option<f64> {
+        match self.rule.last_time() {
+            NO_TIME => None,
+            micros => Some(micros as f64),
         }
+    }
+
+    /// ✅ 하이라이트 구간: `[start, end, highlightIndex, ...]` (UTF-16 오프셋, 시작 위치 순, 겹침 없음)
+    pub fn highlight_spans(&mut self, text: &str) -> Vec<u32> {
+        let mut out = Vec::new();
+        self.push_spans(text.as_bytes(), None, &mut out);
+        out
+    }
+
+    /// 여러 라인을 한 번에: `[lineIndex, start, end, highlightIndex, ...]` (라인 분리/정리는 `filter_chunk` 와 동일)
+    pub fn highlight_spans_batch(&mut self, data: &[u8], line_offsets: Option<Box<[u32]>>) -> Vec<u32> {
+        let mut out = Vec::new();
+        let mut clean_buffer = std::mem::take(&mut self.clean_buffer);
+        line::for_each_line(data, line_offsets.as_deref(), |i, raw| {
+            let clean = line::clean_line(raw, &mut clean_buffer);
+            self.push_spans(clean, Some(i as u32), &mut out);
+        });
+        self.clean_buffer = clean_buffer;
+        out
+    }
 
-        // ✅ Lazy DFA 스타일 최적화 (aho-corasick 1.0 기본은 NFA이나, 내부적으로 DFA를 하이브리드로 사용)
-        // 빌더 설정을 명시적으로 하여 검색 속도를 극대화합니다.
-        let ac = AhoCorasickBuilder::new()
-            .match_kind(MatchKind::LeftmostFirst)
-            .prefilter(true)       // SIMD 가속기 등 사용
-            .build(&processed_keywords)
-            .map_err(|e| JsValue::from_str(&format!("AC build error: {}", e)))?;
+    /// 마지막 `filter_chunk` / `filter_buffer_ptr` / `filter_chunk_masks` 호출에서 처리한 라인 수
+    pub fn last_line_count(&self) -> usize {
+        self.last_line_count
+    }
+}
 
-        self.ac = Some(ac);
+impl FilterEngine {
+    /// JS 바인딩 없이도 쓸 수 있는 설정 함수들 (rlib 사용자용)
+    pub fn set_groups(&mut self, groups: Vec<Vec<String>>) -> Result<(), FilterError> {
+        let mut spec = self.spec.clone();
+        spec.include_groups = groups;
+        self.set_rule(spec)
+    }
+
+    pub fn set_excludes(&mut self, excludes: Vec<String>, case_sensitive: bool) -> Result<(), FilterError> {
+        let mut spec = self.spec.clone();
+        spec.excludes = excludes;
+        spec.block_list_case_sensitive = case_sensitive;
+        self.set_rule(spec)
+    }
+
+    pub fn set_rule(&mut self, spec: RuleSpec) -> Result<(), FilterError> {
+        let rule = CompiledRule::compile(&spec)?;
+        self.install_rule(spec, rule);
         Ok(())
     }
 
-    /// ✅ Zero-copy Match: 메모리 복사 없이 버퍼 직접 참조
-    pub fn check_match_ptr(&self, len: usize) -> bool {
-        let ac = match &self.ac {
-            Some(ac) => ac,
-            None => return true,
+    fn install_rule(&mut self, spec: RuleSpec, rule: CompiledRule) {
+        self.rule = rule;
+        self.spec = spec;
+        // 룰이 바뀌면 단어 구성이 달라지므로 통계도 새로
+        if self.stats.is_some() {
+            self.stats = Some(self.rule.new_stats());
+        }
+    }
+
+    pub fn take_rule_stats(&mut self) -> Option<RuleStats> {
+        let fresh = self.stats.as_ref().map(|_| self.rule.new_stats());
+        std::mem::replace(&mut self.stats, fresh)
+    }
+
+    pub fn rule_blob(&self) -> Result<Vec<u8>, FilterError> {
+        blob::encode_rule(&self.spec)
+    }
+
+    pub fn set_rule_blob(&mut self, blob: &[u8]) -> Result<(), FilterError> {
+        let (spec, rule) = blob::decode_rule(blob)?;
+        self.install_rule(spec, rule);
+        Ok(())
+    }
+
+    pub fn rule_spec(&self) -> &RuleSpec {
+        &self.spec
+    }
+
+    pub fn set_rules(&mut self, specs: &[RuleSpec]) -> Result<(), FilterError> {
+        self.rule_set = RuleSet::build(specs)?;
+        Ok(())
+    }
+
+    pub fn mask_lines(&mut self, data: &[u8], line_offsets: Option<&[u32]>, time_seed: i64) -> Vec<u32> {
+        let mut masks = Vec::new();
+        let rule_set = &mut self.rule_set;
+        let clean_buffer = &mut self.clean_buffer;
+        rule_set.begin_time(time_seed);
+
+        line::for_each_line(data, line_offsets, |_, raw| {
+            let clean = line::clean_line(raw, clean_buffer);
+            masks.push(rule_set.mask(clean));
+        });
+
+        self.last_line_count = masks.len();
+        masks
+    }
+
+    pub fn set_highlights(&mut self, highlights: Vec<HighlightSpec>, case_sensitive: bool) -> Result<(), FilterError> {
+        let mut spec = self.spec.clone();
+        spec.highlights = highlights;
+        spec.color_highlights_case_sensitive = case_sensitive;
+        self.set_rule(spec)
+    }
+
+    pub fn set_quick_filter(&mut self, mode: QuickFilter) -> Result<(), FilterError> {
+        let mut spec = self.spec.clone();
+        spec.quick_filter = mode;
+        self.set_rule(spec)
+    }
+
+    pub fn set_shell_bypass(&mut self, enabled: bool) -> Result<(), FilterError> {
+        let mut spec = self.spec.clone();
+        spec.bypass_shell_filter = enabled;
+        self.set_rule(spec)
+    }
+
+    pub fn set_line_shapes(&mut self, shapes: ShapeSpec) -> Result<(), FilterError> {
+        let mut spec = self.spec.clone();
+        spec.line_shapes = shapes;
+        self.set_rule(spec)
+    }
+
+    pub fn set_time_range(&mut self, range: Option<TimeRange>) -> Result<(), FilterError> {
+        let mut spec = self.spec.clone();
+        spec.time_range = range;
+        self.set_rule(spec)
+    }
+
+    pub fn time_window(&self) -> Option<&TimeWindow> {
+        self.rule.time_window()
+    }
+
+    /// 마지막 필터 호출에서 판정한 마지막 라인의 시각 (`LineTimes::Parse` 로 이어 읽을 때)
+    pub fn last_line_time(&self) -> i64 {
+        self.rule.last_time()
+    }
+
+    /// 라인 하나의 하이라이트 구간 (UTF-16 오프셋)
+    pub fn find_highlights(&mut self, line: &[u8]) -> &[Span] {
+        self.spans.clear();
+        if let Some(set) = self.rule.highlights() {
+            set.find_spans(line, &mut self.spans);
+            highlight::spans_to_utf16(line, &mut self.spans, &mut self.utf16_map);
+        }
+        &self.spans
+    }
+
+    fn push_spans(&mut self, line: &[u8], line_index: Option<u32>, out: &mut Vec<u32>) {
+        for span in self.find_highlights(line) {
+            if let Some(i) = line_index {
+                out.push(i);
+            }
+            out.extend([span.start as u32, span.end as u32, span.id]);
+        }
+    }
+
+    /// 시간 범위의 앞 라인 시각 없이(파일 처음처럼) 필터링
+    pub fn filter_lines(&mut self, data: &[u8], line_offsets: Option<&[u32]>, base_index: i32) -> Vec<i32> {
+        self.filter_lines_with(data, line_offsets, base_index, LineTimes::Parse(NO_TIME))
+    }
+
+    pub fn filter_lines_with(&mut self, data: &[u8], line_offsets: Option<&[u32]>, base_index: i32, times: LineTimes) -> Vec<i32> {
+        let mut matches = Vec::new();
+        self.scan_lines(data, line_offsets, base_index, times, |i, _| matches.push(base_index + i as i32));
+        matches
+    }
+
+    /// 청크를 라인 단위로 평가해서 매칭된 라인마다 `(청크 내 라인 번호, 원본 라인)` 으로 콜백합니다.
+    /// 원본 라인은 CR/ANSI 를 지우기 전 바이트(끝의 `\n` 제외)입니다. 반환값은 라인 수.
+    ///
+    /// 시간 범위의 앞 라인 시각은 호출마다 `times` 로 새로 시작하므로 이전 호출 상태가 섞이지 않습니다.
+    pub fn scan_lines<F>(&mut self, data: &[u8], line_offsets: Option<&[u32]>, base_index: i32, times: LineTimes, mut on_match: F) -> usize
+    where
+        F: FnMut(usize, &[u8]),
+    {
+        let mut line_count = 0;
+        let rule = &mut self.rule;
+        let scratch = &mut self.scratch;
+        let clean_buffer = &mut self.clean_buffer;
+        let mut stats = self.stats.as_mut();
+        rule.begin_time(times.seed(base_index));
+
+        line::for_each_line(data, line_offsets, |i, raw| {
+            line_count = i + 1;
+            let clean = line::clean_line(raw, clean_buffer);
+            let line_index = base_index + i as i32;
+            let time = times.get(line_index);
+            let matched = match stats.as_deref_mut() {
+                Some(stats) => rule.is_match_recording(clean, time, scratch, line_index, stats),
+                None => rule.is_match_at(clean, time, scratch),
+            };
+            if matched {
+                on_match(i, raw);
+            }
+        });
+
+        self.last_line_count = line_count;
+        line_count
+    }
+}
+
+/// ✅ 64-bit 라인 인덱서 (`LogIndexer.worker.ts` 의 `Uint32Array` 오프셋 대체)
+///
+/// 파일 청크를 순서대로 `append` 하면 됩니다. (스트리밍 중 추가도 가능)
+/// 오프셋은 `append` 에 넘긴 바이트 기준입니다. `EncodingDecoder` / `ArchiveStream` 을 거친 청크를 넣었다면
+/// 원본 파일이 아니라 변환된 UTF-8 스트림의 오프셋이므로 `file.slice` 에 쓸 수 없고, 변환된 바이트를 따로 보관해서 잘라야 합니다.
+/// 오프셋 배열은 `new BigUint64Array(memory.buffer, offsets_ptr(), offsets_len())` 로 복사 없이 볼 수 있고,
+/// `append` 후에는 메모리가 옮겨질 수 있으니 뷰를 다시 만들어야 합니다.
+#[wasm_bindgen]
+#[derive(Default)]
+pub struct LineIndexer {
+    index: LineIndex,
+}
+
+#[wasm_bindgen]
+impl LineIndexer {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 파일 크기를 알면 미리 라인 수를 추정해서 확보 (평균 라인 길이로 나눈 값)
+    pub fn reserve_lines(&mut self, lines: usize) {
+        self.index.reserve(lines);
+    }
+
+    pub fn append(&mut self, chunk: &[u8]) {
+        self.index.append(chunk);
+    }
+
+    pub fn line_count(&self) -> usize {
+        self.index.line_count()
+    }
+
+    /// 지금까지 넣은 바이트 수 (2^53 까지 정확한 number)
+    pub fn byte_len(&self) -> f64 {
+        self.index.byte_len() as f64
+    }
+
+    /// 라인 시작 오프셋 배열 포인터 (`u64`, 길이 `offsets_len()`)
+    pub fn offsets_ptr(&self) -> *const u64 {
+        self.index.starts().as_ptr()
+    }
+
+    /// 마지막 원소는 열려 있는 마지막 라인의 시작 (파일이 `\n` 으로 끝났으면 `byte_len`)
+    pub fn offsets_len(&self) -> usize {
+        self.index.starts().len()
+    }
+
+    /// 라인 하나의 `[start, end]` 바이트 구간 (`\n` / `\r\n` 제외). 범위 밖이면 빈 배열
+    pub fn line_range(&self, line: usize) -> Vec<f64> {
+        self.index.line_range(line).map_or_else(Vec::new, |r| vec![r.start as f64, r.end as f64])
+    }
+
+    /// `first` 부터 `count` 개 라인을 담은 구간 `[start, end]` - 변환 없이 넣은 파일이면 `file.slice(start, end)` 용
+    pub fn chunk_range(&self, first: usize, count: usize) -> Vec<f64> {
+        let r = self.index.chunk_range(first, count);
+        vec![r.start as f64, r.end as f64]
+    }
+
+    /// `chunk_range` 로 자른 청크의 라인 시작 오프셋 - `FilterEngine.filter_chunk` 의 `line_offsets` 로 그대로 사용
+    pub fn chunk_offsets(&self, first: usize, count: usize) -> Vec<u32> {
+        self.index.chunk_offsets(first, count)
+    }
+
+    pub fn reset(&mut self) {
+        self.index = LineIndex::new();
+    }
+}
+
+/// ✅ 트라이그램 인덱스 - 키워드가 바뀔 때 파일 전체 대신 후보 라인 구간만 다시 필터링
+///
+/// 인덱싱할 때 `LineIndexer` 와 같은 청크를 `append` 하고, 필터를 바꿀 때마다 `candidates(engine)` 로 받은
+/// 구간만 `LineIndexer.chunk_range` / `chunk_offsets` 로 잘라 `filter_chunk` 에 넘기면 됩니다.
+#[wasm_bindgen]
+pub struct TrigramIndexer {
+    index: TrigramIndex,
+}
+
+#[wasm_bindgen]
+impl TrigramIndexer {
+    /// `block_lines`: 포스팅 단위 라인 수 (0 = 기본 64). 크면 인덱스가 작아지고 후보가 넓어집니다.
+    #[wasm_bindgen(constructor)]
+    pub fn new(block_lines: u32) -> Self {
+        TrigramIndexer { index: TrigramIndex::new(block_lines) }
+    }
+
+    pub fn append(&mut self, chunk: &[u8]) {
+        self.index.append(chunk);
+    }
+
+    /// 파일 끝 (`\n` 없이 끝난 마지막 라인 반영)
+    pub fn finish(&mut self) {
+        self.index.finish();
+    }
+
+    pub fn line_count(&self) -> usize {
+        self.index.line_count()
+    }
+
+    /// 저장된 인덱스를 열었을 때 파일에서 이어서 `append` 할 위치
+    pub fn indexed_bytes(&self) -> f64 {
+        self.index.indexed_bytes() as f64
+    }
+
+    pub fn memory_bytes(&self) -> usize {
+        self.index.memory_bytes()
+    }
+
+    /// 엔진의 현재 룰에 매칭될 수 있는 라인 구간: `[firstLine, count, ...]`
+    pub fn candidates(&self, engine: &FilterEngine) -> Vec<u32> {
+        self.index
+            .candidate_ranges(engine.rule_spec())
+            .into_iter()
+            .flat_map(|r| [r.start as u32, r.len() as u32])
+            .collect()
+    }
+
+    /// 로그 파일 옆에 저장할 바이트
+    pub fn to_bytes(&mut self) -> Vec<u8> {
+        self.index.to_bytes()
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<TrigramIndexer, JsValue> {
+        let index = TrigramIndex::from_bytes(bytes).map_err(filter_error)?;
+        Ok(TrigramIndexer { index })
+    }
+}
+
+/// ✅ 스트림 모드 라인 저장소 (LZ4 블록 압축) - `LogStream.worker.ts` 의 `BUFFER_SIZE` 잘라내기 대체
+#[wasm_bindgen]
+pub struct StreamLineStore {
+    store: LineStore,
+}
+
+#[wasm_bindgen]
+impl StreamLineStore {
+    /// `block_bytes`: 압축 블록 하나의 원본 크기 (0 = 기본 256KB)
+    #[wasm_bindgen(constructor)]
+    pub fn new(block_bytes: usize) -> Self {
+        StreamLineStore { store: LineStore::new(block_bytes) }
+    }
+
+    pub fn append(&mut self, chunk: &[u8]) {
+        self.store.append(chunk);
+    }
+
+    /// sdb/serial 에서 문자열로 받은 청크를 그대로 추가
+    pub fn append_text(&mut self, text: &str) {
+        self.store.append(text.as_bytes());
+    }
+
+    pub fn finish(&mut self) {
+        self.store.finish();
+    }
+
+    pub fn line_count(&self) -> usize {
+        self.store.line_count()
+    }
+
+    /// `\n` 까지 들어온 라인 수 - 다음 `filter` 호출의 `first_line`
+    pub fn complete_line_count(&self) -> usize {
+        self.store.complete_line_count()
+    }
+
+    pub fn raw_bytes(&self) -> f64 {
+        self.store.raw_bytes() as f64
+    }
+
+    /// 압축 후 실제로 차지하는 바이트
+    pub fn stored_bytes(&self) -> usize {
+        self.store.stored_bytes()
+    }
+
+    /// `first` 부터 `count` 개 라인 (`\n` 으로 연결, 원본 그대로)
+    pub fn get_lines(&mut self, first: usize, count: usize) -> String {
+        let mut out: Vec<u8> = Vec::new();
+        self.store.for_each_chunk(first..first.saturating_add(count), |_, data| out.extend_from_slice(data));
+        if out.last() == Some(&b'\n') {
+            out.pop();
+        }
+        String::from_utf8_lossy(&out).into_owned()
+    }
+
+    /// `first_line` 이후 라인 중 엔진 룰에 매칭된 라인 번호 (블록을 하나씩 풀면서 필터링)
+    ///
+    /// `\n` 이 아직 안 나온 마지막 라인은 빠집니다. 다음 호출에는 `complete_line_count()` 를, `time_seed` 에는
+    /// `engine.last_time()` 을 넘기세요. (`time_seed` 는 `filter_chunk` 와 같음)
+    pub fn filter(&mut self, engine: &mut FilterEngine, first_line: usize, time_seed: Option<f64>) -> Vec<i32> {
+        self.store.filter(engine, first_line, LineTimes::Parse(time_seed_micros(time_seed)))
+    }
+}
+
+/// ✅ 압축 해제 스트림 (gzip / tar / tar.gz) - 파일 청크를 넣으면 고른 파일의 내용이 나옵니다.
+///
+/// 나온 바이트를 그대로 `LineIndexer.append` / `filter_chunk` 로 넘기면 됩니다.
+/// zip 은 중앙 디렉터리가 파일 끝에 있어서 스트리밍할 수 없으므로 생성자가 에러를 냅니다. (`extract_archive_member` 사용)
+#[wasm_bindgen]
+pub struct ArchiveStream {
+    decoder: ArchiveDecoder,
+}
+
+#[wasm_bindgen]
+impl ArchiveStream {
+    /// `kind`: `detect_archive` 결과, `member`: `list_archive` 의 `index` (gzip 은 0)
+    #[wasm_bindgen(constructor)]
+    pub fn new(kind: &str, member: usize) -> Result<ArchiveStream, JsValue> {
+        let kind = ArchiveKind::parse(kind).ok_or_else(|| JsValue::from_str(&format!("Unknown archive kind: {}", kind)))?;
+        let decoder = ArchiveDecoder::new(kind, Some(member)).map_err(filter_error)?;
+        Ok(ArchiveStream { decoder })
+    }
+
+    pub fn push(&mut self, chunk: &[u8]) -> Result<Vec<u8>, JsValue> {
+        let mut out = Vec::new();
+        self.decoder.push(chunk, &mut |_, data| out.extend_from_slice(data)).map_err(filter_error)?;
+        Ok(out)
+    }
+
+    pub fn finish(&mut self) -> Result<Vec<u8>, JsValue> {
+        let mut out = Vec::new();
+        self.decoder.finish(&mut |_, data| out.extend_from_slice(data)).map_err(filter_error)?;
+        Ok(out)
+    }
+
+    /// 지금까지 지나간 파일 목록 `[{ index, name, size }]` (tar 는 스트림 끝까지 가야 전체 목록)
+    pub fn members(&self) -> Result<JsValue, JsValue> {
+        to_js(&self.decoder.members())
+    }
+}
+
+/// ✅ 매직 바이트로 형식 판별: `'plain' | 'gzip' | 'tar' | 'tar.gz' | 'zip'` (파일 앞 512바이트 이상 권장)
+#[wasm_bindgen]
+pub fn detect_archive(head: &[u8]) -> String {
+    archive::detect(head).as_str().to_string()
+}
+
+/// 아카이브 전체를 받아 파일 목록 `[{ index, name, size }]` - 여러 파일이면 UI 에서 고르게 합니다.
+#[wasm_bindgen]
+pub fn list_archive(data: &[u8]) -> Result<JsValue, JsValue> {
+    to_js(&archive::list_members(data).map_err(filter_error)?)
+}
+
+/// 아카이브 전체에서 파일 하나를 풀어서 반환 (zip 포함 모든 형식)
+///
+/// 풀린 파일 전체를 한 번에 돌려줍니다. zip 은 이 함수로만 열 수 있으므로 멤버 크기(`list_archive` 의 `size`)를
+/// 보고 너무 크면 UI 에서 막아야 합니다. gzip / tar 는 `ArchiveStream` 으로 청크 단위로 푸세요.
+#[wasm_bindgen]
+pub fn extract_archive_member(data: &[u8], member: usize) -> Result<Vec<u8>, JsValue> {
+    let mut out = Vec::new();
+    archive::extract(data, member, &mut |chunk| out.extend_from_slice(chunk)).map_err(filter_error)?;
+    Ok(out)
+}
+
+/// ✅ 타임스탬프 추출 - `utils/logTime.ts` 의 `extractTimestamp` 대체 (라인마다 `Date` 를 만들지 않음)
+#[wasm_bindgen]
+pub struct TimestampParser {
+    parser: TimeParser,
+}
+
+#[wasm_bindgen]
+impl TimestampParser {
+    /// `config`: `{ format: 'auto' | 'YYYY-MM-DD HH:mm:ss.SSS', referenceDate?: '2024-12-31', utcOffsetMinutes,
+    /// yearRolloverDays?: 30, dayRolloverHours?: 12 }`
+    ///
+    /// 로그에 년도가 없으면(`MM-DD HH:mm:ss`) 직전 라인보다 `yearRolloverDays` 일 넘게 거꾸로 갈 때 다음 해로,
+    /// 날짜가 없으면(`HH:mm:ss`) `dayRolloverHours` 시간 넘게 거꾸로 갈 때 다음 날로 넘깁니다. 0 이면 넘기지 않습니다.
+    #[wasm_bindgen(constructor)]
+    pub fn new(config: JsValue) -> Result<TimestampParser, JsValue> {
+        let spec: TimeSpec = if config.is_undefined() || config.is_null() {
+            TimeSpec::default()
+        } else {
+            serde_wasm_bindgen::from_value(config)?
         };
+        Ok(TimestampParser { parser: TimeParser::new(&spec).map_err(filter_error)? })
+    }
 
-        let data = &self.shared_buffer[..len];
-        
-        if self.case_sensitive {
-            ac.find(data).is_some()
+    /// 청크의 라인별 epoch 마이크로초 (`BigInt64Array`, 없으면 `-(2n ** 63n)`, 커널/모노토닉은 부팅 후 경과 시간)
+    ///
+    /// 년도/날짜 넘어감을 따라가므로 청크는 파일 순서대로 넣어야 합니다.
+    pub fn parse_chunk(&mut self, data: &[u8], line_offsets: Option<Box<[u32]>>) -> Vec<i64> {
+        let mut out = Vec::new();
+        self.parser.parse_lines(data, line_offsets.as_deref(), &mut out);
+        out
+    }
+
+    /// 라인 하나 (밀리초, 없으면 `undefined`) - 기존 `extractTimestamp` 와 같은 단위
+    pub fn parse_line(&mut self, line: &str) -> Option<f64> {
+        match self.parser.parse_line(line.as_bytes()) {
+            NO_TIME => None,
+            micros => Some(micros as f64 / 1000.0),
+        }
+    }
+
+    pub fn reset(&mut self) {
+        self.parser.reset();
+    }
+}
+
+/// ✅ 타임스탬프 컬럼 + 히스토그램 - `buildTimestampCache` / `calculateHistogram` 대체
+///
+/// 인덱싱할 때 `LineIndexer` 와 같은 청크를 `append` 하고 파일 끝에서 `finish` 하면, 필터가 바뀔 때마다
+/// `filter_chunk` 결과(`filteredIndices`)로 `histogram` 만 다시 부르면 됩니다. (라인을 다시 읽거나 파싱하지 않음)
+/// 시간은 모두 epoch 마이크로초입니다. (`TimestampParser.parse_chunk` 와 같은 단위)
+#[wasm_bindgen]
+pub struct TimeIndexer {
+    column: TimeColumn,
+}
+
+#[wasm_bindgen]
+impl TimeIndexer {
+    /// `config`: `TimestampParser` 와 같은 설정 (`undefined` 면 auto)
+    #[wasm_bindgen(constructor)]
+    pub fn new(config: JsValue) -> Result<TimeIndexer, JsValue> {
+        let spec: TimeSpec = if config.is_undefined() || config.is_null() {
+            TimeSpec::default()
         } else {
-            // 소문자 변환이 필요한 경우 이 부분에서만 복사 발생 (DFA의 한계)
-            // 대규모 로그에서는 애초에 키워드와 로그를 소문자화해두는 것이 유리합니다.
-            let target = String::from_utf8_lossy(data).to_lowercase();
-            ac.find(target.as_bytes()).is_some()
+            serde_wasm_bindgen::from_value(config)?
+        };
+        Ok(TimeIndexer { column: TimeColumn::new(&spec).map_err(filter_error)? })
+    }
+
+    pub fn reserve_lines(&mut self, lines: usize) {
+        self.column.reserve(lines);
+    }
+
+    pub fn append(&mut self, chunk: &[u8]) {
+        self.column.append(chunk);
+    }
+
+    pub fn finish(&mut self) {
+        self.column.finish();
+    }
+
+    pub fn line_count(&self) -> usize {
+        self.column.line_count()
+    }
+
+    /// 라인별 타임스탬프 포인터 (`new BigInt64Array(memory.buffer, times_ptr(), line_count())`)
+    ///
+    /// 타임스탬프 없는 라인은 앞 라인 값, 첫 타임스탬프 전 라인은 `-(2n ** 63n)`
+    pub fn times_ptr(&self) -> *const i64 {
+        self.column.times().as_ptr()
+    }
+
+    /// 엔진 룰의 시간 범위에 들어가는 라인 구간: `[firstLine, count, ...]` (시간 범위가 없으면 전체 구간 하나)
+    ///
+    /// 시각이 거꾸로 가지 않는 로그는 이분 탐색이라 파일 크기와 상관없이 즉시 나옵니다.
+    /// `TrigramIndexer.candidates` 와 겹치는 구간만 `filter_chunk_timed` 하면 키워드 + 시간 범위 결과가 됩니다.
+    pub fn window_ranges(&self, engine: &FilterEngine) -> Vec<u32> {
+        match engine.time_window() {
+            Some(window) => self.column.window_ranges(window).into_iter().flat_map(|r| [r.start as u32, r.len() as u32]).collect(),
+            None => vec![0, self.column.line_count() as u32],
         }
     }
 
-    pub fn check_match(&self, text: &str) -> bool {
-        let ac = match &self.ac {
-            Some(ac) => ac,
-            None => return true,
+    /// `{ start, end, bucketWidth, counts, levelCounts, firstPositions, maxCount, bucketedCount, totalCount }`
+    ///
+    /// `filtered_indices` 가 없으면 전체 라인. `window_start` / `window_end` 가 없으면 시간이 있는 라인의 최소~최대.
+    /// `levelCounts` 는 칸마다 7개씩 `[없음, V, D, I, W, E, F]` (미니맵에서 에러 구간을 쌓아서 칠할 때)
+    pub fn histogram(
+        &self,
+        filtered_indices: Option<Box<[i32]>>,
+        bucket_count: usize,
+        window_start: Option<f64>,
+        window_end: Option<f64>,
+    ) -> Result<JsValue, JsValue> {
+        let window = window_start.zip(window_end).map(|(start, end)| (start as i64, end as i64));
+        to_js(&self.column.histogram(filtered_indices.as_deref(), bucket_count, window))
+    }
+
+    pub fn reset(&mut self) {
+        self.column.reset();
+    }
+}
+
+/// ✅ 라인 필드 파싱 (dlog / threadtime / 커널 / `File.cs: Func(12)>`) - `extractLogIds`, `extractSourceMetadata` 대체
+///
+/// `{ format, time, level, tag, pid, tid, file, function, codeLine, message }`.
+/// 구간은 `{ start, end }` (UTF-16 오프셋), 값이 있는 필드는 `{ value, range }`.
+#[wasm_bindgen]
+pub fn parse_line_fields(text: &str) -> Result<JsValue, JsValue> {
+    let mut clean_buffer = Vec::new();
+    let line = line::clean_line(text.as_bytes(), &mut clean_buffer);
+    let mut fields = fields::parse_fields(line);
+    fields.to_utf16(line, &mut Vec::new());
+    to_js(&fields)
+}
+
+/// 청크 전체: 라인마다 `fields::FIELD_STRIDE`(22) 개씩 (레이아웃은 `LineFields::push_flat`, 라인 분리/정리는 `filter_chunk` 와 동일)
+#[wasm_bindgen]
+pub fn parse_chunk_fields(data: &[u8], line_offsets: Option<Box<[u32]>>) -> Vec<u32> {
+    let mut out = Vec::new();
+    let mut clean_buffer = Vec::new();
+    let mut map = Vec::new();
+    line::for_each_line(data, line_offsets.as_deref(), |_, raw| {
+        let line = line::clean_line(raw, &mut clean_buffer);
+        let mut fields = fields::parse_fields(line);
+        fields.to_utf16(line, &mut map);
+        fields.push_flat(&mut out);
+    });
+    out
+}
+
+/// ✅ ANSI 이스케이프 처리 - `utils/ansiUtils.ts` 의 `stripAnsi` 대체
+///
+/// `'strip'` 은 이스케이프만 지우고, `'style'` 은 SGR 색상/굵기를 스타일 구간으로 돌려줍니다.
+/// (`filter_chunk` 는 원본 청크를 그대로 넘겨도 내부에서 같은 방식으로 지우고 매칭합니다)
+#[wasm_bindgen]
+pub struct AnsiProcessor {
+    mode: AnsiMode,
+    styler: AnsiStyler,
+    line_spans: Vec<StyleSpan>,
+    spans: Vec<u32>,
+    buffer: Vec<u8>,
+}
+
+#[wasm_bindgen]
+impl AnsiProcessor {
+    /// `mode`: `'strip'` | `'style'`
+    #[wasm_bindgen(constructor)]
+    pub fn new(mode: &str) -> Result<AnsiProcessor, JsValue> {
+        let mode = AnsiMode::parse(mode).ok_or_else(|| JsValue::from_str(&format!("Unknown ANSI mode: {}", mode)))?;
+        Ok(AnsiProcessor { mode, styler: AnsiStyler::new(), line_spans: Vec::new(), spans: Vec::new(), buffer: Vec::new() })
+    }
+
+    /// 청크의 라인들을 정리한 텍스트 (`\n` 으로 연결, 끝의 `\r` 제거). 라인 분리는 `filter_chunk` 와 동일
+    ///
+    /// `'style'` 모드면 스타일 구간을 `take_spans` 로 가져갑니다. SGR 상태는 다음 청크로 이어집니다.
+    pub fn process(&mut self, data: &[u8], line_offsets: Option<Box<[u32]>>) -> String {
+        let mut text = Vec::with_capacity(data.len());
+        let AnsiProcessor { mode, styler, line_spans, spans, buffer } = self;
+        line::for_each_line(data, line_offsets.as_deref(), |i, raw| {
+            if i > 0 {
+                text.push(b'\n');
+            }
+            let raw = raw.strip_suffix(b"\r").unwrap_or(raw);
+            match mode {
+                AnsiMode::Strip => text.extend_from_slice(ansi::strip(raw, buffer)),
+                AnsiMode::Style => {
+                    styler.style_line(raw, &mut text, line_spans);
+                    for span in line_spans.iter() {
+                        spans.extend([
+                            i as u32,
+                            span.start as u32,
+                            span.end as u32,
+                            span.style.fg.to_u32(),
+                            span.style.bg.to_u32(),
+                            span.style.bold as u32,
+                        ]);
+                    }
+                }
+            }
+        });
+        String::from_utf8_lossy(&text).into_owned()
+    }
+
+    /// 지금까지의 스타일 구간 `[lineIndex, start, end, fg, bg, bold, ...]` (UTF-16 오프셋, 색상 인코딩은 `ansi::Color`)
+    pub fn take_spans(&mut self) -> Vec<u32> {
+        std::mem::take(&mut self.spans)
+    }
+
+    /// SGR 상태 초기화 (파일의 다른 위치부터 읽을 때)
+    pub fn reset(&mut self) {
+        self.styler.reset();
+        self.spans.clear();
+    }
+}
+
+/// 문자열 하나에서 ANSI 이스케이프 제거
+#[wasm_bindgen]
+pub fn strip_ansi(text: &str) -> String {
+    let mut buf = Vec::new();
+    String::from_utf8_lossy(ansi::strip(text.as_bytes(), &mut buf)).into_owned()
+}
+
+/// ✅ 텍스트 인코딩 변환 (UTF-16 / CP949(EUC-KR) / Latin-1 → UTF-8) - 인덱싱 전에 청크마다 통과시킵니다.
+#[wasm_bindgen]
+pub struct EncodingDecoder {
+    decoder: LogDecoder,
+}
+
+#[wasm_bindgen]
+impl EncodingDecoder {
+    /// `encoding`: `'auto'` / `undefined` 면 자동 판별, 아니면 직접 지정 (`'cp949'`, `'utf-16le'`, `'latin1'` ...)
+    #[wasm_bindgen(constructor)]
+    pub fn new(encoding: Option<String>) -> Result<EncodingDecoder, JsValue> {
+        let manual = match encoding.as_deref() {
+            None | Some("") | Some("auto") => None,
+            Some(label) => Some(
+                encoding::encoding_for_label(label).ok_or_else(|| JsValue::from_str(&format!("Unknown encoding: {}", label)))?,
+            ),
         };
+        Ok(EncodingDecoder { decoder: LogDecoder::new(manual) })
+    }
 
-        if self.case_sensitive {
-            ac.find(text.as_bytes()).is_some()
-        } else {
-            ac.find(text.to_lowercase().as_bytes()).is_some()
+    /// 청크를 UTF-8 바이트로 변환 (마지막 청크는 `last = true`)
+    pub fn decode(&mut self, chunk: &[u8], last: bool) -> Vec<u8> {
+        let mut out = Vec::with_capacity(chunk.len());
+        self.decoder.decode(chunk, last, &mut out);
+        out
+    }
+
+    /// 선택된 인코딩 `{ encoding: 'EUC-KR', source: 'bom' | 'zeros' | 'utf8' | 'guess' | 'manual' }` (첫 `decode` 전에는 `null`)
+    ///
+    /// ASCII 만 들어온 동안은 임시로 UTF-8 이고, ASCII 가 아닌 바이트가 처음 나오는 청크에서 다시 판별됩니다.
+    pub fn encoding(&self) -> Result<JsValue, JsValue> {
+        to_js(&self.decoder.detected())
+    }
+}
+
+/// 파일 앞부분으로 인코딩만 판별: `{ encoding, source }`
+#[wasm_bindgen]
+pub fn detect_encoding(head: &[u8]) -> Result<JsValue, JsValue> {
+    to_js(&encoding::detect_encoding(head))
+}
+
+/// 쿼리 문자열을 `{ includeGroups }` 로 컴파일 (UI 에서 룰 편집기로 옮길 때 사용)
+#[wasm_bindgen]
+pub fn compile_query(query: &str) -> Result<JsValue, JsValue> {
+    let compiled = query::compile_query(query).map_err(query_error)?;
+    to_js(&compiled)
+}
+
+/// 결과 객체는 Map 이 아닌 일반 객체로, 숫자는 BigInt 가 아닌 number 로 넘깁니다.
+fn to_js<T: serde::Serialize>(value: &T) -> Result<JsValue, JsValue> {
+    Ok(value.serialize(&serde_wasm_bindgen::Serializer::json_compatible())?)
+}
+
+fn query_error(e: QueryError) -> JsValue {
+    to_js(&e).unwrap_or_else(|_| JsValue::from_str(&e.to_string()))
+}
+
+fn filter_error(e: FilterError) -> JsValue {
+    JsValue::from_str(&e.to_string())
+}
+
+/// JS 의 `time_seed` (epoch 마이크로초, `undefined` = 모름)
+fn time_seed_micros(seed: Option<f64>) -> i64 {
+    seed.map_or(NO_TIME, |micros| micros as i64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn filter_lines_returns_global_indices() {
+        let mut engine = FilterEngine::new(false);
+        engine.set_groups(vec![vec!["error".to_string()]]).unwrap();
+        let data = b"ok\nERROR one\r\nfine\n\x1b[31merror\x1b[0m two\n";
+        assert_eq!(engine.filter_lines(data, None, 100), [101, 103]);
+        assert_eq!(engine.last_line_count(), 4);
+        assert_eq!(engine.filter_lines(data, Some(&[0, 3, 14, 19]), 0), [1, 3]);
+    }
+
+    #[test]
+    fn stats_use_global_line_indices_and_reset_on_take() {
+        let mut engine = FilterEngine::new(false);
+        engine.set_groups(vec![vec!["wifi".to_string(), "fail".to_string()], vec!["timeout".to_string()]]).unwrap();
+        engine.set_excludes(vec!["noise".to_string()], false).unwrap();
+        engine.set_collect_stats(true);
+        let data = b"wifi fail
+wifi ok
+noise timeout
+timeout
+";
+        assert_eq!(engine.filter_lines(data, None, 10), [10, 13]);
+
+        let stats = engine.take_rule_stats().unwrap();
+        assert_eq!(stats.lines, 4);
+        assert_eq!((stats.blocked.hits, stats.blocked.first_line), (1, Some(12)));
+        assert_eq!((stats.matched.hits, stats.matched.first_line, stats.matched.last_line), (2, Some(10), Some(13)));
+        let terms: Vec<(&str, u64)> = stats.terms.iter().map(|t| (t.term.as_str(), t.stat.hits)).collect();
+        // 블록된 라인의 단어는 세지 않음
+        assert_eq!(terms, [("wifi", 2), ("fail", 1), ("timeout", 1)]);
+        assert_eq!(stats.groups.iter().map(|g| g.stat.hits).collect::<Vec<_>>(), [1, 1]);
+
+        assert_eq!(engine.take_rule_stats().unwrap().lines, 0);
+        engine.set_collect_stats(false);
+        assert!(engine.take_rule_stats().is_none());
+    }
+
+    #[test]
+    fn empty_engine_matches_every_line() {
+        let mut engine = FilterEngine::new(false);
+        assert!(engine.is_empty());
+        assert_eq!(engine.filter_lines(b"a\nb\n", None, 0), [0, 1]);
+    }
+
+    const TIMED: [&str; 9] = [
+        "01-01 10:00:00.000 I/a: before",
+        "  continued before",
+        "01-01 10:01:00.000 I/a: inside",
+        "  continued inside",
+        "  more inside",
+        "01-01 10:03:00.000 I/a: after",
+        "  continued after",
+        "01-01 10:02:30.000 I/a: inside again",
+        "  continued again",
+    ];
+
+    fn time_range() -> TimeRange {
+        TimeRange { start: Some("10:01".to_string()), end: Some("10:02".to_string()), ..TimeRange::default() }
+    }
+
+    fn timed_chunk(lines: std::ops::Range<usize>) -> Vec<u8> {
+        TIMED[lines].iter().flat_map(|line| [line.as_bytes(), b"\n"].concat()).collect()
+    }
+
+    fn time_column() -> TimeColumn {
+        let mut column = TimeColumn::new(&TimeSpec::default()).unwrap();
+        column.append(&timed_chunk(0..TIMED.len()));
+        column.finish();
+        column
+    }
+
+    #[test]
+    fn time_state_starts_fresh_on_every_call() {
+        let mut engine = FilterEngine::new(false);
+        engine.set_time_range(Some(time_range())).unwrap();
+        assert_eq!(engine.filter_lines(&timed_chunk(0..9), None, 0), [2, 3, 4, 7, 8]);
+        // 앞 호출의 마지막 라인(10:02:30)이 이어지지 않음
+        assert_eq!(engine.filter_lines(&timed_chunk(3..5), None, 3), [] as [i32; 0]);
+
+        let column = time_column();
+        let seed = column.times()[2];
+        assert_eq!(engine.filter_lines_with(&timed_chunk(3..5), None, 3, LineTimes::Parse(seed)), [3, 4]);
+        assert_eq!(engine.last_line_time(), seed);
+        assert_eq!(engine.filter_lines(&timed_chunk(6..7), None, 6), [] as [i32; 0]);
+        assert!(!engine.check_match("  continued inside"));
+
+        engine.set_rules(&[RuleSpec { time_range: Some(time_range()), ..RuleSpec::default() }]).unwrap();
+        assert_eq!(engine.mask_lines(&timed_chunk(3..5), None, NO_TIME), [0, 0]);
+        assert_eq!(engine.mask_lines(&timed_chunk(3..5), None, seed), [1, 1]);
+    }
+
+    #[test]
+    fn column_times_give_the_same_matches_for_any_slice() {
+        let mut engine = FilterEngine::new(false);
+        engine.set_time_range(Some(time_range())).unwrap();
+        let full = engine.filter_lines(&timed_chunk(0..9), None, 0);
+        // sub-worker 는 블롭으로 받은 엔진으로 일부 구간만 맡음
+        let mut worker = FilterEngine::new(false);
+        worker.set_rule_blob(&engine.rule_blob().unwrap()).unwrap();
+
+        let column = time_column();
+        let times = LineTimes::Column(column.times());
+        for lines in [3..5, 7..9, 6..7, 4..8, 0..9] {
+            let expected: Vec<i32> = full.iter().copied().filter(|&i| lines.contains(&(i as usize))).collect();
+            let chunk = timed_chunk(lines.clone());
+            let base = lines.start as i32;
+            assert_eq!(engine.filter_lines_with(&chunk, None, base, times), expected, "{:?}", lines);
+            assert_eq!(worker.filter_lines_with(&chunk, None, base, times), expected, "{:?}", lines);
+            let seeded = LineTimes::Parse(times.seed(base));
+            assert_eq!(worker.filter_lines_with(&chunk, None, base, seeded), expected, "{:?}", lines);
         }
     }
 }
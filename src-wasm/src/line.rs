@@ -0,0 +1,129 @@
+use memchr::memchr;
+
+use crate::ansi;
+
+/// 청크를 라인 단위로 자릅니다.
+///
+/// `offsets` 가 있으면 각 라인의 시작 오프셋으로 사용하고, 없으면 `\n` 으로 직접 자릅니다.
+/// 청크가 `\n` 으로 끝나는 경우 마지막 빈 라인은 세지 않습니다. (JS `split('\n')` + `pop()` 과 동일)
+pub fn for_each_line<'a, F>(data: &'a [u8], offsets: Option<&[u32]>, mut f: F)
+where
+    F: FnMut(usize, &'a [u8]),
+{
+    match offsets {
+        Some(offsets) => {
+            for (i, &start) in offsets.iter().enumerate() {
+                let start = (start as usize).min(data.len());
+                let end = offsets.get(i + 1).map_or(data.len(), |&e| (e as usize).clamp(start, data.len()));
+                let line = &data[start..end];
+                f(i, line.strip_suffix(b"\n").unwrap_or(line));
+            }
+        }
+        None => {
+            let mut start = 0;
+            let mut index = 0;
+            while start < data.len() {
+                let end = memchr(b'\n', &data[start..]).map_or(data.len(), |p| start + p);
+                f(index, &data[start..end]);
+                index += 1;
+                start = end + 1;
+            }
+        }
+    }
+}
+
+/// ANSI 이스케이프(`\x1B[...m`, 커서 이동, OSC 등)와 끝의 `\r` 제거
+///
+/// ESC 가 없는 라인(대부분)은 복사 없이 원본 슬라이스를 그대로 돌려줍니다.
+pub fn clean_line<'a>(line: &'a [u8], buf: &'a mut Vec<u8>) -> &'a [u8] {
+    ansi::strip(line.strip_suffix(b"\r").unwrap_or(line), buf)
+}
+
+/// 바이트 오프셋 → UTF-16 오프셋 표 (`map[i]` = `i` 바이트 앞에서 시작하는 글자들의 UTF-16 길이, 길이 = 라인 + 1)
+///
+/// 잘못된 UTF-8 바이트열은 `String::from_utf8_lossy` / JS `TextDecoder` 처럼 U+FFFD 한 글자(1 unit)입니다.
+/// 글자 중간 오프셋은 그 글자 뒤로 올림합니다. 하이라이트 / 필드 / ANSI 스타일 구간이 모두 이 규칙을 씁니다.
+pub fn utf16_offsets(line: &[u8], map: &mut Vec<usize>) {
+    map.clear();
+    map.reserve(line.len() + 1);
+    let mut units = 0;
+    for_each_char(line, |bytes, width| {
+        map.push(units);
+        units += width;
+        map.extend(std::iter::repeat_n(units, bytes - 1));
+    });
+    map.push(units);
+}
+
+/// 바이트열의 UTF-16 길이 (`utf16_offsets` 와 같은 규칙)
+pub fn utf16_len(s: &[u8]) -> usize {
+    if s.is_ascii() {
+        return s.len();
+    }
+    let mut units = 0;
+    for_each_char(s, |_, width| units += width);
+    units
+}
+
+/// lossy 디코딩 기준 글자마다 `(바이트 수, UTF-16 길이)`
+fn for_each_char(s: &[u8], mut f: impl FnMut(usize, usize)) {
+    for chunk in s.utf8_chunks() {
+        for c in chunk.valid().chars() {
+            f(c.len_utf8(), c.len_utf16());
+        }
+        if !chunk.invalid().is_empty() {
+            f(chunk.invalid().len(), 1);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lines(data: &[u8], offsets: Option<&[u32]>) -> Vec<Vec<u8>> {
+        let mut out = Vec::new();
+        for_each_line(data, offsets, |i, line| {
+            assert_eq!(i, out.len());
+            out.push(line.to_vec());
+        });
+        out
+    }
+
+    #[test]
+    fn splits_on_newline_without_trailing_empty_line() {
+        assert_eq!(lines(b"a\nbb\n", None), [b"a".to_vec(), b"bb".to_vec()]);
+        assert_eq!(lines(b"a\n\nc", None), [b"a".to_vec(), b"".to_vec(), b"c".to_vec()]);
+        assert!(lines(b"", None).is_empty());
+    }
+
+    #[test]
+    fn uses_given_offsets() {
+        assert_eq!(lines(b"a\nbb\nccc", Some(&[0, 2, 5])), [b"a".to_vec(), b"bb".to_vec(), b"ccc".to_vec()]);
+        // 범위를 벗어난 오프셋은 잘라서 사용
+        assert_eq!(lines(b"ab", Some(&[0, 9])), [b"ab".to_vec(), b"".to_vec()]);
+    }
+
+    #[test]
+    fn clean_line_strips_cr_and_ansi() {
+        let mut buf = Vec::new();
+        assert_eq!(clean_line(b"plain\r", &mut buf), b"plain");
+        assert_eq!(clean_line(b"\x1b[31mred\x1b[0m\r", &mut buf), b"red");
+    }
+
+    #[test]
+    fn utf16_offsets_follow_lossy_decoding() {
+        for line in [&b"a\xED\x95\x9C b"[..], b"\xF0\x9F\x98\x80x", b"a\xFFb\xE2\x82c", b"\x80\x80", b"plain"] {
+            let lossy = String::from_utf8_lossy(line);
+            let mut map = Vec::new();
+            utf16_offsets(line, &mut map);
+            assert_eq!(map.len(), line.len() + 1);
+            assert_eq!(map[line.len()], lossy.encode_utf16().count(), "{:?}", lossy);
+            assert_eq!(utf16_len(line), map[line.len()]);
+        }
+        let mut map = Vec::new();
+        // 잘린 3바이트 글자(`\xE2\x82`)는 U+FFFD 하나
+        utf16_offsets(b"a\xE2\x82c", &mut map);
+        assert_eq!(map, [0, 1, 2, 2, 3]);
+    }
+}
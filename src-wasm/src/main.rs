@@ -0,0 +1,513 @@
+//! `happy-filter` CLI - Electron(hidden BrowserWindow) 없이 터미널/빌드 서버에서 로그 필터링
+//!
+//! ```text
+//! happy-filter --rule rule.json [-n] [-c] [-o out.txt] [FILE...]
+//! cat dlog.txt | happy-filter --query '(Wifi OR "conn fail") AND NOT level:D'
+//! happy-filter -q 'level:E' dlog.tar.gz     # 압축/아카이브는 자동으로 풀어서 처리
+//! happy-filter -q Wifi --encoding cp949 old.log  # UTF-16 / CP949 / Latin-1 은 자동 판별, 직접 지정도 가능
+//! ```
+
+use std::fs::File;
+use std::io::{self, BufWriter, Read, Write};
+use std::process::ExitCode;
+
+use happy_filter::archive::{self, ArchiveDecoder, ArchiveKind, Member};
+use happy_filter::encoding::{self, Encoding, LogDecoder};
+use happy_filter::error::FilterError;
+use happy_filter::query::compile_query;
+use happy_filter::quick::QuickFilter;
+use happy_filter::rule::RuleSpec;
+use happy_filter::time::NO_TIME;
+use happy_filter::timeline::LineTimes;
+use happy_filter::FilterEngine;
+
+const USAGE: &str = "\
+Usage: happy-filter [OPTIONS] [FILE...]
+
+Reads log FILEs (or stdin when none or `-`) and prints the lines that match a LogRule.
+.gz, .tar, .tar.gz and .zip inputs are detected from their contents and decompressed on the fly.
+
+Options:
+  -r, --rule <path>       LogRule JSON (includeGroups, excludes, happyCombosCaseSensitive, ...)
+  -q, --query <expr>      Filter expression, replaces the rule's includeGroups
+                          e.g. '(tag:Wifi OR \"conn fail\") AND NOT level:D'
+      --quick <mode>      Quick filter: error | exception
+  -n, --line-number       Prefix each line with its 1-based line number
+  -c, --count             Print only the number of matching lines
+  -o, --output <path>     Write to a file instead of stdout
+  -m, --member <name>     Only read this file (full path or file name) from tar/zip archives
+  -e, --encoding <name>   Input encoding: auto (default), utf-8, utf-16le, utf-16be, cp949, latin1, ...
+  -h, --help              Show this help
+
+Exit status: 0 if any line matched, 1 if none, 2 on error.";
+
+/// 한 번에 읽는 크기
+const CHUNK_SIZE: usize = 8 << 20;
+
+/// 압축/아카이브 판별에 쓰는 앞부분 크기 (tar 헤더 512바이트 + gzip 헤더 여유)
+const DETECT_LEN: usize = 64 * 1024;
+
+#[derive(Default)]
+struct Options {
+    rule: Option<String>,
+    query: Option<String>,
+    quick: Option<QuickFilter>,
+    line_numbers: bool,
+    count: bool,
+    output: Option<String>,
+    member: Option<String>,
+    /// `None` 이면 파일마다 자동 판별
+    encoding: Option<&'static Encoding>,
+    inputs: Vec<String>,
+}
+
+fn parse_args(mut args: impl Iterator<Item = String>) -> Result<Option<Options>, String> {
+    let mut opts = Options::default();
+    while let Some(arg) = args.next() {
+        let mut value = |name: &str| args.next().ok_or_else(|| format!("{} requires a value", name));
+        match arg.as_str() {
+            "-h" | "--help" => return Ok(None),
+            "-r" | "--rule" => opts.rule = Some(value(&arg)?),
+            "-q" | "--query" => opts.query = Some(value(&arg)?),
+            "--quick" => {
+                let mode = value(&arg)?;
+                opts.quick = Some(QuickFilter::parse(&mode).ok_or_else(|| format!("Unknown quick filter: {}", mode))?);
+            }
+            "-n" | "--line-number" => opts.line_numbers = true,
+            "-c" | "--count" => opts.count = true,
+            "-o" | "--output" => opts.output = Some(value(&arg)?),
+            "-m" | "--member" => opts.member = Some(value(&arg)?),
+            "-e" | "--encoding" => {
+                let label = value(&arg)?;
+                opts.encoding = match label.as_str() {
+                    "auto" => None,
+                    _ => Some(encoding::encoding_for_label(&label).ok_or_else(|| format!("unknown encoding: {}", label))?),
+                };
+            }
+            "-" => opts.inputs.push(arg),
+            _ if arg.starts_with('-') => return Err(format!("Unknown option: {}", arg)),
+            _ => opts.inputs.push(arg),
+        }
+    }
+    Ok(Some(opts))
+}
+
+fn load_rule(opts: &Options) -> Result<RuleSpec, String> {
+    let mut spec = match &opts.rule {
+        Some(path) => {
+            let text = std::fs::read_to_string(path).map_err(|e| format!("{}: {}", path, e))?;
+            serde_json::from_str(&text).map_err(|e| format!("{}: invalid LogRule JSON: {}", path, e))?
+        }
+        None => RuleSpec::default(),
+    };
+    if let Some(query) = &opts.query {
+        spec.include_groups = compile_query(query).map_err(|e| format!("--query: {}", e))?.include_groups;
+    }
+    if let Some(quick) = opts.quick {
+        spec.quick_filter = quick;
+    }
+    Ok(spec)
+}
+
+/// 필터링 결과를 쓰는 곳 (모든 입력/파일이 공유)
+struct Output<'a> {
+    engine: &'a mut FilterEngine,
+    out: &'a mut dyn Write,
+    opts: &'a Options,
+}
+
+/// 파일 하나의 청크를 받아 라인 단위로 필터링/출력합니다. (라인 중간에서 잘린 청크는 다음 청크와 이어 붙임)
+///
+/// UTF-8 이 아니면 먼저 UTF-8 로 바꿉니다. (인코딩은 앞부분으로 판별, ASCII 만 나오는 동안은 계속 확인)
+struct LineSink {
+    label: Option<String>,
+    decoder: LogDecoder,
+    decoded: Vec<u8>,
+    carry: Vec<u8>,
+    line_base: u64,
+    matched: u64,
+}
+
+impl LineSink {
+    fn new(label: Option<String>, encoding: Option<&'static Encoding>) -> Self {
+        LineSink {
+            label,
+            decoder: LogDecoder::new(encoding),
+            decoded: Vec::new(),
+            carry: Vec::new(),
+            line_base: 0,
+            matched: 0,
+        }
+    }
+
+    fn push(&mut self, o: &mut Output, data: &[u8]) -> io::Result<()> {
+        let mut decoded = std::mem::take(&mut self.decoded);
+        let data = self.decoder.decode_slice(data, false, &mut decoded);
+        let result = self.push_utf8(o, data);
+        self.decoded = decoded;
+        result
+    }
+
+    fn push_utf8(&mut self, o: &mut Output, data: &[u8]) -> io::Result<()> {
+        // 마지막 줄바꿈까지만 처리하고 나머지는 다음 청크 앞으로
+        let end = match memchr::memrchr(b'\n', data) {
+            Some(p) => p + 1,
+            None => {
+                self.carry.extend_from_slice(data);
+                return Ok(());
+            }
+        };
+        if self.carry.is_empty() {
+            self.scan(o, &data[..end])?;
+        } else {
+            let mut joined = std::mem::take(&mut self.carry);
+            joined.extend_from_slice(&data[..end]);
+            self.scan(o, &joined)?;
+            joined.clear();
+            self.carry = joined;
+        }
+        self.carry.extend_from_slice(&data[end..]);
+        Ok(())
+    }
+
+    /// 남은 라인까지 처리하고 매칭 라인 수를 돌려줍니다. (`-c` 면 여기서 개수 출력)
+    fn finish(mut self, o: &mut Output) -> io::Result<u64> {
+        // 디코더에 남은 잘린 문자
+        let mut rest = std::mem::take(&mut self.carry);
+        self.decoder.decode(&[], true, &mut rest);
+        self.scan(o, &rest)?;
+        if o.opts.count {
+            match &self.label {
+                Some(label) => writeln!(o.out, "{}:{}", label, self.matched)?,
+                None => writeln!(o.out, "{}", self.matched)?,
+            }
+        }
+        Ok(self.matched)
+    }
+
+    fn scan(&mut self, o: &mut Output, data: &[u8]) -> io::Result<()> {
+        let Output { engine, out, opts } = o;
+        let LineSink { label, line_base, matched, .. } = self;
+        let mut write_error: Option<io::Error> = None;
+        // 청크 사이에서 시간 범위의 앞 라인 시각을 이어받음 (파일 처음은 없음)
+        let times = LineTimes::Parse(if *line_base == 0 { NO_TIME } else { engine.last_line_time() });
+        let lines = engine.scan_lines(data, None, 0, times, |i, raw| {
+            *matched += 1;
+            if opts.count || write_error.is_some() {
+                return;
+            }
+            let result = (|| {
+                if let Some(label) = label {
+                    write!(out, "{}:", label)?;
+                }
+                if opts.line_numbers {
+                    write!(out, "{}:", *line_base + i as u64 + 1)?;
+                }
+                out.write_all(raw)?;
+                out.write_all(b"\n")
+            })();
+            if let Err(e) = result {
+                write_error = Some(e);
+            }
+        });
+        *line_base += lines as u64;
+        write_error.map_or(Ok(()), Err)
+    }
+}
+
+/// 끝까지 청크 단위로 읽어서 `f` 로 넘깁니다.
+fn read_chunks(reader: &mut dyn Read, buf: &mut [u8], mut f: impl FnMut(&[u8]) -> io::Result<()>) -> io::Result<()> {
+    loop {
+        match reader.read(buf) {
+            Ok(0) => return Ok(()),
+            Ok(n) => f(&buf[..n])?,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+/// 형식 판별용 앞부분 (파이프는 조금씩 들어오므로 `DETECT_LEN` 까지 채움)
+fn read_head(reader: &mut dyn Read, buf: &mut [u8]) -> io::Result<usize> {
+    let mut filled = 0;
+    while filled < DETECT_LEN {
+        match reader.read(&mut buf[filled..]) {
+            Ok(0) => break,
+            Ok(n) => filled += n,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(filled)
+}
+
+/// `--member` 가 이름 전체 또는 마지막 경로 조각과 같은지 (파일 하나짜리 gzip 은 `--member` 무시)
+fn member_selected(opts: &Options, kind: ArchiveKind, name: &str) -> bool {
+    match &opts.member {
+        Some(wanted) if kind.is_multi() => name == wanted || name.rsplit('/').next() == Some(wanted.as_str()),
+        _ => true,
+    }
+}
+
+/// 아카이브에 `--member` 로 고른 파일이 없으면 에러 (아무것도 출력하지 않고 끝나지 않도록)
+fn check_member_found<'a>(opts: &Options, kind: ArchiveKind, mut names: impl Iterator<Item = &'a str>) -> io::Result<()> {
+    match &opts.member {
+        Some(wanted) if !names.any(|name| member_selected(opts, kind, name)) => {
+            Err(io::Error::new(io::ErrorKind::NotFound, format!("no such member `{}`", wanted)))
+        }
+        _ => Ok(()),
+    }
+}
+
+/// 입력 하나를 필터링합니다. 압축/아카이브는 매직 바이트로 판별해서 풀면서 처리합니다. 반환값은 매칭 라인 수.
+fn filter_input(o: &mut Output, reader: &mut dyn Read, input: &str, show_names: bool) -> io::Result<u64> {
+    let opts = o.opts;
+    let mut buf = vec![0u8; CHUNK_SIZE];
+    let head_len = read_head(reader, &mut buf)?;
+    let kind = archive::detect(&buf[..head_len]);
+    // 아카이브 안의 파일은 이름을 붙여 출력 (`--member` 로 하나만 고른 경우 제외)
+    let label = |member: &str| match (kind.is_multi() && opts.member.is_none(), show_names) {
+        (true, true) => Some(format!("{}:{}", input, member)),
+        (true, false) => Some(member.to_string()),
+        (false, true) => Some(input.to_string()),
+        (false, false) => None,
+    };
+
+    if kind == ArchiveKind::Plain {
+        let mut sink = LineSink::new(label(""), opts.encoding);
+        sink.push(o, &buf[..head_len])?;
+        read_chunks(reader, &mut buf, |chunk| sink.push(o, chunk))?;
+        return sink.finish(o);
+    }
+
+    let invalid = |e: FilterError| io::Error::new(io::ErrorKind::InvalidData, e.to_string());
+    let mut total = 0;
+    let mut sink_error: Option<io::Error> = None;
+
+    if kind == ArchiveKind::Zip {
+        // 중앙 디렉터리가 끝에 있어서 전체를 읽어야 함
+        let mut data = buf[..head_len].to_vec();
+        reader.read_to_end(&mut data)?;
+        let members = archive::list_members(&data).map_err(invalid)?;
+        check_member_found(opts, kind, members.iter().map(|m| m.name.as_str()))?;
+        for member in members {
+            if !member_selected(opts, kind, &member.name) {
+                continue;
+            }
+            let mut sink = LineSink::new(label(&member.name), opts.encoding);
+            archive::extract(&data, member.index, &mut |chunk| {
+                if sink_error.is_none() {
+                    sink_error = sink.push(o, chunk).err();
+                }
+            })
+            .map_err(invalid)?;
+            if let Some(e) = sink_error.take() {
+                return Err(e);
+            }
+            total += sink.finish(o)?;
+        }
+        return Ok(total);
+    }
+
+    // gzip / tar / tar.gz: 풀리는 대로 파일별 sink 로
+    let mut decoder = ArchiveDecoder::new(kind, None).map_err(invalid)?;
+    let mut current: Option<(usize, LineSink)> = None;
+    let mut on_data = |member: &Member, data: &[u8]| {
+        if sink_error.is_some() || !member_selected(opts, kind, &member.name) {
+            return;
+        }
+        let mut step = || -> io::Result<()> {
+            if current.as_ref().map(|(index, _)| *index) != Some(member.index) {
+                if let Some((_, sink)) = current.take() {
+                    total += sink.finish(o)?;
+                }
+                current = Some((member.index, LineSink::new(label(&member.name), opts.encoding)));
+            }
+            match current.as_mut() {
+                Some((_, sink)) => sink.push(o, data),
+                None => Ok(()),
+            }
+        };
+        sink_error = step().err();
+    };
+
+    decoder.push(&buf[..head_len], &mut on_data).map_err(invalid)?;
+    read_chunks(reader, &mut buf, |chunk| decoder.push(chunk, &mut on_data).map_err(invalid))?;
+    decoder.finish(&mut on_data).map_err(invalid)?;
+    if let Some(e) = sink_error {
+        return Err(e);
+    }
+    if let Some((_, sink)) = current {
+        total += sink.finish(o)?;
+    }
+    check_member_found(opts, kind, decoder.members().iter().map(|m| m.name.as_str()))?;
+    Ok(total)
+}
+
+/// 매칭된 라인이 하나라도 있으면 `Ok(true)`
+fn run(opts: &Options) -> Result<bool, String> {
+    let spec = load_rule(opts)?;
+    let mut engine = FilterEngine::new(false);
+    engine.set_rule(spec).map_err(|e| e.to_string())?;
+
+    let mut out: Box<dyn Write> = match &opts.output {
+        Some(path) => Box::new(BufWriter::new(File::create(path).map_err(|e| format!("{}: {}", path, e))?)),
+        None => Box::new(BufWriter::new(io::stdout().lock())),
+    };
+
+    let inputs: Vec<&str> = if opts.inputs.is_empty() { vec!["-"] } else { opts.inputs.iter().map(|s| s.as_str()).collect() };
+    let show_names = inputs.len() > 1;
+    let mut total: u64 = 0;
+    let mut output = Output { engine: &mut engine, out: &mut out, opts };
+
+    for input in &inputs {
+        let mut reader: Box<dyn Read> = if *input == "-" {
+            Box::new(io::stdin().lock())
+        } else {
+            Box::new(File::open(input).map_err(|e| format!("{}: {}", input, e))?)
+        };
+        let matched = match filter_input(&mut output, &mut reader, input, show_names) {
+            Ok(n) => n,
+            // `| head` 처럼 읽는 쪽이 먼저 닫힌 경우는 정상 종료
+            Err(e) if e.kind() == io::ErrorKind::BrokenPipe => return Ok(true),
+            Err(e) => return Err(format!("{}: {}", input, e)),
+        };
+        total += matched;
+    }
+
+    match out.flush() {
+        Err(e) if e.kind() != io::ErrorKind::BrokenPipe => Err(e.to_string()),
+        _ => Ok(total > 0),
+    }
+}
+
+fn main() -> ExitCode {
+    let opts = match parse_args(std::env::args().skip(1)) {
+        Ok(Some(opts)) => opts,
+        Ok(None) => {
+            println!("{}", USAGE);
+            return ExitCode::SUCCESS;
+        }
+        Err(e) => {
+            eprintln!("happy-filter: {}\n\n{}", e, USAGE);
+            return ExitCode::from(2);
+        }
+    };
+
+    match run(&opts) {
+        Ok(true) => ExitCode::SUCCESS,
+        Ok(false) => ExitCode::from(1),
+        Err(e) => {
+            eprintln!("happy-filter: {}", e);
+            ExitCode::from(2)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    fn args(items: &[&str]) -> impl Iterator<Item = String> {
+        items.iter().map(|s| s.to_string()).collect::<Vec<_>>().into_iter()
+    }
+
+    fn run_input(opts: &Options, data: &[u8]) -> (u64, String) {
+        let mut engine = FilterEngine::new(false);
+        engine.set_rule(load_rule(opts).unwrap()).unwrap();
+        let mut out: Vec<u8> = Vec::new();
+        let mut o = Output { engine: &mut engine, out: &mut out, opts };
+        let matched = filter_input(&mut o, &mut Cursor::new(data), "in.log", false).unwrap();
+        (matched, String::from_utf8(out).unwrap())
+    }
+
+    #[test]
+    fn parses_options_and_inputs() {
+        let opts = parse_args(args(&["-q", "wifi", "--quick", "error", "-n", "-c", "-e", "cp949", "a.log", "-"])).unwrap().unwrap();
+        assert_eq!(opts.query.as_deref(), Some("wifi"));
+        assert_eq!(opts.quick, Some(QuickFilter::Error));
+        assert!(opts.line_numbers && opts.count);
+        assert_eq!(opts.encoding.map(|e| e.name()), Some("EUC-KR"));
+        assert_eq!(opts.inputs, ["a.log", "-"]);
+
+        assert!(parse_args(args(&["--help"])).unwrap().is_none());
+        assert_eq!(parse_args(args(&["-q"])).err().unwrap(), "-q requires a value");
+        assert!(parse_args(args(&["--bogus"])).is_err());
+        assert!(parse_args(args(&["--quick", "warn"])).is_err());
+        assert!(parse_args(args(&["-e", "klingon"])).is_err());
+    }
+
+    #[test]
+    fn filters_plain_input_with_line_numbers() {
+        let opts = parse_args(args(&["-q", "wifi AND NOT ok", "-n"])).unwrap().unwrap();
+        let (matched, out) = run_input(&opts, b"wifi fail\nwifi ok\nnothing\nWIFI down");
+        assert_eq!(matched, 2);
+        assert_eq!(out, "1:wifi fail\n4:WIFI down\n");
+    }
+
+    #[test]
+    fn counts_lines_inside_gzip() {
+        use flate2::{write::GzEncoder, Compression};
+        let mut gz = GzEncoder::new(Vec::new(), Compression::fast());
+        gz.write_all(b"E/Net: boom\nI/Net: fine\nE/Net: again\n").unwrap();
+        let data = gz.finish().unwrap();
+
+        let opts = parse_args(args(&["--quick", "error", "-c"])).unwrap().unwrap();
+        let (matched, out) = run_input(&opts, &data);
+        assert_eq!((matched, out.as_str()), (2, "2\n"));
+    }
+
+    #[test]
+    fn member_is_ignored_for_gzip_and_missing_members_are_errors() {
+        use flate2::{write::GzEncoder, Compression};
+        let mut gz = GzEncoder::new(Vec::new(), Compression::fast());
+        gz.write_all(b"wifi up
+wifi down
+").unwrap();
+        let data = gz.finish().unwrap();
+        let opts = parse_args(args(&["-q", "wifi", "-m", "foo.log", "-c"])).unwrap().unwrap();
+        assert_eq!(run_input(&opts, &data), (2, "2\n".to_string()));
+
+        // 파일 하나짜리 ustar
+        let mut tar = [0u8; 4 * 512];
+        tar[..5].copy_from_slice(b"a.log");
+        tar[124..135].copy_from_slice(b"00000000010");
+        tar[156] = b'0';
+        tar[257..263].copy_from_slice(b"ustar\0");
+        tar[148..156].fill(b' ');
+        let sum: u32 = tar[..512].iter().map(|&b| b as u32).sum();
+        tar[148..155].copy_from_slice(format!("{:06o}\0", sum).as_bytes());
+        tar[512..520].copy_from_slice(b"wifi on\n");
+        assert_eq!(run_input(&parse_args(args(&["-q", "wifi", "-m", "a.log"])).unwrap().unwrap(), &tar), (1, "wifi on\n".to_string()));
+
+        let mut engine = FilterEngine::new(false);
+        let mut out: Vec<u8> = Vec::new();
+        let mut o = Output { engine: &mut engine, out: &mut out, opts: &opts };
+        let err = filter_input(&mut o, &mut Cursor::new(&tar[..]), "in.tar", false).unwrap_err();
+        assert_eq!(err.to_string(), "no such member `foo.log`");
+    }
+
+    #[test]
+    fn joins_lines_split_across_chunks() {
+        let opts = parse_args(args(&["-q", "wifi"])).unwrap().unwrap();
+        let mut engine = FilterEngine::new(false);
+        engine.set_rule(load_rule(&opts).unwrap()).unwrap();
+        let mut out: Vec<u8> = Vec::new();
+        let mut o = Output { engine: &mut engine, out: &mut out, opts: &opts };
+        let mut sink = LineSink::new(None, None);
+        for chunk in [&b"first w"[..], b"ifi line\nsecond", b" line\nlast wi", b"fi"] {
+            sink.push(&mut o, chunk).unwrap();
+        }
+        assert_eq!(sink.finish(&mut o).unwrap(), 2);
+        assert_eq!(String::from_utf8(out).unwrap(), "first wifi line\nlast wifi\n");
+    }
+
+    #[test]
+    fn rule_file_errors_are_reported() {
+        let opts = Options { rule: Some("/nonexistent/rule.json".into()), ..Options::default() };
+        assert!(load_rule(&opts).unwrap_err().starts_with("/nonexistent/rule.json"));
+        let opts = parse_args(args(&["-q", "(unclosed"])).unwrap().unwrap();
+        assert!(load_rule(&opts).unwrap_err().starts_with("--query:"));
+    }
+}
@@ -0,0 +1,186 @@
+use aho_corasick::{AhoCorasick, MatchKind};
+use std::collections::HashMap;
+
+use crate::combo::ComboScratch;
+use crate::error::FilterError;
+use crate::rule::{CompiledRule, RuleSpec};
+use crate::term::build_literals;
+
+/// 비트마스크(u32) 한 칸에 룰 하나
+pub const MAX_RULES: usize = 32;
+
+/// 합친 오토마톤의 단어 하나가 가리키는 곳
+#[derive(Clone, Copy)]
+enum Slot {
+    /// 룰의 Happy Combo 리터럴 id
+    Combo(usize),
+    Block,
+}
+
+struct Target {
+    rule: usize,
+    slot: Slot,
+    /// 대소문자를 구분하는 룰이면 원래 단어 (합친 오토마톤은 ASCII 대소문자를 무시하므로 구간을 다시 비교)
+    exact: Option<Box<[u8]>>,
+}
+
+/// 여러 `LogRule` 을 한꺼번에 평가 (Split view, Analyze Diff, 여러 탭)
+///
+/// 라인 분리/정리는 한 번만 하고, 모든 룰의 Happy Combo / Block List 리터럴을 하나의 Aho-Corasick 으로 합쳐서
+/// 라인당 한 번만 스캔합니다. 적중한 단어는 (룰, 콤보 단어 id 또는 Block List) 로 나눠 각 룰에 기록하고,
+/// 정규식/필드 조건 같은 나머지 판정은 룰마다 합니다.
+pub struct RuleSet {
+    rules: Vec<CompiledRule>,
+    ac: Option<AhoCorasick>,
+    /// 합친 오토마톤의 패턴 id -> 그 단어를 쓰는 룰 자리 목록
+    targets: Vec<Vec<Target>>,
+    /// 룰마다 콤보 적중 기록 (스캔 한 번에 모든 룰을 같이 채움)
+    scratches: Vec<ComboScratch>,
+    block_hits: Vec<bool>,
+    combo_hits: Vec<bool>,
+}
+
+impl RuleSet {
+    pub fn build(specs: &[RuleSpec]) -> Result<Self, FilterError> {
+        if specs.len() > MAX_RULES {
+            return Err(FilterError::TooManyRules(specs.len()));
+        }
+        let rules = specs.iter().map(CompiledRule::compile).collect::<Result<Vec<_>, _>>()?;
+
+        // 같은 단어(ASCII 대소문자 무시)는 패턴 하나로
+        let mut patterns: Vec<String> = Vec::new();
+        let mut ids: HashMap<String, usize> = HashMap::new();
+        let mut targets: Vec<Vec<Target>> = Vec::new();
+        for (rule, compiled) in rules.iter().enumerate() {
+            let combo = compiled.combo_literals().into_iter().flat_map(|(literals, case_sensitive)| {
+                literals.iter().enumerate().map(move |(id, literal)| (literal, case_sensitive, Slot::Combo(id)))
+            });
+            let block = compiled.block_literals().into_iter().flat_map(|(literals, case_sensitive)| {
+                literals.iter().map(move |literal| (literal, case_sensitive, Slot::Block))
+            });
+            for (literal, case_sensitive, slot) in combo.chain(block) {
+                let key = literal.to_ascii_lowercase();
+                let id = *ids.entry(key.clone()).or_insert_with(|| {
+                    patterns.push(key);
+                    targets.push(Vec::new());
+                    patterns.len() - 1
+                });
+                let exact = case_sensitive.then(|| literal.as_bytes().into());
+                targets[id].push(Target { rule, slot, exact });
+            }
+        }
+        let ac = build_literals(&patterns, MatchKind::Standard, false)?;
+
+        Ok(RuleSet {
+            scratches: rules.iter().map(|_| ComboScratch::default()).collect(),
+            block_hits: vec![false; rules.len()],
+            combo_hits: vec![false; rules.len()],
+            rules,
+            ac,
+            targets,
+        })
+    }
+
+    pub fn len(&self) -> usize {
+        self.rules.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    pub fn begin_time(&mut self, seed: i64) {
+        self.rules.iter_mut().for_each(|rule| rule.begin_time(seed));
+    }
+
+    /// bit i = i 번째 룰에 매칭
+    pub fn mask(&mut self, line: &[u8]) -> u32 {
+        let RuleSet { rules, ac, targets, scratches, block_hits, combo_hits } = self;
+        for (i, rule) in rules.iter().enumerate() {
+            if let Some(combos) = rule.combos() {
+                combos.begin(&mut scratches[i]);
+            }
+        }
+        block_hits.fill(false);
+        combo_hits.fill(false);
+
+        if let Some(ac) = ac {
+            for m in ac.find_overlapping_iter(line) {
+                for target in &targets[m.pattern().as_usize()] {
+                    if target.exact.as_deref().is_some_and(|exact| &line[m.start()..m.end()] != exact) {
+                        continue;
+                    }
+                    let rule = target.rule;
+                    match target.slot {
+                        Slot::Block => block_hits[rule] = true,
+                        Slot::Combo(id) => {
+                            if !combo_hits[rule] {
+                                let combos = rules[rule].combos().expect("combo slot belongs to a combo");
+                                combo_hits[rule] = combos.record_hit(id, &mut scratches[rule]);
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        let mut mask = 0;
+        for (i, rule) in rules.iter_mut().enumerate() {
+            if rule.is_match_scanned(line, &mut scratches[i], block_hits[i], combo_hits[i]) {
+                mask |= 1 << i;
+            }
+        }
+        mask
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn strings(items: &[&str]) -> Vec<String> {
+        items.iter().map(|s| s.to_string()).collect()
+    }
+
+    fn specs() -> Vec<RuleSpec> {
+        vec![
+            RuleSpec { include_groups: vec![strings(&["wifi", "fail"])], ..RuleSpec::default() },
+            RuleSpec { include_groups: vec![strings(&["WiFi"])], happy_combos_case_sensitive: true, ..RuleSpec::default() },
+            RuleSpec { excludes: strings(&["wifi"]), ..RuleSpec::default() },
+            RuleSpec { include_groups: vec![strings(&["fail", r"re:code=\d+", "not:retry"])], excludes: strings(&["Noise"]), block_list_case_sensitive: true, ..RuleSpec::default() },
+            RuleSpec::default(),
+        ]
+    }
+
+    #[test]
+    fn one_scan_gives_the_same_masks_as_each_rule() {
+        let mut set = RuleSet::build(&specs()).unwrap();
+        let mut rules: Vec<CompiledRule> = specs().iter().map(|s| CompiledRule::compile(s).unwrap()).collect();
+        let mut scratch = ComboScratch::default();
+        let lines = [
+            "WiFi connect fail code=3",
+            "wifi FAIL",
+            "WIFI ok",
+            "fail code=7 retry",
+            "fail code=7 Noise",
+            "fail code=7 noise",
+            "",
+        ];
+        for line in lines {
+            let expected = rules.iter_mut()
+                .enumerate()
+                .fold(0, |mask, (i, rule)| if rule.is_match(line.as_bytes(), &mut scratch) { mask | 1 << i } else { mask });
+            assert_eq!(set.mask(line.as_bytes()), expected, "{}", line);
+        }
+        assert_eq!(set.mask(b"WiFi connect fail code=3"), 0b11011);
+        assert_eq!(set.mask(b"WIFI ok"), 0b10000);
+    }
+
+    #[test]
+    fn too_many_rules_is_a_descriptive_error() {
+        let err = RuleSet::build(&vec![RuleSpec::default(); MAX_RULES + 1]).err().expect("over the cap");
+        assert!(matches!(err, FilterError::TooManyRules(33)));
+        assert!(err.to_string().contains("max 32"));
+        assert_eq!(RuleSet::build(&vec![RuleSpec::default(); MAX_RULES]).unwrap().len(), MAX_RULES);
+    }
+}
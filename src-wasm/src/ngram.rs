@@ -0,0 +1,540 @@
+use memchr::memchr_iter;
+use regex_syntax::hir::literal::Extractor;
+use regex_syntax::ParserBuilder;
+use std::collections::HashMap;
+use std::hash::{BuildHasherDefault, Hasher};
+use std::ops::Range;
+
+use crate::error::FilterError;
+use crate::line;
+use crate::predicate::FieldPredicate;
+use crate::rule::RuleSpec;
+use crate::term::{split_negation, Term};
+
+/// 인덱스 파일 매직 (`HTRI`)
+const MAGIC: &[u8; 4] = b"HTRI";
+
+pub const INDEX_VERSION: u16 = 1;
+
+/// 포스팅 단위 기본값. 라인 하나하나가 아니라 N 라인 블록 단위로 기록해서 인덱스 크기를 줄입니다.
+/// (후보 블록 안의 라인은 어차피 오토마톤으로 다시 확인하므로 정확도에는 영향이 없음)
+pub const DEFAULT_BLOCK_LINES: u32 = 64;
+
+/// 트라이그램 (3바이트) 포스팅 인덱스
+///
+/// 라인을 ASCII 소문자로 바꾼 뒤 3바이트 조각마다 "이 조각이 나오는 블록" 목록을 varint 델타로 저장합니다.
+/// 룰의 단어에서 반드시 나와야 하는 트라이그램을 뽑아 후보 블록을 좁히고, 실제 판정은 `FilterEngine` 이 합니다.
+/// 소문자 변환은 라인/단어 양쪽에 똑같이 적용되므로 대소문자 구분 여부와 상관없이 후보가 빠지지 않습니다.
+pub struct TrigramIndex {
+    block_lines: u32,
+    postings: HashMap<u32, Posting, BuildHasherDefault<TrigramHasher>>,
+    /// 완결된(끝에 `\n` 이 나온) 라인 수와 그 바이트 수
+    line_count: usize,
+    indexed_bytes: u64,
+    /// 이 라인 번호 이전까지는 포스팅에 모두 반영됨 (이후는 항상 후보)
+    flushed_lines: usize,
+    /// 아직 `\n` 이 안 나온 마지막 라인
+    pending: Vec<u8>,
+    /// 현재 블록에서 이미 나온 트라이그램 (2^24 비트)
+    seen: Vec<u64>,
+    touched: Vec<u32>,
+    clean_buffer: Vec<u8>,
+    lower_buffer: Vec<u8>,
+}
+
+#[derive(Default)]
+struct Posting {
+    /// 블록 번호 델타 (LEB128)
+    data: Vec<u8>,
+    /// 마지막 블록 번호 + 1 (0 = 없음)
+    last: u32,
+    count: u32,
+}
+
+impl Posting {
+    fn push(&mut self, block: u32) {
+        if self.last == block + 1 {
+            return;
+        }
+        let mut delta = block + 1 - self.last;
+        while delta >= 0x80 {
+            self.data.push((delta as u8) | 0x80);
+            delta >>= 7;
+        }
+        self.data.push(delta as u8);
+        self.last = block + 1;
+        self.count += 1;
+    }
+
+    fn blocks(&self) -> Vec<u32> {
+        let mut out = Vec::with_capacity(self.count as usize);
+        let mut prev = 0u32;
+        let mut delta = 0u32;
+        let mut shift = 0;
+        for &b in &self.data {
+            delta |= ((b & 0x7F) as u32) << shift;
+            if b & 0x80 != 0 {
+                shift += 7;
+                continue;
+            }
+            prev += delta;
+            out.push(prev - 1);
+            delta = 0;
+            shift = 0;
+        }
+        out
+    }
+}
+
+/// 트라이그램 키(24bit) 전용 해셔 - SipHash 는 라인 바이트마다 부르기엔 너무 느립니다.
+#[derive(Default)]
+struct TrigramHasher(u64);
+
+impl Hasher for TrigramHasher {
+    fn write(&mut self, bytes: &[u8]) {
+        for &b in bytes {
+            self.0 = (self.0.rotate_left(8) ^ b as u64).wrapping_mul(0x9E37_79B9_7F4A_7C15);
+        }
+    }
+
+    fn write_u32(&mut self, n: u32) {
+        let h = (n as u64).wrapping_mul(0x9E37_79B9_7F4A_7C15);
+        self.0 = h ^ (h >> 32);
+    }
+
+    fn finish(&self) -> u64 {
+        self.0
+    }
+}
+
+/// 후보 블록 집합 (`All` = 좁힐 수 없음)
+enum Candidates {
+    All,
+    Blocks(Vec<u32>),
+}
+
+impl Candidates {
+    fn and(self, other: Candidates) -> Candidates {
+        match (self, other) {
+            (Candidates::All, c) | (c, Candidates::All) => c,
+            (Candidates::Blocks(a), Candidates::Blocks(b)) => Candidates::Blocks(intersect(&a, &b)),
+        }
+    }
+
+    fn or(self, other: Candidates) -> Candidates {
+        match (self, other) {
+            (Candidates::All, _) | (_, Candidates::All) => Candidates::All,
+            (Candidates::Blocks(a), Candidates::Blocks(b)) => Candidates::Blocks(union(&a, &b)),
+        }
+    }
+}
+
+impl TrigramIndex {
+    /// `block_lines` 가 0 이면 `DEFAULT_BLOCK_LINES`
+    pub fn new(block_lines: u32) -> Self {
+        TrigramIndex {
+            block_lines: if block_lines == 0 { DEFAULT_BLOCK_LINES } else { block_lines },
+            postings: HashMap::default(),
+            line_count: 0,
+            indexed_bytes: 0,
+            flushed_lines: 0,
+            pending: Vec::new(),
+            seen: Vec::new(),
+            touched: Vec::new(),
+            clean_buffer: Vec::new(),
+            lower_buffer: Vec::new(),
+        }
+    }
+
+    /// 파일의 다음 청크 (라인 경계와 상관없이 잘라도 됩니다)
+    pub fn append(&mut self, chunk: &[u8]) {
+        let mut start = 0;
+        for pos in memchr_iter(b'\n', chunk) {
+            // 이전 청크에서 넘어온 앞부분도 같이 셉니다.
+            self.indexed_bytes += (self.pending.len() + pos - start) as u64 + 1;
+            if self.pending.is_empty() {
+                self.add_line(&chunk[start..pos]);
+            } else {
+                let mut pending = std::mem::take(&mut self.pending);
+                pending.extend_from_slice(&chunk[start..pos]);
+                self.add_line(&pending);
+                pending.clear();
+                self.pending = pending;
+            }
+            start = pos + 1;
+        }
+        self.pending.extend_from_slice(&chunk[start..]);
+    }
+
+    /// 파일 끝: `\n` 없이 끝난 마지막 라인까지 인덱스에 넣습니다.
+    pub fn finish(&mut self) {
+        if !self.pending.is_empty() {
+            let pending = std::mem::take(&mut self.pending);
+            self.add_line(&pending);
+            self.indexed_bytes += pending.len() as u64;
+        }
+        self.flush();
+    }
+
+    fn add_line(&mut self, raw: &[u8]) {
+        if self.line_count > 0 && self.line_count.is_multiple_of(self.block_lines as usize) {
+            self.flush();
+        }
+        if self.seen.is_empty() {
+            self.seen = vec![0; (1 << 24) / 64];
+        }
+
+        let clean = line::clean_line(raw, &mut self.clean_buffer);
+        self.lower_buffer.clear();
+        self.lower_buffer.extend(clean.iter().map(u8::to_ascii_lowercase));
+        for w in self.lower_buffer.windows(3) {
+            let key = trigram(w);
+            let (word, bit) = (key as usize / 64, 1u64 << (key % 64));
+            if self.seen[word] & bit == 0 {
+                self.seen[word] |= bit;
+                self.touched.push(key);
+            }
+        }
+        self.line_count += 1;
+    }
+
+    /// 현재 블록의 트라이그램을 포스팅에 반영
+    fn flush(&mut self) {
+        if self.line_count == self.flushed_lines {
+            return;
+        }
+        let block = ((self.line_count - 1) / self.block_lines as usize) as u32;
+        for key in self.touched.drain(..) {
+            self.seen[key as usize / 64] &= !(1u64 << (key % 64));
+            self.postings.entry(key).or_default().push(block);
+        }
+        self.flushed_lines = self.line_count;
+    }
+
+    /// 라인 수 (`\n` 이 아직 안 나온 마지막 라인 포함 - `LineIndex::line_count` 와 동일)
+    pub fn line_count(&self) -> usize {
+        self.line_count + !self.pending.is_empty() as usize
+    }
+
+    /// 인덱스에 반영된 바이트 수 (저장한 인덱스를 다시 열 때 파일에서 이어서 `append` 할 위치)
+    pub fn indexed_bytes(&self) -> u64 {
+        self.indexed_bytes
+    }
+
+    /// 포스팅이 차지하는 대략적인 메모리 (바이트)
+    pub fn memory_bytes(&self) -> usize {
+        self.postings.values().map(|p| p.data.capacity() + std::mem::size_of::<Posting>() + 4).sum()
+    }
+
+    /// 룰에 매칭될 수 있는 라인 구간들 (오름차순, 겹침 없음)
+    ///
+    /// 인덱스로 좁힐 수 없는 룰(빈 룰, 짧은 단어, 쉘 출력 우회 등)은 전체 구간 하나를 돌려줍니다.
+    pub fn candidate_ranges(&self, spec: &RuleSpec) -> Vec<Range<usize>> {
+        let total = self.line_count();
+        let blocks = match self.rule_candidates(spec) {
+            Candidates::All => return std::iter::once(0..total).filter(|r| !r.is_empty()).collect(),
+            Candidates::Blocks(blocks) => blocks,
+        };
+
+        let bl = self.block_lines as usize;
+        let mut ranges: Vec<Range<usize>> = Vec::new();
+        let tail = self.flushed_lines..total;
+        let block_ranges = blocks.iter().map(|&b| b as usize * bl..((b as usize + 1) * bl).min(self.flushed_lines));
+        for r in block_ranges.chain(std::iter::once(tail)) {
+            if r.is_empty() {
+                continue;
+            }
+            match ranges.last_mut() {
+                Some(last) if last.end >= r.start => last.end = last.end.max(r.end),
+                _ => ranges.push(r),
+            }
+        }
+        ranges
+    }
+
+    fn rule_candidates(&self, spec: &RuleSpec) -> Candidates {
+        // 강제 포함 접두어 / 쉘 출력은 단어와 상관없이 통과하므로 좁힐 수 없음
+        if spec.bypass_shell_filter {
+            return Candidates::All;
+        }
+        let cs = spec.happy_combos_case_sensitive;
+        let mut result: Option<Candidates> = None;
+        for group in &spec.include_groups {
+            let mut group_result: Option<Candidates> = None;
+            for raw in group {
+                let (negated, raw) = split_negation(raw);
+                let term = match Term::parse(raw, cs) {
+                    Some(term) => term,
+                    None => continue,
+                };
+                let c = if negated { Candidates::All } else { self.term_candidates(&term, cs) };
+                group_result = Some(match group_result {
+                    Some(acc) => acc.and(c),
+                    None => c,
+                });
+            }
+            // 빈 그룹은 `ComboMatcher` 에서도 무시됩니다.
+            if let Some(g) = group_result {
+                result = Some(match result {
+                    Some(acc) => acc.or(g),
+                    None => g,
+                });
+            }
+        }
+        result.unwrap_or(Candidates::All)
+    }
+
+    fn term_candidates(&self, term: &Term, case_sensitive: bool) -> Candidates {
+        match term {
+            Term::Literal(s) => self.literal_candidates(s.as_bytes()),
+            // 필드 값(태그, PID ...)은 라인 어딘가에 그대로 나와야 함
+            Term::Field(text) => match FieldPredicate::parse(text, case_sensitive).ok().and_then(|p| p.required_text(case_sensitive)) {
+                Some(text) => self.literal_candidates(text.as_bytes()),
+                None => Candidates::All,
+            },
+            Term::Regex(pattern) => {
+                let hir = match ParserBuilder::new().case_insensitive(!case_sensitive).utf8(false).build().parse(pattern) {
+                    Ok(hir) => hir,
+                    Err(_) => return Candidates::All,
+                };
+                // 모든 매치는 이 접두어 중 하나로 시작합니다.
+                let seq = Extractor::new().extract(&hir);
+                let literals = match seq.literals() {
+                    Some(literals) if !literals.is_empty() => literals,
+                    _ => return Candidates::All,
+                };
+                let mut result = Candidates::Blocks(Vec::new());
+                for lit in literals {
+                    result = result.or(self.literal_candidates(lit.as_bytes()));
+                    if matches!(result, Candidates::All) {
+                        break;
+                    }
+                }
+                result
+            }
+        }
+    }
+
+    /// 리터럴의 모든 트라이그램이 나온 블록 (짧은 단어는 `All`)
+    fn literal_candidates(&self, literal: &[u8]) -> Candidates {
+        if literal.len() < 3 {
+            return Candidates::All;
+        }
+        let lower: Vec<u8> = literal.iter().map(u8::to_ascii_lowercase).collect();
+        let mut keys: Vec<u32> = lower.windows(3).map(trigram).collect();
+        keys.sort_unstable();
+        keys.dedup();
+
+        let mut postings = Vec::with_capacity(keys.len());
+        for key in keys {
+            match self.postings.get(&key) {
+                Some(p) => postings.push(p),
+                None => return Candidates::Blocks(Vec::new()),
+            }
+        }
+        // 짧은 포스팅부터 교집합
+        postings.sort_unstable_by_key(|p| p.count);
+        let mut blocks = postings[0].blocks();
+        for p in &postings[1..] {
+            if blocks.is_empty() {
+                break;
+            }
+            blocks = intersect(&blocks, &p.blocks());
+        }
+        Candidates::Blocks(blocks)
+    }
+
+    /// 디스크 저장용 바이트 (로그 파일 옆에 두고 다시 열 때 `from_bytes`)
+    ///
+    /// 아직 `\n` 이 안 나온 마지막 라인은 저장되지 않습니다. 다시 열면 `indexed_bytes()` 부터 이어서 `append` 하면 됩니다.
+    pub fn to_bytes(&mut self) -> Vec<u8> {
+        self.flush();
+        let mut keys: Vec<u32> = self.postings.keys().copied().collect();
+        keys.sort_unstable();
+
+        let mut out = Vec::with_capacity(32 + self.memory_bytes());
+        out.extend_from_slice(MAGIC);
+        out.extend_from_slice(&INDEX_VERSION.to_le_bytes());
+        out.extend_from_slice(&self.block_lines.to_le_bytes());
+        out.extend_from_slice(&(self.line_count as u64).to_le_bytes());
+        out.extend_from_slice(&self.indexed_bytes.to_le_bytes());
+        out.extend_from_slice(&(keys.len() as u32).to_le_bytes());
+        for key in keys {
+            let p = &self.postings[&key];
+            out.extend_from_slice(&key.to_le_bytes());
+            out.extend_from_slice(&p.count.to_le_bytes());
+            out.extend_from_slice(&p.last.to_le_bytes());
+            out.extend_from_slice(&(p.data.len() as u32).to_le_bytes());
+            out.extend_from_slice(&p.data);
+        }
+        out
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, FilterError> {
+        let corrupt = || FilterError::Blob("Corrupt trigram index".to_string());
+        if bytes.len() < 6 || &bytes[..4] != MAGIC {
+            return Err(FilterError::Blob("Not a happy-filter trigram index".to_string()));
+        }
+        let version = u16::from_le_bytes([bytes[4], bytes[5]]);
+        if version != INDEX_VERSION {
+            return Err(FilterError::Blob(format!(
+                "Incompatible trigram index version {} (expected {})",
+                version, INDEX_VERSION
+            )));
+        }
+
+        let mut reader = Reader { bytes, pos: 6 };
+        let block_lines = reader.u32().ok_or_else(corrupt)?;
+        let line_count = reader.u64().ok_or_else(corrupt)? as usize;
+        let indexed_bytes = reader.u64().ok_or_else(corrupt)?;
+        let entries = reader.u32().ok_or_else(corrupt)?;
+        if block_lines == 0 {
+            return Err(corrupt());
+        }
+
+        let mut index = TrigramIndex::new(block_lines);
+        index.postings.reserve(entries as usize);
+        for _ in 0..entries {
+            let key = reader.u32().ok_or_else(corrupt)?;
+            let count = reader.u32().ok_or_else(corrupt)?;
+            let last = reader.u32().ok_or_else(corrupt)?;
+            let len = reader.u32().ok_or_else(corrupt)? as usize;
+            let data = reader.take(len).ok_or_else(corrupt)?.to_vec();
+            index.postings.insert(key, Posting { data, last, count });
+        }
+        index.line_count = line_count;
+        index.flushed_lines = line_count;
+        index.indexed_bytes = indexed_bytes;
+        Ok(index)
+    }
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Option<&'a [u8]> {
+        let slice = self.bytes.get(self.pos..self.pos.checked_add(len)?)?;
+        self.pos += len;
+        Some(slice)
+    }
+
+    fn u32(&mut self) -> Option<u32> {
+        Some(u32::from_le_bytes(self.take(4)?.try_into().ok()?))
+    }
+
+    fn u64(&mut self) -> Option<u64> {
+        Some(u64::from_le_bytes(self.take(8)?.try_into().ok()?))
+    }
+}
+
+fn trigram(w: &[u8]) -> u32 {
+    (w[0] as u32) << 16 | (w[1] as u32) << 8 | w[2] as u32
+}
+
+fn intersect(a: &[u32], b: &[u32]) -> Vec<u32> {
+    let mut out = Vec::with_capacity(a.len().min(b.len()));
+    let (mut i, mut j) = (0, 0);
+    while i < a.len() && j < b.len() {
+        match a[i].cmp(&b[j]) {
+            std::cmp::Ordering::Less => i += 1,
+            std::cmp::Ordering::Greater => j += 1,
+            std::cmp::Ordering::Equal => {
+                out.push(a[i]);
+                i += 1;
+                j += 1;
+            }
+        }
+    }
+    out
+}
+
+fn union(a: &[u32], b: &[u32]) -> Vec<u32> {
+    let mut out = Vec::with_capacity(a.len() + b.len());
+    let (mut i, mut j) = (0, 0);
+    while i < a.len() && j < b.len() {
+        match a[i].cmp(&b[j]) {
+            std::cmp::Ordering::Less => {
+                out.push(a[i]);
+                i += 1;
+            }
+            std::cmp::Ordering::Greater => {
+                out.push(b[j]);
+                j += 1;
+            }
+            std::cmp::Ordering::Equal => {
+                out.push(a[i]);
+                i += 1;
+                j += 1;
+            }
+        }
+    }
+    out.extend_from_slice(&a[i..]);
+    out.extend_from_slice(&b[j..]);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LOG: &[u8] = b"alpha WiFi\nbeta\ngamma\ndelta\nwifi fail\nx\ntail wifi";
+
+    fn rule(terms: &[&str]) -> RuleSpec {
+        RuleSpec { include_groups: vec![terms.iter().map(|t| t.to_string()).collect()], ..RuleSpec::default() }
+    }
+
+    fn index() -> TrigramIndex {
+        let mut index = TrigramIndex::new(2);
+        // 라인 중간에서 잘린 청크
+        for chunk in LOG.chunks(7) {
+            index.append(chunk);
+        }
+        index
+    }
+
+    #[test]
+    fn candidates_cover_every_matching_line() {
+        let index = index();
+        assert_eq!(index.line_count(), 7);
+        // 블록 0 + 아직 포스팅에 반영 안 된 블록 2 와 마지막 라인
+        assert_eq!(index.candidate_ranges(&rule(&["wifi"])), vec![0..2, 4..7]);
+        assert_eq!(index.candidate_ranges(&rule(&["wifi", "fail"])), vec![4..7]);
+        assert_eq!(index.candidate_ranges(&rule(&["nowhere"])), vec![4..7]);
+        assert_eq!(index.candidate_ranges(&rule(&["re:fail|gamma"])), vec![2..7]);
+    }
+
+    #[test]
+    fn unnarrowable_rules_return_everything() {
+        let index = index();
+        assert_eq!(index.candidate_ranges(&rule(&["ab"])), vec![0..7]);
+        assert_eq!(index.candidate_ranges(&rule(&["not:wifi"])), vec![0..7]);
+        assert_eq!(index.candidate_ranges(&rule(&["re:.*"])), vec![0..7]);
+        // 인덱스는 ASCII 만 접으므로 `ÄRGER` 태그를 놓치지 않도록
+        assert_eq!(index.candidate_ranges(&rule(&["field:tag:Ärger"])), vec![0..7]);
+        assert_eq!(index.candidate_ranges(&RuleSpec::default()), vec![0..7]);
+        let bypass = RuleSpec { bypass_shell_filter: true, ..rule(&["nowhere"]) };
+        assert_eq!(index.candidate_ranges(&bypass), vec![0..7]);
+    }
+
+    #[test]
+    fn saved_index_resumes_after_the_last_full_line() {
+        let mut index = index();
+        let bytes = index.to_bytes();
+        let mut reopened = TrigramIndex::from_bytes(&bytes).unwrap();
+        assert_eq!(reopened.line_count(), 6);
+        assert_eq!(reopened.indexed_bytes() as usize, LOG.len() - b"tail wifi".len());
+        assert_eq!(reopened.candidate_ranges(&rule(&["wifi"])), vec![0..2, 4..6]);
+
+        reopened.append(&LOG[reopened.indexed_bytes() as usize..]);
+        reopened.finish();
+        index.finish();
+        assert_eq!(reopened.candidate_ranges(&rule(&["tail"])), index.candidate_ranges(&rule(&["tail"])));
+        assert_eq!(reopened.candidate_ranges(&rule(&["tail"])), vec![6..7]);
+
+        assert!(TrigramIndex::from_bytes(b"HFLT").is_err());
+        assert!(TrigramIndex::from_bytes(&bytes[..bytes.len() - 1]).is_err());
+    }
+}
@@ -0,0 +1,180 @@
+use crate::fields::LineFields;
+use crate::level::LogLevel;
+
+/// 레벨 비교 연산 (`level:W` / `level>=W` ...)
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum LevelOp {
+    Eq,
+    Ge,
+    Gt,
+    Le,
+    Lt,
+}
+
+/// 파싱된 헤더 필드에 대한 조건 (`field:` 단어). 본문에 같은 글자가 있어도 헤더 필드만 봅니다.
+///
+/// - `level:W`, `level>=W`, `level<I` ...: 레벨 비교 (레벨이 없는 라인은 불일치)
+/// - `tag:이름`, `file:Player.cs`, `function:OnCreate`: 필드 전체가 값과 정확히 같은지 (대소문자는 Happy Combo 설정).
+///   접두어/부분 일치는 하지 않으므로 `tag:Wifi` 는 `WifiManager` 태그에 맞지 않습니다. (부분 일치는 일반 단어나 `re:` 사용)
+/// - `pid:1234`, `tid:5678`: 숫자 비교
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum FieldPredicate {
+    Level(LevelOp, LogLevel),
+    Tag(String),
+    Pid(u32),
+    Tid(u32),
+    File(String),
+    Function(String),
+}
+
+impl FieldPredicate {
+    /// `field:` 뒤의 본문 (`level>=W`, `tag:WifiManager`) 파싱. 에러는 사용자에게 보여줄 메시지
+    pub fn parse(text: &str, case_sensitive: bool) -> Result<Self, String> {
+        let text = text.trim();
+        let name_len = text.bytes().take_while(u8::is_ascii_alphabetic).count();
+        let (name, rest) = text.split_at(name_len);
+        let name = name.to_ascii_lowercase();
+
+        if name == "level" {
+            let (op, value) = if let Some(v) = rest.strip_prefix(">=") {
+                (LevelOp::Ge, v)
+            } else if let Some(v) = rest.strip_prefix("<=") {
+                (LevelOp::Le, v)
+            } else if let Some(v) = rest.strip_prefix('>') {
+                (LevelOp::Gt, v)
+            } else if let Some(v) = rest.strip_prefix('<') {
+                (LevelOp::Lt, v)
+            } else if let Some(v) = rest.strip_prefix(':').or_else(|| rest.strip_prefix('=')) {
+                (LevelOp::Eq, v)
+            } else {
+                return Err(format!("Expected `:`, `>=`, `<=`, `>` or `<` after `level` in `{}`", text));
+            };
+            let value = value.trim().to_ascii_uppercase();
+            let level = match value.as_bytes() {
+                [c] => LogLevel::from_letter(*c),
+                _ => None,
+            };
+            return match level {
+                Some(level) => Ok(FieldPredicate::Level(op, level)),
+                None => Err(format!("Unknown level `{}` (expected one of V, D, I, W, E, F)", value)),
+            };
+        }
+
+        let value = match rest.strip_prefix(':') {
+            Some(value) if !value.trim().is_empty() => value.trim(),
+            _ => return Err(format!("Missing value in `{}`", text)),
+        };
+        let text_value = || if case_sensitive { value.to_string() } else { value.to_lowercase() };
+        let number = || value.parse::<u32>().map_err(|_| format!("`{}` needs a number, got `{}`", name, value));
+        match name.as_str() {
+            "tag" => Ok(FieldPredicate::Tag(text_value())),
+            "file" => Ok(FieldPredicate::File(text_value())),
+            "function" => Ok(FieldPredicate::Function(text_value())),
+            "pid" => Ok(FieldPredicate::Pid(number()?)),
+            "tid" => Ok(FieldPredicate::Tid(number()?)),
+            _ => Err(format!("Unknown field `{}` (expected level, tag, pid, tid, file or function)", name)),
+        }
+    }
+
+    /// 검색 인덱스로 후보를 좁힐 때 쓰는, 일치하는 라인에 반드시 들어 있는 글자
+    ///
+    /// 인덱스는 ASCII 대소문자만 접으므로, 대소문자를 무시하는 조건의 값에 대소문자가 있는 비 ASCII 글자(`Ä`, `Σ` ...)가
+    /// 있으면 라인의 글자와 바이트가 달라질 수 있어 `None` 입니다. (`case_sensitive` 는 `parse` 에 넘긴 값)
+    pub fn required_text(&self, case_sensitive: bool) -> Option<String> {
+        match self {
+            FieldPredicate::Level(..) => None,
+            FieldPredicate::Tag(s) | FieldPredicate::File(s) | FieldPredicate::Function(s) => {
+                (case_sensitive || !s.chars().any(has_case)).then(|| s.clone())
+            }
+            FieldPredicate::Pid(n) | FieldPredicate::Tid(n) => Some(n.to_string()),
+        }
+    }
+
+    /// `fields` 는 `line` 을 `fields::parse_fields` 로 파싱한 결과
+    pub fn is_match(&self, line: &[u8], fields: &LineFields, case_sensitive: bool) -> bool {
+        let text_eq = |range: &Option<std::ops::Range<usize>>, value: &str| {
+            range.as_ref().is_some_and(|r| {
+                let field = &line[r.clone()];
+                if case_sensitive {
+                    field == value.as_bytes()
+                } else if value.is_ascii() {
+                    field.eq_ignore_ascii_case(value.as_bytes())
+                } else {
+                    // 값은 이미 소문자
+                    String::from_utf8_lossy(field).to_lowercase() == value
+                }
+            })
+        };
+        match self {
+            FieldPredicate::Level(op, level) => fields.level.as_ref().is_some_and(|f| {
+                let actual = f.value;
+                match op {
+                    LevelOp::Eq => actual == *level,
+                    LevelOp::Ge => actual >= *level,
+                    LevelOp::Gt => actual > *level,
+                    LevelOp::Le => actual <= *level,
+                    LevelOp::Lt => actual < *level,
+                }
+            }),
+            FieldPredicate::Tag(value) => text_eq(&fields.tag, value),
+            FieldPredicate::File(value) => text_eq(&fields.file, value),
+            FieldPredicate::Function(value) => text_eq(&fields.function, value),
+            FieldPredicate::Pid(n) => fields.pid.as_ref().is_some_and(|f| f.value == *n),
+            FieldPredicate::Tid(n) => fields.tid.as_ref().is_some_and(|f| f.value == *n),
+        }
+    }
+}
+
+/// 대소문자 구분이 있는 비 ASCII 글자
+fn has_case(c: char) -> bool {
+    !c.is_ascii() && (c.is_lowercase() || c.is_uppercase())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fields::parse_fields;
+
+    fn matches(term: &str, case_sensitive: bool, line: &str) -> bool {
+        let predicate = FieldPredicate::parse(term, case_sensitive).unwrap();
+        predicate.is_match(line.as_bytes(), &parse_fields(line.as_bytes()), case_sensitive)
+    }
+
+    const LINE: &str = "01-01 10:00:00.123 W/WifiManager(P 12, T 34): Ärger Player.cs: Play(3)> started";
+
+    #[test]
+    fn parses_levels_and_reports_errors() {
+        assert_eq!(FieldPredicate::parse("LEVEL>=w", false), Ok(FieldPredicate::Level(LevelOp::Ge, LogLevel::Warn)));
+        assert_eq!(FieldPredicate::parse("level=E", false), Ok(FieldPredicate::Level(LevelOp::Eq, LogLevel::Error)));
+        assert!(FieldPredicate::parse("level~E", false).unwrap_err().contains("Expected"));
+        assert!(FieldPredicate::parse("level:X", false).unwrap_err().contains("Unknown level"));
+        assert!(FieldPredicate::parse("tag:", false).unwrap_err().contains("Missing value"));
+        assert!(FieldPredicate::parse("pid:abc", false).unwrap_err().contains("needs a number"));
+        assert!(FieldPredicate::parse("host:x", false).unwrap_err().contains("Unknown field"));
+    }
+
+    #[test]
+    fn text_fields_are_exact_matches() {
+        assert!(matches("tag:wifimanager", false, LINE));
+        assert!(!matches("tag:wifimanager", true, LINE));
+        assert!(matches("tag:WifiManager", true, LINE));
+        // 접두어/부분 일치는 아님
+        assert!(!matches("tag:Wifi", false, LINE));
+        assert!(matches("file:player.cs", false, LINE));
+        assert!(matches("function:Play", true, LINE));
+        assert!(matches("pid:12", false, LINE) && matches("tid:34", false, LINE) && !matches("pid:34", false, LINE));
+        assert!(matches("level>I", false, LINE) && !matches("level<W", false, LINE));
+        assert!(matches("tag:ärger", false, "I/ÄRGER( 1): x"));
+    }
+
+    #[test]
+    fn required_text_skips_case_folded_non_ascii_values() {
+        let required = |term: &str, cs: bool| FieldPredicate::parse(term, cs).unwrap().required_text(cs);
+        assert_eq!(required("tag:WifiManager", false).as_deref(), Some("wifimanager"));
+        assert_eq!(required("tag:Ä", false), None);
+        assert_eq!(required("tag:Ä", true).as_deref(), Some("Ä"));
+        assert_eq!(required("tag:한글태그", false).as_deref(), Some("한글태그"));
+        assert_eq!(required("pid:42", false).as_deref(), Some("42"));
+        assert_eq!(required("level>=W", false), None);
+    }
+}
@@ -0,0 +1,427 @@
+use serde::Serialize;
+use std::fmt;
+
+use crate::predicate::FieldPredicate;
+use crate::term::{escape_literal, FIELD_PREFIX, NOT_PREFIX, REGEX_PREFIX};
+
+/// 쿼리 하나가 펼쳐질 수 있는 최대 그룹 수 (`(a OR b) AND (c OR d) ...` 의 곱 폭발 방지)
+const MAX_GROUPS: usize = 256;
+
+/// 괄호 / `NOT` 중첩 한도 (재귀 파싱과 DNF 변환이 스택을 넘치지 않도록)
+const MAX_DEPTH: usize = 64;
+
+/// 쿼리 컴파일 결과: UI 가 쓰는 `LogRule` 과 같은 모양
+///
+/// 부정 단어는 그룹 안에 `not:` 접두어로 들어갑니다. (그룹마다 다를 수 있으므로 Block List 로 빼지 않음)
+/// 글자 그대로 찾는 단어 중 `not:`/`re:`/`field:`/`\` 로 시작하는 것은 `\` 로 이스케이프됩니다.
+#[derive(Serialize, Default, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct CompiledQuery {
+    pub include_groups: Vec<Vec<String>>,
+}
+
+/// 파싱 에러. `start`/`end` 는 UTF-16 오프셋이라 UI 에서 그대로 밑줄을 그을 수 있습니다.
+#[derive(Serialize, Clone, Debug, PartialEq, Eq)]
+pub struct QueryError {
+    pub message: String,
+    pub start: usize,
+    pub end: usize,
+}
+
+impl fmt::Display for QueryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} (column {})", self.message, self.start + 1)
+    }
+}
+
+impl std::error::Error for QueryError {}
+
+/// `(tag:NetworkManager OR "conn fail") AND NOT level:D` 같은 쿼리를 Happy Combo 로 컴파일
+///
+/// - 연산자: `AND`, `OR`, `NOT` (대문자만), 괄호, 공백으로 이어 쓰면 AND
+/// - 단어: 공백/괄호 전까지, 또는 `"..."` (안에서 `\"` 로 따옴표)
+/// - 필드 (이름은 대소문자 무시): `re:패턴`, `tag:이름`, `level:V|D|I|W|E|F`, `level>=W`, `pid:1234`, `tid:5678`, `file:Player.cs`,
+///   `function:OnCreate` (그 외 `xxx:yyy` 는 그냥 단어). `re:` 외에는 파싱된 라인 헤더와 비교하는 `field:` 단어가 됩니다.
+/// - `"not: found"`, `"re:x"` 처럼 따옴표로 감싼 단어는 접두어가 있어도 항상 글자 그대로 찾습니다.
+pub fn compile_query(query: &str) -> Result<CompiledQuery, QueryError> {
+    let tokens = tokenize(query)?;
+    let mut parser = Parser { tokens: &tokens, pos: 0, end: utf16_len(query), depth: 0 };
+    if tokens.is_empty() {
+        return Ok(CompiledQuery::default());
+    }
+
+    let expr = parser.parse_or()?;
+    if let Some(tok) = parser.peek() {
+        return Err(tok.error(if tok.kind == TokenKind::RParen { "Unexpected `)`" } else { "Unexpected token" }));
+    }
+
+    let clauses = to_dnf(&expr, false)
+        .ok_or_else(|| QueryError { message: "Query expands to too many combos".to_string(), start: 0, end: parser.end })?;
+
+    let include_groups = clauses.into_iter()
+        .map(|clause| {
+            let mut group: Vec<String> = Vec::new();
+            for lit in clause {
+                let term = if lit.negated { format!("{}{}", NOT_PREFIX, lit.term) } else { lit.term };
+                if !group.contains(&term) {
+                    group.push(term);
+                }
+            }
+            group
+        })
+        .collect();
+
+    Ok(CompiledQuery { include_groups })
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum TokenKind {
+    LParen,
+    RParen,
+    And,
+    Or,
+    Not,
+    /// 엔진 단어로 변환이 끝난 값 (`re:`/`field:` 접두어 또는 `\` 이스케이프 포함 가능)
+    Term(String),
+}
+
+#[derive(Clone, Debug)]
+struct Token {
+    kind: TokenKind,
+    start: usize,
+    end: usize,
+}
+
+impl Token {
+    fn error(&self, message: &str) -> QueryError {
+        QueryError { message: message.to_string(), start: self.start, end: self.end }
+    }
+}
+
+fn utf16_len(s: &str) -> usize {
+    s.chars().map(char::len_utf16).sum()
+}
+
+fn tokenize(query: &str) -> Result<Vec<Token>, QueryError> {
+    let chars: Vec<char> = query.chars().collect();
+    // 글자 인덱스 -> UTF-16 오프셋
+    let mut offsets = Vec::with_capacity(chars.len() + 1);
+    let mut unit = 0;
+    for c in &chars {
+        offsets.push(unit);
+        unit += c.len_utf16();
+    }
+    offsets.push(unit);
+
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        if c.is_whitespace() {
+            i += 1;
+            continue;
+        }
+        let start = i;
+        let kind = match c {
+            '(' => {
+                i += 1;
+                TokenKind::LParen
+            }
+            ')' => {
+                i += 1;
+                TokenKind::RParen
+            }
+            '"' => {
+                let value = read_quoted(&chars, &mut i, &offsets)?;
+                TokenKind::Term(escape_literal(&value))
+            }
+            _ => {
+                while i < chars.len() && !chars[i].is_whitespace() && !matches!(chars[i], '(' | ')' | '"') {
+                    i += 1;
+                }
+                let word: String = chars[start..i].iter().collect();
+                match word.as_str() {
+                    "AND" => TokenKind::And,
+                    "OR" => TokenKind::Or,
+                    "NOT" => TokenKind::Not,
+                    _ => {
+                        // `tag:"Network Manager"` 처럼 필드 뒤에 따옴표 값
+                        let value = if word.ends_with(':') && field_kind(&word[..word.len() - 1]).is_some() && chars.get(i) == Some(&'"') {
+                            let quoted = read_quoted(&chars, &mut i, &offsets)?;
+                            format!("{}{}", word, quoted)
+                        } else {
+                            word
+                        };
+                        let term = field_term(&value).map_err(|message| QueryError { message, start: offsets[start], end: offsets[i] })?;
+                        TokenKind::Term(term)
+                    }
+                }
+            }
+        };
+        tokens.push(Token { kind, start: offsets[start], end: offsets[i] });
+    }
+    Ok(tokens)
+}
+
+/// `"` 에서 시작해 닫는 `"` 다음까지 읽습니다. `\"` 만 이스케이프로 처리 (정규식의 `\d` 등은 그대로)
+fn read_quoted(chars: &[char], i: &mut usize, offsets: &[usize]) -> Result<String, QueryError> {
+    let start = *i;
+    *i += 1;
+    let mut value = String::new();
+    while *i < chars.len() {
+        match chars[*i] {
+            '"' => {
+                *i += 1;
+                return Ok(value);
+            }
+            '\\' if chars.get(*i + 1) == Some(&'"') => {
+                value.push('"');
+                *i += 2;
+            }
+            c => {
+                value.push(c);
+                *i += 1;
+            }
+        }
+    }
+    Err(QueryError { message: "Unterminated quote".to_string(), start: offsets[start], end: offsets[chars.len()] })
+}
+
+#[derive(Clone, Copy)]
+enum FieldKind {
+    Regex,
+    /// `fields::parse_fields` 로 뽑은 헤더 필드 (`level`, `tag`, `pid`, `tid`, `file`, `function`)
+    Header,
+}
+
+fn field_kind(name: &str) -> Option<FieldKind> {
+    match name.to_ascii_lowercase().as_str() {
+        "re" => Some(FieldKind::Regex),
+        "level" | "tag" | "pid" | "tid" | "file" | "function" => Some(FieldKind::Header),
+        _ => None,
+    }
+}
+
+/// 필드 단어를 엔진 단어로 변환. 알 수 없는 필드는 `foo:bar` 그대로 리터럴 (`not:` 등 엔진 접두어는 이스케이프)
+///
+/// 헤더 필드는 `field:` 단어로 바꿔서 본문에 같은 글자가 있어도 걸리지 않게 합니다. (`level` 은 `>=`, `<` 등 비교도 가능)
+fn field_term(word: &str) -> Result<String, String> {
+    let name_len = word.bytes().take_while(u8::is_ascii_alphabetic).count();
+    let (name, rest) = word.split_at(name_len);
+    let kind = match field_kind(name) {
+        Some(FieldKind::Header) if name.eq_ignore_ascii_case("level") && rest.starts_with(['>', '<', '=']) => FieldKind::Header,
+        Some(kind) if rest.starts_with(':') => kind,
+        _ => return Ok(escape_literal(word)),
+    };
+    let value = &rest[1..];
+    if value.is_empty() {
+        return Err(format!("Missing value after `{}`", &word));
+    }
+    match kind {
+        FieldKind::Regex => Ok(format!("{}{}", REGEX_PREFIX, value)),
+        FieldKind::Header => {
+            // 값 검사만 (대소문자 처리는 룰 컴파일 때)
+            FieldPredicate::parse(word, true)?;
+            Ok(format!("{}{}", FIELD_PREFIX, word))
+        }
+    }
+}
+
+#[derive(Debug)]
+enum Expr {
+    Term(String),
+    Not(Box<Expr>),
+    And(Vec<Expr>),
+    Or(Vec<Expr>),
+}
+
+struct Parser<'a> {
+    tokens: &'a [Token],
+    pos: usize,
+    end: usize,
+    /// 지금 파싱 중인 괄호 / `NOT` 중첩 수
+    depth: usize,
+}
+
+impl Parser<'_> {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn eof_error(&self, message: &str) -> QueryError {
+        QueryError { message: message.to_string(), start: self.end, end: self.end }
+    }
+
+    fn parse_or(&mut self) -> Result<Expr, QueryError> {
+        let mut items = vec![self.parse_and()?];
+        while matches!(self.peek().map(|t| &t.kind), Some(TokenKind::Or)) {
+            self.pos += 1;
+            items.push(self.parse_and()?);
+        }
+        Ok(if items.len() == 1 { items.pop().unwrap() } else { Expr::Or(items) })
+    }
+
+    fn parse_and(&mut self) -> Result<Expr, QueryError> {
+        let mut items = vec![self.parse_unary()?];
+        loop {
+            match self.peek().map(|t| &t.kind) {
+                Some(TokenKind::And) => {
+                    self.pos += 1;
+                    items.push(self.parse_unary()?);
+                }
+                // 공백으로 이어 쓴 단어는 AND
+                Some(TokenKind::Term(_) | TokenKind::LParen | TokenKind::Not) => items.push(self.parse_unary()?),
+                _ => break,
+            }
+        }
+        Ok(if items.len() == 1 { items.pop().unwrap() } else { Expr::And(items) })
+    }
+
+    fn parse_unary(&mut self) -> Result<Expr, QueryError> {
+        let tok = match self.peek() {
+            Some(tok) => tok.clone(),
+            None => return Err(self.eof_error("Expected a term")),
+        };
+        self.pos += 1;
+        if matches!(tok.kind, TokenKind::Not | TokenKind::LParen) {
+            if self.depth == MAX_DEPTH {
+                return Err(tok.error(&format!("Query is nested too deeply (max {} levels of `(` / `NOT`)", MAX_DEPTH)));
+            }
+            self.depth += 1;
+        }
+        let expr = match tok.kind {
+            TokenKind::Not => Ok(Expr::Not(Box::new(self.parse_unary()?))),
+            TokenKind::Term(term) => return Ok(Expr::Term(term)),
+            TokenKind::LParen => {
+                let inner = self.parse_or()?;
+                match self.peek() {
+                    Some(Token { kind: TokenKind::RParen, .. }) => {
+                        self.pos += 1;
+                        Ok(inner)
+                    }
+                    Some(other) => Err(other.error("Expected `)`")),
+                    None => Err(QueryError { message: "Unclosed `(`".to_string(), start: tok.start, end: tok.end }),
+                }
+            }
+            TokenKind::RParen => Err(tok.error("Unexpected `)`")),
+            TokenKind::And | TokenKind::Or => return Err(tok.error("Expected a term before operator")),
+        };
+        self.depth -= 1;
+        expr
+    }
+}
+
+#[derive(Clone, Debug)]
+struct Literal {
+    term: String,
+    negated: bool,
+}
+
+/// 드 모르간으로 NOT 을 단어까지 밀어 내린 OR-of-AND. 그룹 수가 `MAX_GROUPS` 를 넘으면 `None`
+fn to_dnf(expr: &Expr, negate: bool) -> Option<Vec<Vec<Literal>>> {
+    match expr {
+        Expr::Term(term) => Some(vec![vec![Literal { term: term.clone(), negated: negate }]]),
+        Expr::Not(inner) => to_dnf(inner, !negate),
+        // NOT (a AND b) = NOT a OR NOT b
+        Expr::And(items) if negate => union(items, negate),
+        Expr::And(items) => product(items, negate),
+        // NOT (a OR b) = NOT a AND NOT b
+        Expr::Or(items) if negate => product(items, negate),
+        Expr::Or(items) => union(items, negate),
+    }
+}
+
+fn union(items: &[Expr], negate: bool) -> Option<Vec<Vec<Literal>>> {
+    let mut out = Vec::new();
+    for item in items {
+        out.extend(to_dnf(item, negate)?);
+        if out.len() > MAX_GROUPS {
+            return None;
+        }
+    }
+    Some(out)
+}
+
+fn product(items: &[Expr], negate: bool) -> Option<Vec<Vec<Literal>>> {
+    let mut out: Vec<Vec<Literal>> = vec![Vec::new()];
+    for item in items {
+        let rhs = to_dnf(item, negate)?;
+        if out.len() * rhs.len() > MAX_GROUPS {
+            return None;
+        }
+        out = out.iter()
+            .flat_map(|l| rhs.iter().map(move |r| l.iter().chain(r).cloned().collect()))
+            .collect();
+    }
+    Some(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn groups(query: &str) -> Vec<Vec<String>> {
+        compile_query(query).unwrap().include_groups
+    }
+
+    fn strings(items: &[&str]) -> Vec<String> {
+        items.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn operators_expand_to_or_of_and() {
+        assert_eq!(groups("(a OR b) c"), vec![strings(&["a", "c"]), strings(&["b", "c"])]);
+        assert_eq!(groups("NOT (a OR b)"), vec![strings(&["not:a", "not:b"])]);
+        assert_eq!(groups("NOT (a AND b)"), vec![strings(&["not:a"]), strings(&["not:b"])]);
+        assert_eq!(groups(""), Vec::<Vec<String>>::new());
+    }
+
+    #[test]
+    fn quoted_and_bare_prefixes_stay_literal() {
+        assert_eq!(groups(r#""not: found""#), vec![strings(&[r"\not: found"])]);
+        assert_eq!(groups(r#""re:x" "conn fail""#), vec![strings(&[r"\re:x", "conn fail"])]);
+        assert_eq!(groups("not:x field:y"), vec![strings(&[r"\not:x", r"\field:y"])]);
+        assert_eq!(groups(r"\path"), vec![strings(&[r"\\path"])]);
+        assert_eq!(groups("foo:bar"), vec![strings(&["foo:bar"])]);
+    }
+
+    #[test]
+    fn field_names_ignore_case() {
+        assert_eq!(groups(r"RE:\d+ Tag:Net"), vec![strings(&[r"re:\d+", "field:Tag:Net"])]);
+        assert_eq!(groups("Level>=W"), vec![strings(&["field:Level>=W"])]);
+        assert_eq!(groups(r#"TAG:"Network Manager""#), vec![strings(&["field:TAG:Network Manager"])]);
+        assert!(compile_query("Level:X").is_err());
+    }
+
+    #[test]
+    fn errors_carry_utf16_offsets() {
+        let err = compile_query("가나 (a").unwrap_err();
+        assert_eq!((err.start, err.end), (3, 4));
+        let err = compile_query(r#"a "b"#).unwrap_err();
+        assert_eq!(err.message, "Unterminated quote");
+        assert!(compile_query("a OR").is_err());
+        assert!(compile_query("a )").is_err());
+    }
+
+    #[test]
+    fn group_explosion_is_rejected() {
+        let query = (0..9).map(|i| format!("(a{} OR b{})", i, i)).collect::<Vec<_>>().join(" ");
+        assert!(compile_query(&query).is_err());
+    }
+
+    #[test]
+    fn deep_nesting_is_a_positioned_error() {
+        let parens = format!("{}a{}", "(".repeat(10_000), ")".repeat(10_000));
+        let err = compile_query(&parens).unwrap_err();
+        assert!(err.message.contains("nested too deeply"), "{}", err);
+        assert_eq!((err.start, err.end), (MAX_DEPTH, MAX_DEPTH + 1));
+
+        let nots = format!("{}a", "NOT ".repeat(10_000));
+        let err = compile_query(&nots).unwrap_err();
+        assert_eq!((err.start, err.end), (MAX_DEPTH * 4, MAX_DEPTH * 4 + 3));
+
+        let ok = format!("{}a{}", "(".repeat(MAX_DEPTH), ")".repeat(MAX_DEPTH));
+        assert_eq!(groups(&ok), vec![strings(&["a"])]);
+        assert_eq!(groups(&format!("{}a", "NOT ".repeat(MAX_DEPTH))), vec![strings(&["a"])]);
+    }
+}
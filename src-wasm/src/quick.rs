@@ -0,0 +1,119 @@
+use aho_corasick::{AhoCorasick, AhoCorasickBuilder, MatchKind};
+use serde::{Deserialize, Serialize};
+
+use crate::ansi;
+use crate::fields::header_level;
+use crate::level::LogLevel;
+
+/// 상단 바의 Quick Filter 버튼 (`'none' | 'error' | 'exception'`)
+#[derive(Serialize, Deserialize, Default, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum QuickFilter {
+    #[default]
+    None,
+    Error,
+    Exception,
+}
+
+impl QuickFilter {
+    pub fn parse(mode: &str) -> Option<QuickFilter> {
+        match mode {
+            "none" | "" => Some(QuickFilter::None),
+            "error" => Some(QuickFilter::Error),
+            "exception" => Some(QuickFilter::Exception),
+            _ => None,
+        }
+    }
+}
+
+/// 레벨이 없는 라인(쉘 출력, 스택 트레이스 등)에서 에러로 보는 단어들
+const ERROR_KEYWORDS: [&str; 3] = ["error", "fail", "fatal"];
+
+/// 미리 정의된 Quick Filter 판정기 (룰과 AND 로 결합됩니다)
+pub struct QuickMatcher {
+    mode: QuickFilter,
+    ac: Option<AhoCorasick>,
+}
+
+impl QuickMatcher {
+    pub fn new(mode: QuickFilter) -> Self {
+        let keywords: &[&str] = match mode {
+            QuickFilter::None => &[],
+            QuickFilter::Error => &ERROR_KEYWORDS,
+            QuickFilter::Exception => &["exception"],
+        };
+        let ac = if keywords.is_empty() {
+            None
+        } else {
+            Some(AhoCorasickBuilder::new()
+                .match_kind(MatchKind::LeftmostFirst)
+                .ascii_case_insensitive(true)
+                .build(keywords)
+                .expect("built-in quick filter keywords"))
+        };
+        QuickMatcher { mode, ac }
+    }
+
+    pub fn mode(&self) -> QuickFilter {
+        self.mode
+    }
+
+    /// `error`: 알려진 형식(dlog/brief, threadtime, 커널 `<N>`) 의 헤더 레벨이 있으면 레벨로만 판단(E/F),
+    /// 없으면 ERROR/FAIL/FATAL 단어로 판단
+    /// (`I/Tag: no error found` 같은 라인은 걸리지 않고, 본문 중간의 ` E ` 글자는 레벨로 보지 않습니다)
+    pub fn is_match(&self, line: &[u8]) -> bool {
+        let keyword_hit = || self.ac.as_ref().is_some_and(|ac| ac.is_match(line));
+        match self.mode {
+            QuickFilter::None => true,
+            QuickFilter::Error => match header_level(ansi::strip(line, &mut Vec::new())) {
+                Some(level) => level >= LogLevel::Error,
+                None => keyword_hit(),
+            },
+            QuickFilter::Exception => keyword_hit(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn error_uses_header_level_of_known_formats() {
+        let quick = QuickMatcher::new(QuickFilter::Error);
+        assert!(quick.is_match(b"01-01 10:00:00.123+0900 E/Tag(P 1, T 2): boom"));
+        assert!(quick.is_match(b"01-01 10:00:00.123  1234  5678 F Tag  : abort"));
+        assert!(quick.is_match(b"<3>[  12.345678] usb: error"));
+        assert!(quick.is_match(b"E/Tag( 123): brief without time"));
+        // 레벨이 I 면 본문의 error 단어는 무시
+        assert!(!quick.is_match(b"01-01 10:00:00.123 I/Tag: no error found"));
+        assert!(!quick.is_match(b"<6>[  12.345678] error in name only"));
+    }
+
+    #[test]
+    fn stray_level_letters_are_not_levels() {
+        let quick = QuickMatcher::new(QuickFilter::Error);
+        // 형식을 모르는 라인의 ` E ` 글자는 레벨이 아님 -> 단어로 판단
+        assert!(!quick.is_match(b"Plan A E B"));
+        assert!(!quick.is_match(b"sh: W something E else"));
+        assert!(quick.is_match(b"make: *** [all] Error 2"));
+        assert!(quick.is_match(b"Build FAILED"));
+    }
+
+    #[test]
+    fn ansi_colored_header_is_cleaned_first() {
+        let quick = QuickMatcher::new(QuickFilter::Error);
+        assert!(quick.is_match(b"\x1b[31mE/Tag: colored\x1b[0m"));
+        assert!(!quick.is_match(b"\x1b[32mI/Tag: fail count 0\x1b[0m"));
+    }
+
+    #[test]
+    fn exception_and_none_modes() {
+        assert!(QuickMatcher::new(QuickFilter::None).is_match(b"anything"));
+        let quick = QuickMatcher::new(QuickFilter::Exception);
+        assert!(quick.is_match(b"java.lang.NullPointerException: x"));
+        assert!(!quick.is_match(b"E/Tag: error"));
+        assert_eq!(QuickFilter::parse(""), Some(QuickFilter::None));
+        assert_eq!(QuickFilter::parse("bogus"), None);
+    }
+}
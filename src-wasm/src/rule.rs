@@ -0,0 +1,321 @@
+use aho_corasick::MatchKind;
+use serde::{Deserialize, Serialize};
+
+use crate::automata::{Automata, LiteralSet, RegexMatcher};
+use crate::combo::{ComboMatcher, ComboScratch};
+use crate::error::FilterError;
+use crate::fields;
+use crate::highlight::{HighlightSet, HighlightSpec};
+use crate::predicate::FieldPredicate;
+use crate::quick::{QuickFilter, QuickMatcher};
+use crate::shape::{ShapeClassifier, ShapeSpec};
+use crate::stats::RuleStats;
+use crate::term::Term;
+use crate::time::NO_TIME;
+use crate::timeline::{TimeFilter, TimeRange, TimeWindow};
+
+/// JS `LogRule` 중 필터 판정에 필요한 필드만 받습니다. (나머지 필드는 무시)
+#[derive(Serialize, Deserialize, Default, Clone, Debug)]
+#[serde(rename_all = "camelCase", default)]
+pub struct RuleSpec {
+    pub include_groups: Vec<Vec<String>>,
+    pub excludes: Vec<String>,
+    pub happy_combos_case_sensitive: bool,
+    pub block_list_case_sensitive: bool,
+    pub highlights: Vec<HighlightSpec>,
+    pub color_highlights_case_sensitive: bool,
+    /// worker payload 에 같이 실려 오는 `quickFilter`
+    pub quick_filter: QuickFilter,
+    /// `undefined` 는 true 취급 (JS 의 `showRawLogLines !== false`)
+    pub show_raw_log_lines: Option<bool>,
+    /// 스트림 모드 전용: 쉘 출력 우회 + 강제 포함 접두어 적용 여부 (LogRule 필드가 아니라 worker 상태)
+    pub bypass_shell_filter: bool,
+    pub line_shapes: ShapeSpec,
+    /// 시간 범위 (`{ start: '10:32:00', end: '10:35:00' }`) - 키워드 조건과 AND
+    pub time_range: Option<TimeRange>,
+}
+
+/// Block List: 하나라도 나오면 탈락이므로 단순 any-match 오토마톤 (+ 정규식, 필드 조건)
+struct BlockList {
+    ac: Option<LiteralSet>,
+    literals: Vec<String>,
+    regexes: Vec<RegexMatcher>,
+    fields: Vec<FieldPredicate>,
+    case_sensitive: bool,
+}
+
+impl BlockList {
+    fn build(excludes: &[String], case_sensitive: bool, automata: &mut Automata) -> Result<Option<Self>, FilterError> {
+        let mut keywords: Vec<String> = Vec::new();
+        let mut regexes: Vec<RegexMatcher> = Vec::new();
+        let mut fields: Vec<FieldPredicate> = Vec::new();
+        for term in excludes.iter().filter_map(|raw| Term::parse(raw, case_sensitive)) {
+            match term {
+                Term::Literal(s) => keywords.push(s),
+                Term::Regex(pattern) => regexes.push(automata.regex(&pattern, case_sensitive)?),
+                Term::Field(text) => fields.push(
+                    FieldPredicate::parse(&text, case_sensitive).map_err(|message| FilterError::Field { term: text, message })?,
+                ),
+            }
+        }
+
+        if keywords.is_empty() && regexes.is_empty() && fields.is_empty() {
+            return Ok(None);
+        }
+
+        let ac = automata.literals(&keywords, MatchKind::LeftmostFirst, case_sensitive)?;
+        Ok(Some(BlockList { ac, literals: keywords, regexes, fields, case_sensitive }))
+    }
+
+    fn is_match(&self, line: &[u8]) -> bool {
+        self.ac.as_ref().is_some_and(|ac| ac.is_match(line)) || self.is_match_except_literals(line)
+    }
+
+    fn is_match_except_literals(&self, line: &[u8]) -> bool {
+        self.regexes.iter().any(|re| re.is_match(line))
+            || (!self.fields.is_empty() && {
+                let parsed = fields::parse_fields(line);
+                self.fields.iter().any(|f| f.is_match(line, &parsed, self.case_sensitive))
+            })
+    }
+}
+
+/// 컴파일된 `LogRule`: 한 번의 호출로 Block List + Happy Combo 최종 판정
+pub struct CompiledRule {
+    combos: Option<ComboMatcher>,
+    block: Option<BlockList>,
+    highlights: Option<HighlightSet>,
+    quick: QuickMatcher,
+    /// `bypass_shell_filter` 일 때만 만들어집니다.
+    shape: Option<ShapeClassifier>,
+    show_raw_log_lines: bool,
+    time: Option<TimeFilter>,
+}
+
+impl CompiledRule {
+    pub fn compile(spec: &RuleSpec) -> Result<Self, FilterError> {
+        Self::compile_with(spec, &mut Automata::compile())
+    }
+
+    /// 오토마톤 출처를 지정해서 컴파일 (블롭 내보내기/읽기는 `blob` 참고)
+    ///
+    /// Quick Filter 의 내장 키워드(3개)와 필드/시간 조건은 오토마톤이 아니므로 항상 여기서 만듭니다.
+    pub fn compile_with(spec: &RuleSpec, automata: &mut Automata) -> Result<Self, FilterError> {
+        Ok(CompiledRule {
+            combos: ComboMatcher::build(&spec.include_groups, spec.happy_combos_case_sensitive, automata)?,
+            block: BlockList::build(&spec.excludes, spec.block_list_case_sensitive, automata)?,
+            highlights: HighlightSet::build(&spec.highlights, spec.color_highlights_case_sensitive, automata)?,
+            quick: QuickMatcher::new(spec.quick_filter),
+            shape: if spec.bypass_shell_filter { Some(ShapeClassifier::build(&spec.line_shapes, automata)?) } else { None },
+            show_raw_log_lines: spec.show_raw_log_lines != Some(false),
+            time: match &spec.time_range {
+                Some(range) => TimeFilter::compile(range)?,
+                None => None,
+            },
+        })
+    }
+
+    /// 하이라이트는 판정에는 영향이 없고 span API 에서만 사용합니다.
+    pub fn highlights(&self) -> Option<&HighlightSet> {
+        self.highlights.as_ref()
+    }
+
+    /// `TimeIndexer.window_ranges` 로 후보 구간을 좁힐 때 사용
+    pub fn time_window(&self) -> Option<&TimeWindow> {
+        self.time.as_ref().map(TimeFilter::window)
+    }
+
+    /// 필터 호출마다 시간 범위의 "앞 라인 시각" 을 `seed` 로 새로 시작합니다. (`NO_TIME` = 모름)
+    pub fn begin_time(&mut self, seed: i64) {
+        if let Some(time) = &mut self.time {
+            time.begin(seed);
+        }
+    }
+
+    /// 마지막으로 판정한 라인의 시각 (시간 범위가 없으면 `NO_TIME`)
+    pub fn last_time(&self) -> i64 {
+        self.time.as_ref().map_or(NO_TIME, TimeFilter::last)
+    }
+
+    /// 콤보도 블록리스트도 Quick Filter 도 시간 범위도 없는 빈 룰인지 (JS의 hasHappy / hasBlock 체크와 동일)
+    pub fn is_empty(&self) -> bool {
+        self.combos.is_none() && self.block.is_none() && self.quick.mode() == QuickFilter::None && self.time.is_none()
+    }
+
+    /// 대소문자 무시도 오토마톤 내부에서 처리하므로 라인 복사가 전혀 없습니다.
+    ///
+    /// 시간 범위가 있으면 타임스탬프 없는 라인이 앞 라인 시각을 물려받으므로 `begin_time` 뒤로 라인을 파일 순서대로 넣어야 합니다.
+    pub fn is_match(&mut self, line: &[u8], scratch: &mut ComboScratch) -> bool {
+        self.is_match_at(line, None, scratch)
+    }
+
+    /// `time` = 이미 아는 라인 시각 (`TimeColumn::times`). `None` 이면 라인에서 읽습니다.
+    pub fn is_match_at(&mut self, line: &[u8], time: Option<i64>, scratch: &mut ComboScratch) -> bool {
+        if !self.time_accepts(line, time) {
+            return false;
+        }
+        if let Some(verdict) = self.precheck(line) {
+            return verdict;
+        }
+
+        if let Some(block) = &self.block {
+            if block.is_match(line) {
+                return false;
+            }
+        }
+
+        match &self.combos {
+            None => true,
+            Some(combos) => combos.is_match(line, scratch),
+        }
+    }
+
+    /// 이 룰의 단어/그룹 구성에 맞춘 빈 통계
+    pub fn new_stats(&self) -> RuleStats {
+        let (terms, groups) = self.combos.as_ref().map(|c| c.new_stats()).unwrap_or_default();
+        RuleStats { terms, groups, ..RuleStats::default() }
+    }
+
+    /// `is_match` 와 같은 판정을 하면서 같은 스캔에서 통계도 기록합니다. (조기 종료가 없어 조금 느림)
+    pub fn is_match_recording(&mut self, line: &[u8], time: Option<i64>, scratch: &mut ComboScratch, line_index: i32, stats: &mut RuleStats) -> bool {
+        stats.lines += 1;
+        if !self.time_accepts(line, time) {
+            return false;
+        }
+        let matched = match self.precheck(line) {
+            Some(verdict) => verdict,
+            None if self.block.as_ref().is_some_and(|b| b.is_match(line)) => {
+                stats.blocked.record(line_index);
+                false
+            }
+            None => match &self.combos {
+                None => true,
+                Some(combos) => combos.is_match_recording(line, scratch, line_index, &mut stats.terms, &mut stats.groups),
+            },
+        };
+        if matched {
+            stats.matched.record(line_index);
+        }
+        matched
+    }
+
+    /// Happy Combo 의 리터럴 단어와 대소문자 구분 여부 (`RuleSet` 이 모든 룰의 단어를 한 오토마톤으로 합칠 때)
+    pub fn combo_literals(&self) -> Option<(&[String], bool)> {
+        self.combos.as_ref().map(|c| (c.literals(), c.case_sensitive()))
+    }
+
+    /// Block List 의 리터럴 단어와 대소문자 구분 여부
+    pub fn block_literals(&self) -> Option<(&[String], bool)> {
+        self.block.as_ref().map(|b| (b.literals.as_slice(), b.case_sensitive))
+    }
+
+    pub fn combos(&self) -> Option<&ComboMatcher> {
+        self.combos.as_ref()
+    }
+
+    /// 리터럴 스캔을 밖(`RuleSet`)에서 끝낸 상태로 `is_match` 와 같은 판정을 합니다.
+    ///
+    /// `scratch` 는 `ComboMatcher::begin` / `record_hit` 로 적중이 기록된 상태,
+    /// `block_hit` 은 Block List 리터럴이 나왔는지, `combo_hit` 은 리터럴만으로 콤보 매칭이 확정됐는지입니다.
+    pub fn is_match_scanned(&mut self, line: &[u8], scratch: &mut ComboScratch, block_hit: bool, combo_hit: bool) -> bool {
+        if !self.time_accepts(line, None) {
+            return false;
+        }
+        if let Some(verdict) = self.precheck(line) {
+            return verdict;
+        }
+        if block_hit || self.block.as_ref().is_some_and(|b| b.is_match_except_literals(line)) {
+            return false;
+        }
+        match &self.combos {
+            None => true,
+            Some(combos) => combo_hit || combos.finish(line, scratch),
+        }
+    }
+
+    /// 시간 범위 판정 (걸러질 라인도 앞 라인 시각을 갱신해야 하므로 다른 조건보다 먼저)
+    fn time_accepts(&mut self, line: &[u8], time: Option<i64>) -> bool {
+        self.time.as_mut().is_none_or(|filter| match time {
+            Some(time) => filter.accepts_at(time),
+            None => filter.accepts(line),
+        })
+    }
+
+    /// 키워드 평가 전에 결론이 나는 경우 (Quick Filter 탈락 / 스트림 모드 우회)
+    fn precheck(&self, line: &[u8]) -> Option<bool> {
+        if !self.quick.is_match(line) {
+            return Some(false);
+        }
+
+        // 스트림 모드: 강제 포함 접두어 / 로그처럼 생기지 않은 쉘 출력은 룰을 건너뛰고 통과
+        if let Some(shape) = &self.shape {
+            if shape.is_allowed(line) {
+                return Some(true);
+            }
+            if self.show_raw_log_lines && !self.is_empty() && !shape.is_standard(line) {
+                return Some(true);
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn strings(items: &[&str]) -> Vec<String> {
+        items.iter().map(|s| s.to_string()).collect()
+    }
+
+    fn rule(spec: RuleSpec) -> CompiledRule {
+        CompiledRule::compile(&spec).unwrap()
+    }
+
+    #[test]
+    fn empty_rule_passes_everything() {
+        let mut r = rule(RuleSpec::default());
+        assert!(r.is_empty());
+        assert!(r.is_match(b"anything", &mut ComboScratch::default()));
+    }
+
+    #[test]
+    fn block_list_has_its_own_case_sensitivity() {
+        let spec = RuleSpec {
+            include_groups: vec![strings(&["wifi"])],
+            excludes: strings(&["Noise"]),
+            happy_combos_case_sensitive: false,
+            block_list_case_sensitive: true,
+            ..RuleSpec::default()
+        };
+        let mut r = rule(spec);
+        let mut s = ComboScratch::default();
+        assert!(r.is_match(b"WIFI up", &mut s));
+        assert!(!r.is_match(b"wifi Noise", &mut s));
+        assert!(r.is_match(b"wifi noise", &mut s));
+    }
+
+    #[test]
+    fn block_list_alone_and_with_regex() {
+        let mut r = rule(RuleSpec { excludes: strings(&["chatty", r"re:^\s*$"]), ..RuleSpec::default() });
+        let mut s = ComboScratch::default();
+        assert!(!r.is_empty());
+        assert!(r.is_match(b"real line", &mut s));
+        assert!(!r.is_match(b"CHATTY spam", &mut s));
+        assert!(!r.is_match(b"   ", &mut s));
+    }
+
+    #[test]
+    fn blocked_lines_are_counted_in_stats() {
+        let mut r = rule(RuleSpec { include_groups: vec![strings(&["a"])], excludes: strings(&["b"]), ..RuleSpec::default() });
+        let mut stats = r.new_stats();
+        let mut s = ComboScratch::default();
+        for (i, line) in [&b"a"[..], b"a b", b"c"].iter().enumerate() {
+            r.is_match_recording(line, None, &mut s, i as i32, &mut stats);
+        }
+        assert_eq!(stats.lines, 3);
+        assert_eq!(stats.blocked.hits, 1);
+        assert_eq!(stats.blocked.first_line, Some(1));
+        assert_eq!(stats.matched.hits, 1);
+    }
+}
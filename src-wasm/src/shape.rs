@@ -0,0 +1,156 @@
+use serde::{Deserialize, Serialize};
+
+use crate::automata::{Automata, RegexMatcher};
+use crate::error::FilterError;
+
+/// 스트림 모드에서 "표준 로그"로 인정하는 라인 형태
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum KnownFormat {
+    /// `[` 로 시작 (커널 로그, `[TAG]` 형태 등)
+    Bracket,
+    /// `2024-01-01`, `01-01-2024`, `12:34:56` 으로 시작
+    TimeOrDate,
+    /// ` INFO `, `[ERROR]`, `/WARN:` 같은 긴 레벨 표기 (대소문자 무시)
+    LevelLong,
+    /// ` I `, `E/`, `[W]` 같은 한 글자 레벨 표기
+    LevelShort,
+    /// ` 1234 `, `(12345/` 같은 4~6자리 PID
+    Pid,
+}
+
+impl KnownFormat {
+    pub const ALL: [KnownFormat; 5] = [
+        KnownFormat::Bracket,
+        KnownFormat::TimeOrDate,
+        KnownFormat::LevelLong,
+        KnownFormat::LevelShort,
+        KnownFormat::Pid,
+    ];
+
+    /// logFiltering.ts 의 RE_* 정규식과 동일 (`Bracket` 은 정규식 없이 첫 글자로 판단)
+    fn pattern(self) -> Option<&'static str> {
+        match self {
+            KnownFormat::Bracket => None,
+            KnownFormat::TimeOrDate => Some(r"^\s*(\[?[0-9]{2,4}[-/.][0-9]{2}[-/.][0-9]{2,4}|\[?[0-9]{2}:[0-9]{2}:[0-9]{2})"),
+            KnownFormat::LevelLong => Some(r"(?i)[ \[/](TRACE|DEBUG|INFO|WARN|ERROR|FATAL)[ /\]:]"),
+            KnownFormat::LevelShort => Some(r"[ \[/](V|D|I|W|E|F)[ /\]:]"),
+            KnownFormat::Pid => Some(r"[ (][0-9]{4,6}[ /]"),
+        }
+    }
+}
+
+/// 라인 형태 판별 설정
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ShapeSpec {
+    pub formats: Vec<KnownFormat>,
+    /// 사용자가 추가하는 형태 (정규식, 앞 공백을 제거한 라인에 적용)
+    pub extra_formats: Vec<String>,
+    /// 이 접두어로 시작하는 라인은 룰과 상관없이 무조건 포함 (Tizen Connection Test 의 시뮬레이션 로그)
+    pub allow_prefixes: Vec<String>,
+}
+
+impl Default for ShapeSpec {
+    fn default() -> Self {
+        ShapeSpec {
+            formats: KnownFormat::ALL.to_vec(),
+            extra_formats: Vec::new(),
+            allow_prefixes: vec!["[TEST_LOG_".to_string()],
+        }
+    }
+}
+
+/// 스트림 모드의 "로그 vs 쉘 출력" 판별기
+pub struct ShapeClassifier {
+    bracket: bool,
+    set: RegexMatcher,
+    allow_prefixes: Vec<Vec<u8>>,
+}
+
+impl ShapeClassifier {
+    pub fn build(spec: &ShapeSpec, automata: &mut Automata) -> Result<Self, FilterError> {
+        let patterns: Vec<&str> = spec.formats.iter()
+            .filter_map(|f| f.pattern())
+            .chain(spec.extra_formats.iter().map(|s| s.as_str()).filter(|s| !s.trim().is_empty()))
+            .collect();
+        let set = automata.regex_set(&patterns)?;
+
+        Ok(ShapeClassifier {
+            bracket: spec.formats.contains(&KnownFormat::Bracket),
+            set,
+            allow_prefixes: spec.allow_prefixes.iter()
+                .filter(|p| !p.is_empty())
+                .map(|p| p.as_bytes().to_vec())
+                .collect(),
+        })
+    }
+
+    /// 강제 포함 접두어로 시작하는지 (앞 공백 무시)
+    pub fn is_allowed(&self, line: &[u8]) -> bool {
+        let trimmed = line.trim_ascii_start();
+        self.allow_prefixes.iter().any(|p| trimmed.starts_with(p))
+    }
+
+    /// 알려진 로그 형태 중 하나라도 맞으면 true. 빈 라인은 false (= 쉘 출력으로 취급되어 통과)
+    pub fn is_standard(&self, line: &[u8]) -> bool {
+        let trimmed = line.trim_ascii_start();
+        if trimmed.is_empty() {
+            return false;
+        }
+        (self.bracket && trimmed[0] == b'[') || self.set.is_match(trimmed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn classifier(formats: &[KnownFormat], extra: &[&str]) -> ShapeClassifier {
+        let spec = ShapeSpec {
+            formats: formats.to_vec(),
+            extra_formats: extra.iter().map(|s| s.to_string()).collect(),
+            ..ShapeSpec::default()
+        };
+        ShapeClassifier::build(&spec, &mut Automata::compile()).unwrap()
+    }
+
+    #[test]
+    fn default_formats_recognize_log_lines() {
+        let shape = ShapeClassifier::build(&ShapeSpec::default(), &mut Automata::compile()).unwrap();
+        assert!(shape.is_standard(b"[  12.345] kernel"));
+        assert!(shape.is_standard(b"  2024-01-01 10:00:00 boot"));
+        assert!(shape.is_standard(b"10:00:00 started"));
+        assert!(shape.is_standard(b"app [ERROR] broken"));
+        assert!(shape.is_standard(b"app E/Tag: broken"));
+        assert!(shape.is_standard(b"worker (12345/main) done"));
+        assert!(!shape.is_standard(b"$ ls -al"));
+        assert!(!shape.is_standard(b"total 12"));
+        assert!(!shape.is_standard(b"   "));
+    }
+
+    #[test]
+    fn only_selected_formats_apply() {
+        let shape = classifier(&[KnownFormat::TimeOrDate], &[]);
+        assert!(shape.is_standard(b"01-01-2024 x"));
+        assert!(!shape.is_standard(b"[tag] x"));
+        assert!(!shape.is_standard(b"app [ERROR] x"));
+
+        let shape = classifier(&[], &[r"^>>>", "  "]);
+        assert!(shape.is_standard(b"  >>> custom"));
+        assert!(!shape.is_standard(b"[tag] x"));
+    }
+
+    #[test]
+    fn allow_prefixes_ignore_leading_spaces() {
+        let shape = ShapeClassifier::build(&ShapeSpec::default(), &mut Automata::compile()).unwrap();
+        assert!(shape.is_allowed(b"  [TEST_LOG_1] simulated"));
+        assert!(!shape.is_allowed(b"x [TEST_LOG_1]"));
+    }
+
+    #[test]
+    fn invalid_extra_format_is_reported() {
+        let spec = ShapeSpec { extra_formats: vec!["(".to_string()], ..ShapeSpec::default() };
+        assert!(matches!(ShapeClassifier::build(&spec, &mut Automata::compile()), Err(FilterError::Regex { .. })));
+    }
+}
@@ -0,0 +1,84 @@
+use serde::Serialize;
+
+/// 적중 라인 수 + 처음/마지막 적중 라인 (전역 라인 인덱스)
+#[derive(Serialize, Default, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct HitStat {
+    pub hits: u64,
+    pub first_line: Option<i32>,
+    pub last_line: Option<i32>,
+}
+
+impl HitStat {
+    pub fn record(&mut self, line_index: i32) {
+        self.hits += 1;
+        if self.first_line.is_none() {
+            self.first_line = Some(line_index);
+        }
+        self.last_line = Some(line_index);
+    }
+}
+
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct TermStat {
+    pub term: String,
+    #[serde(flatten)]
+    pub stat: HitStat,
+}
+
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct GroupStat {
+    /// 그룹의 단어들 (부정 단어는 `not:` 접두어)
+    pub terms: Vec<String>,
+    #[serde(flatten)]
+    pub stat: HitStat,
+}
+
+/// 필터 패널용 통계: "ERROR: 12,004 hits / Wifi AND timeout: 0"
+///
+/// 단어/그룹 통계는 Quick Filter / Block List 를 통과해 콤보 평가까지 온 라인 기준입니다.
+#[derive(Serialize, Default, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct RuleStats {
+    /// 스캔한 전체 라인 수
+    pub lines: u64,
+    /// Block List 로 제외된 라인
+    pub blocked: HitStat,
+    /// 최종 매칭된 라인
+    pub matched: HitStat,
+    pub terms: Vec<TermStat>,
+    pub groups: Vec<GroupStat>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hit_stat_keeps_first_and_last_line() {
+        let mut stat = HitStat::default();
+        assert_eq!(stat.first_line, None);
+        for line in [7, 9, 42] {
+            stat.record(line);
+        }
+        assert_eq!(stat, HitStat { hits: 3, first_line: Some(7), last_line: Some(42) });
+    }
+
+    #[test]
+    fn term_and_group_stats_flatten_for_js() {
+        let mut stat = HitStat::default();
+        stat.record(3);
+        let term = TermStat { term: "wifi".into(), stat: stat.clone() };
+        assert_eq!(
+            serde_json::to_value(&term).unwrap(),
+            serde_json::json!({ "term": "wifi", "hits": 1, "firstLine": 3, "lastLine": 3 })
+        );
+        let group = GroupStat { terms: vec!["wifi".into(), "not:ok".into()], stat: HitStat::default() };
+        assert_eq!(
+            serde_json::to_value(&group).unwrap(),
+            serde_json::json!({ "terms": ["wifi", "not:ok"], "hits": 0, "firstLine": null, "lastLine": null })
+        );
+    }
+}
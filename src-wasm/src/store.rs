@@ -0,0 +1,297 @@
+use memchr::{memchr, memchr_iter};
+use std::ops::Range;
+
+use crate::timeline::LineTimes;
+use crate::FilterEngine;
+
+/// 블록 하나의 원본 크기 기본값 (LZ4 압축 단위)
+pub const DEFAULT_BLOCK_BYTES: usize = 256 * 1024;
+
+/// 블록 압축 라인 저장소 (스트림 모드용)
+///
+/// 라인을 `\n` 으로 이어 붙여 `block_bytes` 가 차면 LZ4 로 압축해 봉인합니다.
+/// 라인 번호로 임의 접근할 수 있고(블록 이진 탐색 + 마지막으로 푼 블록 캐시),
+/// 필터링은 블록을 하나씩 풀면서 `FilterEngine` 으로 돌립니다.
+/// 로그는 보통 4~8배 압축되므로 `BUFFER_SIZE` 로 잘라내지 않아도 하루치 스트림을 담을 수 있습니다.
+pub struct LineStore {
+    block_bytes: usize,
+    blocks: Vec<Block>,
+    /// 아직 봉인되지 않은 마지막 블록 (원본 그대로, 라인마다 `\n` 으로 끝남)
+    tail: Vec<u8>,
+    tail_first_line: usize,
+    tail_lines: usize,
+    /// 아직 `\n` 이 안 나온 라인
+    pending: Vec<u8>,
+    raw_bytes: u64,
+    cache: BlockCache,
+}
+
+struct Block {
+    first_line: usize,
+    line_count: usize,
+    raw_len: usize,
+    data: Vec<u8>,
+}
+
+/// 마지막으로 압축을 푼 블록 (연속된 라인 조회가 같은 블록을 반복해서 풀지 않도록)
+#[derive(Default)]
+struct BlockCache {
+    block: Option<usize>,
+    data: Vec<u8>,
+    starts: Vec<usize>,
+}
+
+impl LineStore {
+    /// `block_bytes` 가 0 이면 `DEFAULT_BLOCK_BYTES`
+    pub fn new(block_bytes: usize) -> Self {
+        LineStore {
+            block_bytes: if block_bytes == 0 { DEFAULT_BLOCK_BYTES } else { block_bytes },
+            blocks: Vec::new(),
+            tail: Vec::new(),
+            tail_first_line: 0,
+            tail_lines: 0,
+            pending: Vec::new(),
+            raw_bytes: 0,
+            cache: BlockCache::default(),
+        }
+    }
+
+    /// 스트림 청크 추가 (라인 중간에서 잘려도 됩니다)
+    pub fn append(&mut self, chunk: &[u8]) {
+        self.raw_bytes += chunk.len() as u64;
+        let mut start = 0;
+        for pos in memchr_iter(b'\n', chunk) {
+            if self.pending.is_empty() {
+                self.tail.extend_from_slice(&chunk[start..=pos]);
+            } else {
+                self.tail.append(&mut self.pending);
+                self.tail.extend_from_slice(&chunk[start..=pos]);
+            }
+            self.tail_lines += 1;
+            start = pos + 1;
+            if self.tail.len() >= self.block_bytes {
+                self.seal();
+            }
+        }
+        self.pending.extend_from_slice(&chunk[start..]);
+    }
+
+    /// `\n` 없이 남은 마지막 라인을 완결된 라인으로 넣습니다. (스트림 종료 시)
+    pub fn finish(&mut self) {
+        if !self.pending.is_empty() {
+            self.append(b"\n");
+            self.raw_bytes -= 1;
+        }
+    }
+
+    fn seal(&mut self) {
+        if self.tail_lines == 0 {
+            return;
+        }
+        let data = lz4_flex::block::compress(&self.tail);
+        self.blocks.push(Block {
+            first_line: self.tail_first_line,
+            line_count: self.tail_lines,
+            raw_len: self.tail.len(),
+            data,
+        });
+        self.tail_first_line += self.tail_lines;
+        self.tail_lines = 0;
+        self.tail.clear();
+    }
+
+    /// 라인 수 (`\n` 이 아직 안 나온 마지막 라인 포함)
+    pub fn line_count(&self) -> usize {
+        self.tail_first_line + self.tail_lines + !self.pending.is_empty() as usize
+    }
+
+    /// 지금까지 들어온 원본 바이트 수
+    pub fn raw_bytes(&self) -> u64 {
+        self.raw_bytes
+    }
+
+    /// 실제로 차지하는 메모리 (압축 블록 + 미압축 꼬리)
+    pub fn stored_bytes(&self) -> usize {
+        self.blocks.iter().map(|b| b.data.len()).sum::<usize>() + self.tail.len() + self.pending.len()
+    }
+
+    /// 라인 하나 (끝의 `\n` 제외, 원본 그대로). 범위 밖이면 `None`
+    pub fn line(&mut self, line: usize) -> Option<&[u8]> {
+        if line >= self.line_count() {
+            return None;
+        }
+        if line >= self.tail_first_line + self.tail_lines {
+            return Some(&self.pending);
+        }
+        if line >= self.tail_first_line {
+            let skip = line - self.tail_first_line;
+            let start = nth_line_start(&self.tail, skip);
+            let end = memchr(b'\n', &self.tail[start..]).map_or(self.tail.len(), |p| start + p);
+            return Some(&self.tail[start..end]);
+        }
+
+        let bi = self.blocks.partition_point(|b| b.first_line + b.line_count <= line);
+        self.load_block(bi);
+        let cache = &self.cache;
+        let i = line - self.blocks[bi].first_line;
+        let start = cache.starts[i];
+        let end = cache.starts.get(i + 1).map_or(cache.data.len(), |&s| s) - 1;
+        Some(&cache.data[start..end])
+    }
+
+    fn load_block(&mut self, bi: usize) {
+        if self.cache.block == Some(bi) {
+            return;
+        }
+        let block = &self.blocks[bi];
+        let cache = &mut self.cache;
+        cache.data.clear();
+        cache.data.resize(block.raw_len, 0);
+        lz4_flex::block::decompress_into(&block.data, &mut cache.data).expect("blocks are compressed by this store");
+        cache.starts.clear();
+        cache.starts.push(0);
+        cache.starts.extend(memchr_iter(b'\n', &cache.data).map(|p| p + 1));
+        cache.starts.pop();
+        cache.block = Some(bi);
+    }
+
+    /// `lines` 구간의 원본 바이트를 블록별로 넘겨줍니다: `f(첫 라인 번호, 라인들)` (라인마다 `\n` 으로 끝남, 마지막 제외 가능)
+    pub fn for_each_chunk<F>(&mut self, lines: Range<usize>, mut f: F)
+    where
+        F: FnMut(usize, &[u8]),
+    {
+        let end = lines.end.min(self.line_count());
+        let mut line = lines.start;
+        if line >= end {
+            return;
+        }
+
+        // 블록은 캐시로 풀어서, 같은 블록을 이어서 보는 다음 호출(`get_lines` 스크롤 등)은 다시 풀지 않습니다.
+        let first_block = self.blocks.partition_point(|b| b.first_line + b.line_count <= line);
+        for bi in first_block..self.blocks.len() {
+            let (first_line, line_count) = (self.blocks[bi].first_line, self.blocks[bi].line_count);
+            if first_line >= end {
+                break;
+            }
+            self.load_block(bi);
+            let cache = &self.cache;
+            let to = end - first_line;
+            let start = cache.starts[line - first_line];
+            let stop = cache.starts.get(to).map_or(cache.data.len(), |&s| s);
+            f(line, &cache.data[start..stop]);
+            line = (first_line + line_count).min(end);
+        }
+
+        if line < end && line < self.tail_first_line + self.tail_lines {
+            let data = line_slice(&self.tail, line - self.tail_first_line, end - self.tail_first_line);
+            f(line, data);
+            line = (self.tail_first_line + self.tail_lines).min(end);
+        }
+        if line < end {
+            f(line, &self.pending);
+        }
+    }
+
+    /// `\n` 까지 들어온 라인 수 (`\n` 이 아직 안 나온 마지막 라인 제외)
+    pub fn complete_line_count(&self) -> usize {
+        self.tail_first_line + self.tail_lines
+    }
+
+    /// `first_line` 이후 라인을 필터링해서 매칭된 라인 번호 (스트림에서 새로 들어온 라인만 다시 볼 때 `first_line` 사용)
+    ///
+    /// 아직 `\n` 이 안 나온 마지막 라인은 잘린 채로 룰에 걸리지 않도록 빼고 봅니다.
+    /// 다음 호출의 `first_line` 은 `complete_line_count()` 를 넘기면 그 라인이 끝난 뒤에 한 번 평가됩니다. (`finish` 후에는 포함)
+    ///
+    /// 블록 사이에서는 앞 블록 마지막 라인의 시각을 이어받습니다. (`times` 가 `Parse` 일 때)
+    pub fn filter(&mut self, engine: &mut FilterEngine, first_line: usize, mut times: LineTimes) -> Vec<i32> {
+        let mut matches = Vec::new();
+        self.for_each_chunk(first_line..self.complete_line_count(), |base, data| {
+            matches.extend(engine.filter_lines_with(data, None, base as i32, times));
+            if let LineTimes::Parse(_) = times {
+                times = LineTimes::Parse(engine.last_line_time());
+            }
+        });
+        matches
+    }
+}
+
+/// `\n` 으로 끝나는 라인들에서 `skip` 번째 라인의 시작 오프셋
+fn nth_line_start(data: &[u8], skip: usize) -> usize {
+    if skip == 0 {
+        return 0;
+    }
+    memchr_iter(b'\n', data).nth(skip - 1).map_or(data.len(), |p| p + 1)
+}
+
+/// 꼬리 원본에서 `[from, to)` 번째 라인 구간
+fn line_slice(data: &[u8], from: usize, to: usize) -> &[u8] {
+    let start = nth_line_start(data, from);
+    let end = start + nth_line_start(&data[start..], to - from);
+    &data[start..end]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::time::NO_TIME;
+
+    fn store() -> LineStore {
+        // 블록 하나에 라인 두세 개
+        let mut store = LineStore::new(12);
+        for chunk in b"wifi one\nbeta\nwifi two\ngamma\ndelta\nwifi three\nepsilon\nwifi par".chunks(5) {
+            store.append(chunk);
+        }
+        store
+    }
+
+    fn lines(store: &mut LineStore, range: Range<usize>) -> Vec<u8> {
+        let mut out = Vec::new();
+        store.for_each_chunk(range, |_, data| out.extend_from_slice(data));
+        out
+    }
+
+    #[test]
+    fn random_access_across_blocks_tail_and_pending() {
+        let mut store = store();
+        assert!(store.blocks.len() >= 2);
+        assert_eq!(store.line_count(), 8);
+        assert_eq!(store.complete_line_count(), 7);
+        assert_eq!(store.line(0).unwrap(), b"wifi one");
+        assert_eq!(store.line(5).unwrap(), b"wifi three");
+        assert_eq!(store.line(1).unwrap(), b"beta");
+        assert_eq!(store.line(7).unwrap(), b"wifi par");
+        assert!(store.line(8).is_none());
+        assert_eq!(lines(&mut store, 1..4), b"beta\nwifi two\ngamma\n");
+        assert_eq!(lines(&mut store, 6..100), b"epsilon\nwifi par");
+        assert!(lines(&mut store, 9..100).is_empty());
+    }
+
+    #[test]
+    fn chunk_reads_reuse_the_decoded_block() {
+        let mut store = store();
+        assert_eq!(lines(&mut store, 0..1), b"wifi one\n");
+        let block = store.cache.block;
+        assert_eq!(block, Some(0));
+        let data = store.cache.data.as_ptr();
+        assert_eq!(lines(&mut store, 1..2), b"beta\n");
+        assert_eq!((store.cache.block, store.cache.data.as_ptr()), (block, data));
+        assert_eq!(store.line(0).unwrap(), b"wifi one");
+        assert_eq!(store.cache.block, block);
+    }
+
+    #[test]
+    fn filter_waits_for_the_pending_line() {
+        let mut store = store();
+        let mut engine = FilterEngine::new(false);
+        engine.set_groups(vec![vec!["wifi".to_string()]]).unwrap();
+        assert_eq!(store.filter(&mut engine, 0, LineTimes::Parse(NO_TIME)), [0, 2, 5]);
+
+        let next = store.complete_line_count();
+        store.append(b"tial\nwifi");
+        assert_eq!(store.filter(&mut engine, next, LineTimes::Parse(NO_TIME)), [7]);
+
+        let next = store.complete_line_count();
+        store.finish();
+        assert_eq!(store.filter(&mut engine, next, LineTimes::Parse(NO_TIME)), [8]);
+    }
+}
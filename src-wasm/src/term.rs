@@ -0,0 +1,190 @@
+use aho_corasick::{AhoCorasick, AhoCorasickBuilder, MatchKind};
+use regex::bytes::{Regex, RegexBuilder};
+
+use crate::error::FilterError;
+
+/// 이 접두어로 시작하는 단어는 정규식으로 취급합니다. (예: `re:pid=\d+`)
+pub const REGEX_PREFIX: &str = "re:";
+
+/// 파싱된 헤더 필드 조건 접두어 (예: `field:level>=W`, `field:tag:WifiManager`) - `predicate::FieldPredicate`
+pub const FIELD_PREFIX: &str = "field:";
+
+/// Happy Combo 그룹 안에서만 쓰는 부정 접두어 (예: `not:debug`, `not:re:pid=\d+`)
+pub const NOT_PREFIX: &str = "not:";
+
+/// 단어 앞에 붙이면 나머지를 접두어 해석 없이 글자 그대로 찾습니다. (예: `\re:x` = 리터럴 `re:x`, `not:\not:x`)
+///
+/// 호환성: 접두어가 생기기 전에 저장된 `LogRule` 키워드 중 `re:` / `not:` / `field:` 로 시작하는 것은 이제
+/// 정규식/부정/필드 조건으로 해석됩니다. 예전처럼 글자 그대로 찾으려면 앞에 `\` 를 붙여야 하고,
+/// 원래 `\` 로 시작하던 키워드는 `\\` 로 써야 합니다.
+pub const ESCAPE_PREFIX: char = '\\';
+
+/// 모든 접두어 (`escape_literal` 에서 이스케이프가 필요한지 판단)
+const PREFIXES: [&str; 3] = [REGEX_PREFIX, FIELD_PREFIX, NOT_PREFIX];
+
+/// 정규식 컴파일 크기 제한 (거대한 반복 패턴으로 워커가 메모리를 다 먹는 것 방지)
+pub(crate) const REGEX_SIZE_LIMIT: usize = 1 << 20;
+
+/// Happy Combo / Block List 의 단어 하나
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Term {
+    Literal(String),
+    Regex(String),
+    /// `field:` 뒤의 조건 본문 (컴파일은 `FieldPredicate::parse`)
+    Field(String),
+}
+
+impl Term {
+    /// trim 후 빈 단어는 `None`. 대소문자 무시일 때 리터럴은 소문자로 정규화합니다. (중복 제거용)
+    /// (정규식은 `\D` 같은 클래스가 깨지므로 패턴을 건드리지 않고 컴파일 플래그로 처리)
+    ///
+    /// 대소문자 무시 + 비ASCII 대소문자 문자(예: `Ä`, `Ж`)가 있는 리터럴은 이스케이프된 정규식으로 바꿉니다.
+    /// Aho-Corasick 은 ASCII 대소문자만 접어주므로, 나머지는 정규식의 유니코드 case folding 에 맡깁니다.
+    /// `ESCAPE_PREFIX` 로 시작하면 그 한 글자를 빼고 리터럴로 취급합니다.
+    pub fn parse(raw: &str, case_sensitive: bool) -> Option<Term> {
+        let raw = raw.trim();
+        let raw = match raw.strip_prefix(ESCAPE_PREFIX) {
+            Some(literal) => literal,
+            None => {
+                if let Some(predicate) = raw.strip_prefix(FIELD_PREFIX) {
+                    let predicate = predicate.trim();
+                    return if predicate.is_empty() { None } else { Some(Term::Field(predicate.to_string())) };
+                }
+                if let Some(pattern) = raw.strip_prefix(REGEX_PREFIX) {
+                    return if pattern.is_empty() { None } else { Some(Term::Regex(pattern.to_string())) };
+                }
+                raw
+            }
+        };
+        if raw.is_empty() {
+            return None;
+        }
+        if case_sensitive {
+            return Some(Term::Literal(raw.to_string()));
+        }
+        let lower = raw.to_lowercase();
+        if needs_unicode_folding(&lower) {
+            Some(Term::Regex(regex::escape(&lower)))
+        } else {
+            Some(Term::Literal(lower))
+        }
+    }
+}
+
+/// 글자 그대로 찾을 단어를 `Term::parse` 가 리터럴로 읽도록 필요할 때만 `ESCAPE_PREFIX` 를 붙입니다.
+pub fn escape_literal(word: &str) -> String {
+    let trimmed = word.trim_start();
+    if trimmed.starts_with(ESCAPE_PREFIX) || PREFIXES.iter().any(|p| trimmed.starts_with(p)) {
+        format!("{}{}", ESCAPE_PREFIX, trimmed)
+    } else {
+        word.to_string()
+    }
+}
+
+/// `not:` 접두어 분리 (앞 공백 무시)
+pub fn split_negation(raw: &str) -> (bool, &str) {
+    match raw.trim_start().strip_prefix(NOT_PREFIX) {
+        Some(rest) => (true, rest),
+        None => (false, raw),
+    }
+}
+
+/// ASCII 외에 대소문자 구분이 있는 문자가 있는지 (한글처럼 대소문자가 없는 문자는 해당 없음)
+fn needs_unicode_folding(s: &str) -> bool {
+    s.chars().any(|c| {
+        !c.is_ascii() && (c.to_lowercase().ne(std::iter::once(c)) || c.to_uppercase().ne(std::iter::once(c)))
+    })
+}
+
+/// 리터럴 단어용 Aho-Corasick 빌드. 대소문자 무시는 오토마톤 안에서 ASCII folding 으로 처리합니다.
+/// (라인을 소문자로 복사하지 않으므로 오프셋이 원본 바이트와 그대로 일치)
+pub fn build_literals(literals: &[String], kind: MatchKind, case_sensitive: bool) -> Result<Option<AhoCorasick>, FilterError> {
+    if literals.is_empty() {
+        return Ok(None);
+    }
+    let ac = AhoCorasickBuilder::new()
+        .match_kind(kind)
+        .ascii_case_insensitive(!case_sensitive)
+        .prefilter(true)
+        .build(literals)?;
+    Ok(Some(ac))
+}
+
+/// 선형 시간 정규식 엔진(regex crate)으로 컴파일 - 백트래킹이 없어서 나쁜 패턴도 워커를 멈추지 않습니다.
+pub fn compile_regex(pattern: &str, case_sensitive: bool) -> Result<Regex, FilterError> {
+    RegexBuilder::new(pattern)
+        .case_insensitive(!case_sensitive)
+        .size_limit(REGEX_SIZE_LIMIT)
+        .build()
+        .map_err(|e| FilterError::Regex { pattern: pattern.to_string(), message: e.to_string() })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(raw: &str) -> Option<Term> {
+        Term::parse(raw, true)
+    }
+
+    #[test]
+    fn prefixes() {
+        assert_eq!(parse("  wifi "), Some(Term::Literal("wifi".into())));
+        assert_eq!(parse(r"re:pid=\d+"), Some(Term::Regex(r"pid=\d+".into())));
+        assert_eq!(parse("field: level>=W "), Some(Term::Field("level>=W".into())));
+        assert_eq!(parse("re:"), None);
+        assert_eq!(parse("field:"), None);
+        assert_eq!(parse("   "), None);
+    }
+
+    #[test]
+    fn escape_keeps_prefixed_words_literal() {
+        assert_eq!(parse(r"\re:x"), Some(Term::Literal("re:x".into())));
+        assert_eq!(parse(r"\field:tag:A"), Some(Term::Literal("field:tag:A".into())));
+        assert_eq!(parse(r"\\n"), Some(Term::Literal(r"\n".into())));
+        assert_eq!(parse(r"\"), None);
+        assert_eq!(split_negation(r"\not:x"), (false, r"\not:x"));
+        assert_eq!(split_negation(r"not:\not:x"), (true, r"\not:x"));
+        assert_eq!(parse(split_negation(r"not:\not:x").1), Some(Term::Literal("not:x".into())));
+    }
+
+    #[test]
+    fn escape_literal_round_trips() {
+        for word in ["plain", "re:x", "not: found", "field:pid:1", r"\back", "  re:x"] {
+            assert!(!split_negation(&escape_literal(word)).0, "{}", word);
+            assert_eq!(parse(&escape_literal(word)), Some(Term::Literal(word.trim().to_string())), "{}", word);
+        }
+        assert_eq!(escape_literal("plain"), "plain");
+    }
+
+    #[test]
+    fn case_insensitive_literals_are_normalized() {
+        assert_eq!(Term::parse("WiFi", false), Some(Term::Literal("wifi".into())));
+        assert_eq!(Term::parse("WiFi", true), Some(Term::Literal("WiFi".into())));
+        // 대소문자가 없는 비ASCII 문자는 리터럴 그대로
+        assert_eq!(Term::parse("연결 실패", false), Some(Term::Literal("연결 실패".into())));
+        // 비ASCII 대소문자는 유니코드 folding 을 위해 정규식으로
+        assert_eq!(Term::parse("Ärger", false), Some(Term::Regex("ärger".into())));
+        assert_eq!(Term::parse("a.Ä", false), Some(Term::Regex(r"a\.ä".into())));
+    }
+
+    #[test]
+    fn ascii_folding_happens_inside_the_automaton() {
+        let ac = build_literals(&["wifi".to_string()], MatchKind::LeftmostFirst, false).unwrap().unwrap();
+        let m = ac.find(&b"x WIFI y"[..]).unwrap();
+        assert_eq!((m.start(), m.end()), (2, 6));
+        assert!(build_literals(&[], MatchKind::LeftmostFirst, false).unwrap().is_none());
+    }
+
+    #[test]
+    fn unicode_folding_through_regex() {
+        let re = compile_regex(&regex::escape("ärger"), false).unwrap();
+        assert!(re.is_match("GROSSER ÄRGER".as_bytes()));
+        assert!(!compile_regex("ärger", true).unwrap().is_match("ÄRGER".as_bytes()));
+    }
+
+    #[test]
+    fn oversized_regex_is_rejected() {
+        assert!(matches!(compile_regex(r"\w{1000}{1000}", false), Err(FilterError::Regex { .. })));
+    }
+}
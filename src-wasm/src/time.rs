@@ -0,0 +1,638 @@
+use memchr::{memchr_iter, memmem};
+use serde::{Deserialize, Serialize};
+
+use crate::error::FilterError;
+use crate::line;
+
+/// 타임스탬프가 없는 라인 (`BigInt64Array` 에서 `-(2n ** 63n)`)
+pub const NO_TIME: i64 = i64::MIN;
+
+pub(crate) const MICROS_PER_SEC: i64 = 1_000_000;
+pub(crate) const MICROS_PER_DAY: i64 = 86_400 * MICROS_PER_SEC;
+
+/// `TimeSpec::year_rollover_days` 기본값
+pub const DEFAULT_YEAR_ROLLOVER_DAYS: u32 = 30;
+/// `TimeSpec::day_rollover_hours` 기본값
+pub const DEFAULT_DAY_ROLLOVER_HOURS: u32 = 12;
+
+/// `TimeParser::seed` 가 날짜로 보는 최소값 (2000-01-01, 그 아래는 monotonic 으로 봅니다)
+const CIVIL_SEED_MIN: i64 = 10_957 * MICROS_PER_DAY;
+
+/// 헤더로 보는 앞부분 (메시지 안의 `200000.0` 같은 숫자를 시간으로 잡지 않도록)
+const PREAMBLE_LEN: usize = 256;
+
+/// 타임스탬프 추출 설정
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase", default)]
+pub struct TimeSpec {
+    /// `"auto"` (logTime.ts 의 `extractTimestamp` 규칙) 또는 사용자 형식 (`"YYYY/MM/DD HH:mm:ss.SSS"`)
+    ///
+    /// 형식 토큰: `YYYY` `YY` `MM` `DD` `HH` `mm` `ss` `S..S`(소수 자릿수만큼), 공백은 공백 1개 이상, 나머지는 그대로 일치
+    pub format: String,
+    /// 년/월/일이 없는 로그의 기준 날짜 `"YYYY-MM-DD"` (보통 파일 수정 시각). 없으면 1970-01-01
+    ///
+    /// 로그는 수정 시각 전에 쓰였으므로 첫 `MM-DD` 라인이 기준 날짜보다 뒤면 그 전 해로 봅니다. (01-02 기준의 12-31 = 작년)
+    pub reference_date: Option<String>,
+    /// 로그 시각의 UTC 오프셋 (분, KST = 540). JS 에서는 `-new Date().getTimezoneOffset()`
+    pub utc_offset_minutes: i32,
+    /// 년도 없는 로그(`MM-DD`)에서 직전 라인보다 이 일수 넘게 거꾸로 가면 해가 바뀐 것으로 봅니다. (12-31 → 01-01, 0 = 안 함)
+    pub year_rollover_days: u32,
+    /// 날짜 없는 로그(`HH:mm:ss`)에서 직전 라인보다 이 시간 넘게 거꾸로 가면 자정을 넘은 것으로 봅니다. (23:59 → 00:00, 0 = 안 함)
+    pub day_rollover_hours: u32,
+}
+
+impl Default for TimeSpec {
+    fn default() -> Self {
+        TimeSpec {
+            format: "auto".to_string(),
+            reference_date: None,
+            utc_offset_minutes: 0,
+            year_rollover_days: DEFAULT_YEAR_ROLLOVER_DAYS,
+            day_rollover_hours: DEFAULT_DAY_ROLLOVER_HOURS,
+        }
+    }
+}
+
+/// 라인에서 읽은 값 (년/월/일은 빠질 수 있음)
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Stamp {
+    Civil(Civil),
+    /// 부팅 후 경과 시간 (커널 `[ 123.456]`, 모노토닉 초) - 날짜 계산 없이 그대로 마이크로초
+    Monotonic(i64),
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+struct Civil {
+    year: Option<i32>,
+    month_day: Option<(u32, u32)>,
+    hour: u32,
+    minute: u32,
+    second: u32,
+    micros: u32,
+}
+
+/// 사용자 형식 토큰
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Token {
+    Year4,
+    Year2,
+    Month,
+    Day,
+    Hour,
+    Minute,
+    Second,
+    /// 소수 자릿수
+    Fraction(usize),
+    Space,
+    Literal(u8),
+}
+
+fn compile_format(format: &str) -> Result<Vec<Token>, FilterError> {
+    let error = |message: &str| FilterError::TimeFormat { format: format.to_string(), message: message.to_string() };
+    let bytes = format.as_bytes();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < bytes.len() {
+        let rest = &bytes[i..];
+        let (token, len) = if rest.starts_with(b"YYYY") {
+            (Token::Year4, 4)
+        } else if rest.starts_with(b"YY") {
+            (Token::Year2, 2)
+        } else if rest.starts_with(b"MM") {
+            (Token::Month, 2)
+        } else if rest.starts_with(b"DD") {
+            (Token::Day, 2)
+        } else if rest.starts_with(b"HH") {
+            (Token::Hour, 2)
+        } else if rest.starts_with(b"mm") {
+            (Token::Minute, 2)
+        } else if rest.starts_with(b"ss") {
+            (Token::Second, 2)
+        } else if rest[0] == b'S' {
+            let n = rest.iter().take_while(|&&b| b == b'S').count();
+            if n > 9 {
+                return Err(error("fraction supports up to 9 digits"));
+            }
+            (Token::Fraction(n), n)
+        } else if rest[0].is_ascii_whitespace() {
+            (Token::Space, rest.iter().take_while(|b| b.is_ascii_whitespace()).count())
+        } else if rest[0].is_ascii_alphabetic() {
+            return Err(error(&format!("unknown token at {}", i)));
+        } else {
+            (Token::Literal(rest[0]), 1)
+        };
+        tokens.push(token);
+        i += len;
+    }
+    if !tokens.iter().any(|t| matches!(t, Token::Hour | Token::Day)) {
+        return Err(error("needs at least HH or DD"));
+    }
+    Ok(tokens)
+}
+
+/// `s[i..i + n]` 이 숫자면 그 값
+pub(crate) fn digits(s: &[u8], i: usize, n: usize) -> Option<u32> {
+    let part = s.get(i..i + n)?;
+    part.iter().try_fold(0u32, |acc, &b| b.is_ascii_digit().then(|| acc * 10 + (b - b'0') as u32))
+}
+
+/// 소수 부분 (최대 6자리까지 마이크로초로)
+pub(crate) fn fraction_micros(part: &[u8]) -> u32 {
+    let mut micros = 0;
+    for i in 0..6 {
+        micros = micros * 10 + part.get(i).map_or(0, |&b| (b - b'0') as u32);
+    }
+    micros
+}
+
+fn valid(civil: &Civil) -> bool {
+    let date_ok = civil.month_day.is_none_or(|(m, d)| (1..=12).contains(&m) && (1..=31).contains(&d));
+    date_ok && civil.hour < 24 && civil.minute < 60 && civil.second < 61
+}
+
+/// 사용자 형식을 `line[start..]` 에 맞춰 봅니다.
+fn match_format(tokens: &[Token], line: &[u8], start: usize) -> Option<Civil> {
+    let mut civil = Civil::default();
+    let (mut month, mut day) = (None, None);
+    let mut i = start;
+    for &token in tokens {
+        match token {
+            Token::Year4 => {
+                civil.year = Some(digits(line, i, 4)? as i32);
+                i += 4;
+            }
+            Token::Year2 => {
+                civil.year = Some(2000 + digits(line, i, 2)? as i32);
+                i += 2;
+            }
+            Token::Month => {
+                month = Some(digits(line, i, 2)?);
+                i += 2;
+            }
+            Token::Day => {
+                day = Some(digits(line, i, 2)?);
+                i += 2;
+            }
+            Token::Hour => {
+                civil.hour = digits(line, i, 2)?;
+                i += 2;
+            }
+            Token::Minute => {
+                civil.minute = digits(line, i, 2)?;
+                i += 2;
+            }
+            Token::Second => {
+                civil.second = digits(line, i, 2)?;
+                i += 2;
+            }
+            Token::Fraction(n) => {
+                digits(line, i, n)?;
+                civil.micros = fraction_micros(&line[i..i + n]);
+                i += n;
+            }
+            Token::Space => {
+                let n = line[i.min(line.len())..].iter().take_while(|b| b.is_ascii_whitespace()).count();
+                if n == 0 {
+                    return None;
+                }
+                i += n;
+            }
+            Token::Literal(b) => {
+                if line.get(i) != Some(&b) {
+                    return None;
+                }
+                i += 1;
+            }
+        }
+    }
+    // 월/일 중 하나만 있는 형식은 날짜 없는 것으로 취급
+    if let (Some(m), Some(d)) = (month, day) {
+        civil.month_day = Some((m, d));
+    }
+    valid(&civil).then_some(civil)
+}
+
+/// 헤더 경계를 찾는 검색기 (라인마다 만들지 않도록 미리 생성)
+struct Boundaries {
+    arrow: memmem::Finder<'static>,
+    colon: memmem::Finder<'static>,
+    meta: memmem::Finder<'static>,
+}
+
+impl Boundaries {
+    fn new() -> Self {
+        Boundaries { arrow: memmem::Finder::new(b" > "), colon: memmem::Finder::new(b": "), meta: memmem::Finder::new(b")>") }
+    }
+}
+
+/// logTime.ts 의 헤더 경계: ` > ` 앞, 아니면 17번째 글자 이후의 `: ` 앞, `)>` (C# 메타) 가 더 뒤면 거기까지
+fn preamble<'a>(line: &'a [u8], finders: &Boundaries) -> &'a [u8] {
+    let mut boundary = PREAMBLE_LEN;
+    if let Some(arrow) = finders.arrow.find(line) {
+        boundary = arrow;
+    } else if let Some(colon) = finders.colon.find(line) {
+        if colon > 16 && colon < boundary {
+            boundary = colon;
+        }
+    }
+    if let Some(meta) = finders.meta.find(&line[..line.len().min(PREAMBLE_LEN)]) {
+        if meta + 2 < PREAMBLE_LEN && meta + 2 > boundary {
+            boundary = meta + 2;
+        }
+    }
+    &line[..boundary.min(line.len())]
+}
+
+/// `(YYYY-)?(MM-DD\s+)?HH:mm:ss.fff` 를 `colon - 2` 에서 찾습니다: (시작, 끝, 값)
+fn match_standard(s: &[u8], colon: usize) -> Option<(usize, usize, Civil)> {
+    let start = colon.checked_sub(2)?;
+    let hour = digits(s, start, 2)?;
+    if s.get(start + 5) != Some(&b':') || s.get(start + 8) != Some(&b'.') {
+        return None;
+    }
+    let minute = digits(s, start + 3, 2)?;
+    let second = digits(s, start + 6, 2)?;
+    digits(s, start + 9, 3)?;
+    let frac_len = s[start + 9..].iter().take(6).take_while(|b| b.is_ascii_digit()).count();
+    let end = start + 9 + frac_len;
+    let mut civil = Civil {
+        hour,
+        minute,
+        second,
+        micros: fraction_micros(&s[start + 9..end]),
+        ..Civil::default()
+    };
+
+    // 앞의 `MM-DD ` / `YYYY-MM-DD `
+    let mut first = start;
+    let spaces = s[..start].iter().rev().take_while(|b| b.is_ascii_whitespace()).count();
+    if spaces > 0 && start >= spaces + 5 {
+        let date = start - spaces - 5;
+        if s[date + 2] == b'-' {
+            if let (Some(m), Some(d)) = (digits(s, date, 2), digits(s, date + 3, 2)) {
+                civil.month_day = Some((m, d));
+                first = date;
+                if date >= 5 && s[date - 1] == b'-' {
+                    if let Some(y) = digits(s, date - 5, 4) {
+                        civil.year = Some(y as i32);
+                        first = date - 5;
+                    }
+                }
+            }
+        }
+    }
+    valid(&civil).then_some((first, end, civil))
+}
+
+/// 정수 부분 시작부터 `\d+\.\d+` 를 읽어 마이크로초로: (끝, 값, 소수 자릿수)
+fn read_seconds(s: &[u8], start: usize) -> Option<(usize, i64, usize)> {
+    let int_len = s[start..].iter().take_while(|b| b.is_ascii_digit()).count();
+    if int_len == 0 || int_len > 12 || s.get(start + int_len) != Some(&b'.') {
+        return None;
+    }
+    let frac_start = start + int_len + 1;
+    let frac_len = s[frac_start..].iter().take_while(|b| b.is_ascii_digit()).count();
+    if frac_len == 0 {
+        return None;
+    }
+    let secs = s[start..start + int_len].iter().fold(0i64, |acc, &b| acc * 10 + (b - b'0') as i64);
+    let micros = secs * MICROS_PER_SEC + fraction_micros(&s[frac_start..frac_start + frac_len]) as i64;
+    Some((frac_start + frac_len, micros, frac_len))
+}
+
+/// `auto` 형식: 헤더 안의 후보(표준 시각, 모노토닉 초) 중 가장 오른쪽 것
+///
+/// `standard` 는 표준 시각 구간을 담는 재사용 버퍼 (그 안의 `ss.fff` 를 모노토닉으로 잡지 않도록)
+fn scan_auto(line: &[u8], finders: &Boundaries, standard: &mut Vec<(usize, usize)>) -> Option<Stamp> {
+    let s = preamble(line, finders);
+    let mut best: Option<(usize, Stamp)> = None;
+    standard.clear();
+    let consider = |index: usize, stamp: Stamp, best: &mut Option<(usize, Stamp)>| {
+        if best.is_none_or(|(i, _)| index > i) {
+            *best = Some((index, stamp));
+        }
+    };
+
+    for colon in memchr_iter(b':', s) {
+        if let Some((start, end, civil)) = match_standard(s, colon) {
+            standard.push((start, end));
+            consider(start, Stamp::Civil(civil), &mut best);
+        }
+    }
+
+    // 모노토닉: (시작|공백|`:`|`[`) \d+.\d{3,} (공백|`]`|`:`|끝)
+    for dot in memchr_iter(b'.', s) {
+        let int_len = s[..dot].iter().rev().take_while(|b| b.is_ascii_digit()).count();
+        let start = dot - int_len;
+        if int_len == 0 || standard.iter().any(|&(a, b)| start >= a && start < b) {
+            continue;
+        }
+        let before_ok = start == 0 || matches!(s[start - 1], b':' | b'[') || s[start - 1].is_ascii_whitespace();
+        let Some((end, micros, frac_len)) = read_seconds(s, start) else { continue };
+        let after_ok = end == s.len() || matches!(s[end], b']' | b':') || s[end].is_ascii_whitespace();
+        if before_ok && after_ok && frac_len >= 3 {
+            consider(start, Stamp::Monotonic(micros), &mut best);
+        }
+    }
+
+    // 맨 앞의 짧은 모노토닉 `12.34`, `[ 5.6]`
+    if best.is_none() {
+        let mut i = s.iter().take_while(|b| b.is_ascii_whitespace()).count();
+        if s.get(i) == Some(&b'[') {
+            i += 1 + s[i + 1..].iter().take_while(|b| b.is_ascii_whitespace()).count();
+        }
+        if let Some((_, micros, _)) = read_seconds(s, i) {
+            best = Some((i, Stamp::Monotonic(micros)));
+        }
+    }
+
+    best.map(|(_, stamp)| stamp)
+}
+
+/// 1970-01-01 부터의 일수 (proleptic Gregorian)
+pub(crate) fn days_from_civil(year: i32, month: u32, day: u32) -> i64 {
+    let y = year as i64 - (month <= 2) as i64;
+    let era = y.div_euclid(400);
+    let yoe = y - era * 400;
+    let m = month as i64;
+    let doy = (153 * (if m > 2 { m - 3 } else { m + 9 }) + 2) / 5 + day as i64 - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146_097 + doe - 719_468
+}
+
+fn civil_from_days(days: i64) -> (i32, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    ((yoe + era * 400 + (month <= 2) as i64) as i32, month, day)
+}
+
+/// `"YYYY-MM-DD"` (뒤에 시각이 붙어 있어도 날짜만 사용)
+pub(crate) fn parse_date(s: &str) -> Option<(i32, u32, u32)> {
+    let b = s.trim().as_bytes();
+    if b.get(4) != Some(&b'-') || b.get(7) != Some(&b'-') {
+        return None;
+    }
+    let (y, m, d) = (digits(b, 0, 4)?, digits(b, 5, 2)?, digits(b, 8, 2)?);
+    ((1..=12).contains(&m) && (1..=31).contains(&d)).then_some((y as i32, m, d))
+}
+
+/// 라인별 타임스탬프 추출기 (epoch 마이크로초)
+///
+/// 로그에 년/월/일이 없으면 기준 날짜로 채우고, 시각이 크게 거꾸로 가면 날짜/년도가 넘어간 것으로 봅니다.
+/// (기준은 `TimeSpec::year_rollover_days` / `day_rollover_hours`)
+/// 상태(현재 날짜, 직전 시각)가 라인 사이에 이어지므로 파일을 앞에서부터 순서대로 넣어야 합니다.
+pub struct TimeParser {
+    tokens: Option<Vec<Token>>,
+    reference: (i32, u32, u32),
+    /// `reference_date` 를 받았는지 (1970-01-01 기본값이면 전 해로 되돌리지 않음)
+    has_reference: bool,
+    offset_micros: i64,
+    /// 해/날짜가 넘어간 것으로 보는 역행 폭 (마이크로초, `None` = 넘기지 않음)
+    year_rollover: Option<i64>,
+    day_rollover: Option<i64>,
+    /// 년/월/일 없는 라인에 쓰는 현재 날짜
+    date: (i32, u32, u32),
+    last: Option<i64>,
+    finders: Boundaries,
+    ranges: Vec<(usize, usize)>,
+    clean_buffer: Vec<u8>,
+}
+
+impl TimeParser {
+    pub fn new(spec: &TimeSpec) -> Result<Self, FilterError> {
+        let tokens = match spec.format.trim() {
+            "" | "auto" => None,
+            format => Some(compile_format(format)?),
+        };
+        let reference = match &spec.reference_date {
+            Some(date) => parse_date(date).ok_or_else(|| FilterError::TimeFormat {
+                format: date.clone(),
+                message: "reference date must be YYYY-MM-DD".to_string(),
+            })?,
+            None => (1970, 1, 1),
+        };
+        Ok(TimeParser {
+            tokens,
+            reference,
+            has_reference: spec.reference_date.is_some(),
+            offset_micros: spec.utc_offset_minutes as i64 * 60 * MICROS_PER_SEC,
+            year_rollover: (spec.year_rollover_days > 0).then(|| spec.year_rollover_days as i64 * MICROS_PER_DAY),
+            day_rollover: (spec.day_rollover_hours > 0).then(|| spec.day_rollover_hours as i64 * 3600 * MICROS_PER_SEC),
+            date: reference,
+            last: None,
+            finders: Boundaries::new(),
+            ranges: Vec::new(),
+            clean_buffer: Vec::new(),
+        })
+    }
+
+    /// 기준 날짜로 되돌림 (파일을 처음부터 다시 읽을 때)
+    pub fn reset(&mut self) {
+        self.date = self.reference;
+        self.last = None;
+    }
+
+    /// 직전 라인의 시각이 `seed` 인 것처럼 이어서 읽습니다. (청크/구간을 따로 읽을 때 년도·날짜 추정용)
+    ///
+    /// `NO_TIME` 이나 2000년 이전 값(커널 부팅 후 시간 같은 monotonic 값)이면 `reset` 과 같습니다.
+    pub fn seed(&mut self, seed: i64) {
+        self.reset();
+        if seed >= CIVIL_SEED_MIN {
+            self.date = civil_from_days((seed + self.offset_micros).div_euclid(MICROS_PER_DAY));
+            self.last = Some(seed);
+        }
+    }
+
+    /// 라인 하나의 타임스탬프 (없으면 `NO_TIME`). ANSI 이스케이프는 무시합니다.
+    pub fn parse_line(&mut self, raw: &[u8]) -> i64 {
+        let mut buf = std::mem::take(&mut self.clean_buffer);
+        let stamp = self.parse_clean(line::clean_line(raw, &mut buf));
+        self.clean_buffer = buf;
+        stamp
+    }
+
+    /// `parse_line` 과 같지만 이미 `line::clean_line` 으로 정리한 라인
+    pub fn parse_clean(&mut self, line: &[u8]) -> i64 {
+        let stamp = match &self.tokens {
+            None => scan_auto(line, &self.finders, &mut self.ranges),
+            Some(tokens) => {
+                let limit = line.len().min(PREAMBLE_LEN);
+                (0..limit).find_map(|start| match_format(tokens, line, start)).map(Stamp::Civil)
+            }
+        };
+        match stamp {
+            None => NO_TIME,
+            Some(Stamp::Monotonic(micros)) => micros,
+            Some(Stamp::Civil(civil)) => self.resolve(civil),
+        }
+    }
+
+    /// 청크의 라인별 타임스탬프를 `out` 에 추가 (라인 분리는 `filter_chunk` 와 동일)
+    pub fn parse_lines(&mut self, data: &[u8], line_offsets: Option<&[u32]>, out: &mut Vec<i64>) {
+        line::for_each_line(data, line_offsets, |_, raw| out.push(self.parse_line(raw)));
+    }
+
+    fn to_micros(&self, (year, month, day): (i32, u32, u32), c: &Civil) -> i64 {
+        let secs = days_from_civil(year, month, day) * 86_400 + (c.hour * 3600 + c.minute * 60 + c.second) as i64;
+        secs * MICROS_PER_SEC + c.micros as i64 - self.offset_micros
+    }
+
+    /// 직전 라인보다 `rollover` 넘게 거꾸로 갔는지
+    fn rolled_over(&self, micros: i64, rollover: Option<i64>) -> bool {
+        matches!((self.last, rollover), (Some(last), Some(rollover)) if micros < last - rollover)
+    }
+
+    fn resolve(&mut self, c: Civil) -> i64 {
+        let micros = match (c.year, c.month_day) {
+            (Some(year), Some((month, day))) => {
+                self.date = (year, month, day);
+                self.to_micros(self.date, &c)
+            }
+            (None, Some((month, day))) => {
+                let mut date = (self.date.0, month, day);
+                // 첫 라인이 기준 날짜(파일 수정 시각)보다 뒤면 작년 로그
+                if self.last.is_none() && self.has_reference && (month, day) > (self.date.1, self.date.2) {
+                    date.0 -= 1;
+                }
+                let mut micros = self.to_micros(date, &c);
+                if self.rolled_over(micros, self.year_rollover) {
+                    date.0 += 1;
+                    micros = self.to_micros(date, &c);
+                }
+                self.date = date;
+                micros
+            }
+            (year, None) => {
+                if let Some(year) = year {
+                    self.date.0 = year;
+                }
+                let mut micros = self.to_micros(self.date, &c);
+                if self.rolled_over(micros, self.day_rollover) {
+                    let (y, m, d) = self.date;
+                    self.date = civil_from_days(days_from_civil(y, m, d) + 1);
+                    micros = self.to_micros(self.date, &c);
+                }
+                micros
+            }
+        };
+        self.last = Some(micros);
+        micros
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parser(format: &str, configure: impl FnOnce(&mut TimeSpec)) -> TimeParser {
+        let mut spec = TimeSpec {
+            format: format.to_string(),
+            reference_date: Some("2024-12-31".to_string()),
+            ..TimeSpec::default()
+        };
+        configure(&mut spec);
+        TimeParser::new(&spec).unwrap()
+    }
+
+    fn at(date: (i32, u32, u32), hms: (i64, i64, i64)) -> i64 {
+        (days_from_civil(date.0, date.1, date.2) * 86_400 + hms.0 * 3600 + hms.1 * 60 + hms.2) * MICROS_PER_SEC
+    }
+
+    #[test]
+    fn auto_reads_logcat_kernel_and_full_dates() {
+        let mut p = parser("auto", |_| {});
+        assert_eq!(p.parse_line(b"12-31 23:59:58.500 I/Tag( 1): x"), at((2024, 12, 31), (23, 59, 58)) + 500_000);
+        assert_eq!(p.parse_line(b"2023-05-01 10:00:00.000 boot"), at((2023, 5, 1), (10, 0, 0)));
+        assert_eq!(p.parse_line(b"[  12.345678] usb: reset"), 12_345_678);
+        assert_eq!(p.parse_line(b"\x1B[32m12-31 10:00:00.000\x1B[0m colored"), at((2023, 12, 31), (10, 0, 0)));
+        assert_eq!(p.parse_line(b"no time here 200000.0"), NO_TIME);
+    }
+
+    #[test]
+    fn year_and_day_rollover_use_the_configured_thresholds() {
+        let mut p = parser("auto", |_| {});
+        p.parse_line(b"12-31 23:59:59.000 last of the year");
+        assert_eq!(p.parse_line(b"01-01 00:00:01.000 new year"), at((2025, 1, 1), (0, 0, 1)));
+        // 30일 이내로 거꾸로 가면 같은 해 (로그 순서가 조금 섞인 것)
+        p.reset();
+        p.parse_line(b"03-10 10:00:00.000 a");
+        assert_eq!(p.parse_line(b"03-01 10:00:00.000 b"), at((2024, 3, 1), (10, 0, 0)));
+
+        let mut p = parser("auto", |s| s.year_rollover_days = 5);
+        p.parse_line(b"03-10 10:00:00.000 a");
+        assert_eq!(p.parse_line(b"03-01 10:00:00.000 b"), at((2025, 3, 1), (10, 0, 0)));
+
+        let mut p = parser("auto", |s| s.year_rollover_days = 0);
+        p.parse_line(b"12-31 23:59:59.000 a");
+        assert_eq!(p.parse_line(b"01-01 00:00:01.000 b"), at((2024, 1, 1), (0, 0, 1)));
+    }
+
+    #[test]
+    fn day_rollover_for_time_only_logs() {
+        let mut p = parser("HH:mm:ss", |_| {});
+        assert_eq!(p.parse_line(b"23:59:59 a"), at((2024, 12, 31), (23, 59, 59)));
+        assert_eq!(p.parse_line(b"00:00:02 b"), at((2025, 1, 1), (0, 0, 2)));
+        // 12시간 이내 역행은 같은 날
+        assert_eq!(p.parse_line(b"13:00:00 c"), at((2025, 1, 1), (13, 0, 0)));
+        assert_eq!(p.parse_line(b"02:00:00 d"), at((2025, 1, 1), (2, 0, 0)));
+
+        let mut p = parser("HH:mm:ss", |s| s.day_rollover_hours = 1);
+        p.parse_line(b"13:00:00 a");
+        assert_eq!(p.parse_line(b"11:00:00 b"), at((2025, 1, 1), (11, 0, 0)));
+
+        let mut p = parser("HH:mm:ss", |s| s.day_rollover_hours = 0);
+        p.parse_line(b"23:59:59 a");
+        assert_eq!(p.parse_line(b"00:00:02 b"), at((2024, 12, 31), (0, 0, 2)));
+    }
+
+    #[test]
+    fn first_year_less_date_after_the_reference_is_last_year() {
+        let mut p = parser("MM-DD HH:mm", |s| s.reference_date = Some("2025-01-02".to_string()));
+        assert_eq!(p.parse_line(b"12-31 23:59 a"), at((2024, 12, 31), (23, 59, 0)));
+        assert_eq!(p.parse_line(b"01-01 00:01 b"), at((2025, 1, 1), (0, 1, 0)));
+
+        // 기준 날짜 이전이면 그 해 그대로
+        let mut p = parser("MM-DD HH:mm", |s| s.reference_date = Some("2025-01-02".to_string()));
+        assert_eq!(p.parse_line(b"01-01 00:01 a"), at((2025, 1, 1), (0, 1, 0)));
+        // 기준 날짜가 없으면 1970 그대로
+        let mut p = parser("MM-DD HH:mm", |s| s.reference_date = None);
+        assert_eq!(p.parse_line(b"12-31 23:59 a"), at((1970, 12, 31), (23, 59, 0)));
+    }
+
+    #[test]
+    fn seed_continues_the_year_and_day_of_an_earlier_line() {
+        let mut p = parser("MM-DD HH:mm:ss", |s| s.utc_offset_minutes = 540);
+        p.seed(at((2025, 12, 31), (14, 0, 0)));
+        assert_eq!(p.parse_line(b"01-01 09:00:01 a"), at((2026, 1, 1), (0, 0, 1)));
+
+        let mut p = parser("HH:mm:ss", |_| {});
+        p.seed(at((2025, 3, 9), (23, 59, 0)));
+        assert_eq!(p.parse_line(b"00:00:30 a"), at((2025, 3, 10), (0, 0, 30)));
+
+        // NO_TIME / monotonic 값은 reset 과 같음
+        for seed in [NO_TIME, 12_345_678] {
+            p.seed(seed);
+            assert_eq!(p.parse_line(b"00:00:30 a"), at((2024, 12, 31), (0, 0, 30)));
+        }
+    }
+
+    #[test]
+    fn custom_format_offset_and_errors() {
+        let mut p = parser("YYYY/MM/DD HH:mm:ss.SSS", |s| s.utc_offset_minutes = 540);
+        assert_eq!(p.parse_line(b"[main] 2024/02/29  09:00:00.250 up"), at((2024, 2, 29), (0, 0, 0)) + 250_000);
+        assert_eq!(p.parse_line(b"2024/13/01 09:00:00.000"), NO_TIME);
+
+        for bad in ["YYYY-MM", "HH:mm:ss.SSSSSSSSSS", "HH:mm Q"] {
+            let spec = TimeSpec { format: bad.to_string(), ..TimeSpec::default() };
+            assert!(matches!(TimeParser::new(&spec), Err(FilterError::TimeFormat { .. })), "{}", bad);
+        }
+        let spec = TimeSpec { reference_date: Some("31/12/2024".to_string()), ..TimeSpec::default() };
+        assert!(TimeParser::new(&spec).is_err());
+    }
+}
@@ -0,0 +1,641 @@
+use memchr::memchr_iter;
+use serde::{Deserialize, Serialize};
+use std::ops::Range;
+
+use crate::error::FilterError;
+use crate::fields;
+use crate::line;
+use crate::time::{self, TimeParser, TimeSpec, MICROS_PER_DAY, MICROS_PER_SEC, NO_TIME};
+
+/// 레벨별 칸 수: 레벨 없음 + V, D, I, W, E, F
+pub const LEVEL_SLOTS: usize = 7;
+
+/// `levels` 의 플래그: 라인에 자기 타임스탬프가 있음 (없으면 앞 라인 시각을 물려받은 것)
+const HAS_TIME: u8 = 0x80;
+
+/// 라인별 타임스탬프/레벨 컬럼 (`workerHistogramHandler.ts` 의 `buildTimestampCache` 대체)
+///
+/// 인덱싱할 때 `LineIndex` 와 같은 청크를 `append` 하면 라인마다 한 번씩만 시간을 파싱해 둡니다.
+/// 시간은 epoch 마이크로초로, 타임스탬프가 없는 라인(스택 트레이스 등)은 앞 라인의 시각을 물려받습니다.
+/// (첫 타임스탬프 전 라인은 `NO_TIME`) 레벨은 `fields::parse_fields` 가 헤더에서 찾은 레벨입니다.
+/// 시간과 레벨 모두 ANSI 이스케이프 / 끝의 `\r` 을 지운 같은 라인에서 읽습니다.
+pub struct TimeColumn {
+    parser: TimeParser,
+    times: Vec<i64>,
+    /// 하위 비트: 0 = 레벨 없음, 1.. = `LogLevel as u8 + 1` (히스토그램 칸 번호와 같음) + `HAS_TIME`
+    levels: Vec<u8>,
+    /// 마지막으로 나온 타임스탬프
+    last: i64,
+    /// 시각이 한 번도 거꾸로 가지 않았으면 true (시간 범위를 이분 탐색으로 찾을 수 있음)
+    sorted: bool,
+    /// 아직 `\n` 이 안 나온 마지막 라인
+    pending: Vec<u8>,
+    clean_buffer: Vec<u8>,
+}
+
+/// 히스토그램 결과 (시간은 epoch 마이크로초, 구간은 `[start, end)`, 마지막 칸만 `end` 포함)
+#[derive(Serialize, Clone, Debug, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct Histogram {
+    pub start: i64,
+    pub end: i64,
+    pub bucket_width: f64,
+    pub counts: Vec<u32>,
+    /// 칸마다 `LEVEL_SLOTS` 개씩: `[없음, V, D, I, W, E, F]`
+    pub level_counts: Vec<u32>,
+    /// 칸에 처음 들어온 라인의 `indices` 안 위치 (= 필터 결과 목록의 행 번호, 없으면 -1)
+    pub first_positions: Vec<i32>,
+    pub max_count: u32,
+    /// 칸에 들어간 라인 수 (시간이 없거나 창 밖인 라인 제외)
+    pub bucketed_count: u32,
+    /// 입력 라인 수 (`indices.length`)
+    pub total_count: u32,
+}
+
+impl TimeColumn {
+    pub fn new(spec: &TimeSpec) -> Result<Self, FilterError> {
+        Ok(TimeColumn {
+            parser: TimeParser::new(spec)?,
+            times: Vec::new(),
+            levels: Vec::new(),
+            last: NO_TIME,
+            sorted: true,
+            pending: Vec::new(),
+            clean_buffer: Vec::new(),
+        })
+    }
+
+    /// 예상 라인 수만큼 미리 확보
+    pub fn reserve(&mut self, lines: usize) {
+        self.times.reserve(lines);
+        self.levels.reserve(lines);
+    }
+
+    /// 파일의 다음 청크 (라인 경계와 상관없이 잘라도 되지만, 파일 순서대로 넣어야 날짜 넘어감을 따라갑니다)
+    pub fn append(&mut self, chunk: &[u8]) {
+        let mut start = 0;
+        for pos in memchr_iter(b'\n', chunk) {
+            if self.pending.is_empty() {
+                self.add_line(&chunk[start..pos]);
+            } else {
+                let mut pending = std::mem::take(&mut self.pending);
+                pending.extend_from_slice(&chunk[start..pos]);
+                self.add_line(&pending);
+                pending.clear();
+                self.pending = pending;
+            }
+            start = pos + 1;
+        }
+        self.pending.extend_from_slice(&chunk[start..]);
+    }
+
+    /// 파일 끝: `\n` 없이 끝난 마지막 라인까지 컬럼에 넣습니다.
+    pub fn finish(&mut self) {
+        if !self.pending.is_empty() {
+            let pending = std::mem::take(&mut self.pending);
+            self.add_line(&pending);
+        }
+    }
+
+    fn add_line(&mut self, raw: &[u8]) {
+        let mut buf = std::mem::take(&mut self.clean_buffer);
+        let clean = line::clean_line(raw, &mut buf);
+        let mut flags = fields::parse_fields(clean).level.map_or(0, |level| level.value as u8 + 1);
+        let stamp = self.parser.parse_clean(clean);
+        self.clean_buffer = buf;
+        if stamp != NO_TIME {
+            self.sorted &= stamp >= self.last;
+            self.last = stamp;
+            flags |= HAS_TIME;
+        }
+        self.times.push(self.last);
+        self.levels.push(flags);
+    }
+
+    /// 컬럼에 들어간 (완결된) 라인 수
+    pub fn line_count(&self) -> usize {
+        self.times.len()
+    }
+
+    /// 라인별 타임스탬프 (epoch 마이크로초, 없는 라인은 앞 라인 값, 첫 타임스탬프 전은 `NO_TIME`)
+    pub fn times(&self) -> &[i64] {
+        &self.times
+    }
+
+    pub fn reset(&mut self) {
+        self.parser.reset();
+        self.times.clear();
+        self.levels.clear();
+        self.last = NO_TIME;
+        self.sorted = true;
+        self.pending.clear();
+    }
+
+    /// `line` 자체에 타임스탬프가 있을 때만 그 시각
+    fn own_time(&self, line: usize) -> Option<i64> {
+        (self.levels.get(line)? & HAS_TIME != 0).then(|| self.times[line])
+    }
+
+    /// 시간 범위에 들어가는 라인 구간들 (오름차순, 겹침 없음)
+    ///
+    /// 시각이 거꾸로 간 적 없는 로그는 이분 탐색으로 찾고, 아니면 컬럼만 한 번 훑습니다. (라인 본문은 다시 읽지 않음)
+    /// 판정은 룰의 `TimeFilter` 와 같으므로 이 구간만 `filter_chunk_timed` 해도 결과가 같습니다.
+    pub fn window_ranges(&self, window: &TimeWindow) -> Vec<Range<usize>> {
+        let mut ranges: Vec<Range<usize>> = Vec::new();
+        let mut push = |range: Range<usize>| match ranges.last_mut() {
+            Some(last) if last.end == range.start => last.end = range.end,
+            _ if range.is_empty() => {}
+            _ => ranges.push(range),
+        };
+
+        if self.sorted {
+            // 첫 타임스탬프 전 라인(`NO_TIME`)은 맨 앞에 모여 있음
+            let first = self.times.partition_point(|&t| t == NO_TIME);
+            if let (Some(&lo), Some(&hi)) = (self.times.get(first), self.times.last()) {
+                for (start, end) in window.intervals(lo, hi) {
+                    push(self.times.partition_point(|&t| t < start)..self.times.partition_point(|&t| t <= end));
+                }
+            }
+        } else {
+            let mut run_start = None;
+            for (line, &t) in self.times.iter().enumerate() {
+                match (window.contains(t), run_start) {
+                    (true, None) => run_start = Some(line),
+                    (false, Some(start)) => {
+                        push(start..line);
+                        run_start = None;
+                    }
+                    _ => {}
+                }
+            }
+            if let Some(start) = run_start {
+                push(start..self.times.len());
+            }
+        }
+        ranges
+    }
+
+    /// `indices` 라인들(`None` = 전체)의 시간 분포를 `bucket_count` 칸으로 셉니다.
+    ///
+    /// `window` 가 없으면 `indices` 중 시간이 있는 라인의 최소~최대 구간을 씁니다. 컬럼 밖 인덱스는 무시합니다.
+    pub fn histogram(&self, indices: Option<&[i32]>, bucket_count: usize, window: Option<(i64, i64)>) -> Histogram {
+        let bucket_count = bucket_count.max(1);
+        let total_count = indices.map_or(self.times.len(), <[i32]>::len) as u32;
+        let time_at = |line: i32| usize::try_from(line).ok().and_then(|l| self.own_time(l));
+
+        let (start, end) = match window {
+            Some((start, end)) => (start.min(end), start.max(end)),
+            None => {
+                let mut range: Option<(i64, i64)> = None;
+                let mut widen = |t: i64| range = Some(range.map_or((t, t), |(lo, hi)| (lo.min(t), hi.max(t))));
+                match indices {
+                    Some(indices) => indices.iter().filter_map(|&i| time_at(i)).for_each(&mut widen),
+                    None => (0..self.times.len()).filter_map(|l| self.own_time(l)).for_each(&mut widen),
+                }
+                match range {
+                    Some(range) => range,
+                    None => return Histogram { total_count, ..Histogram::default() },
+                }
+            }
+        };
+
+        // 모든 시각이 같으면 폭 1 짜리 칸 하나
+        let bucket_count = if start == end { 1 } else { bucket_count };
+        // 호출하는 쪽 구간이 i64 끝까지 가도 넘치지 않도록 abs_diff (u64)
+        let bucket_width = (end.abs_diff(start) as f64 / bucket_count as f64).max(1.0);
+        let mut histogram = Histogram {
+            start,
+            end,
+            bucket_width,
+            counts: vec![0; bucket_count],
+            level_counts: vec![0; bucket_count * LEVEL_SLOTS],
+            first_positions: vec![-1; bucket_count],
+            max_count: 0,
+            bucketed_count: 0,
+            total_count,
+        };
+
+        let mut add = |position: usize, line: usize| {
+            // 히스토그램은 자기 타임스탬프가 있는 라인만 (기존 `calculateHistogram` 과 동일)
+            let t = match self.own_time(line) {
+                Some(t) if (start..=end).contains(&t) => t,
+                _ => return,
+            };
+            let bucket = ((t.abs_diff(start) as f64 / bucket_width) as usize).min(bucket_count - 1);
+            if histogram.counts[bucket] == 0 {
+                histogram.first_positions[bucket] = position as i32;
+            }
+            histogram.counts[bucket] += 1;
+            histogram.level_counts[bucket * LEVEL_SLOTS + (self.levels[line] & !HAS_TIME) as usize] += 1;
+            histogram.max_count = histogram.max_count.max(histogram.counts[bucket]);
+            histogram.bucketed_count += 1;
+        };
+        match indices {
+            Some(indices) => {
+                for (position, &line) in indices.iter().enumerate() {
+                    match usize::try_from(line) {
+                        Ok(line) if line < self.times.len() => add(position, line),
+                        _ => {}
+                    }
+                }
+            }
+            None => (0..self.times.len()).for_each(|line| add(line, line)),
+        }
+        histogram
+    }
+}
+
+/// 룰의 시간 범위 조건 (`LogRule.timeRange`)
+#[derive(Serialize, Deserialize, Default, Clone, Debug)]
+#[serde(rename_all = "camelCase", default)]
+pub struct TimeRange {
+    /// `"10:32:00"`, `"10:32"`, `"10:32:00.500"` (매일 반복) 또는 `"2024-03-01 10:32:00"`, `"2024-03-01"`. 없으면 처음부터
+    pub start: Option<String>,
+    /// `start` 와 같은 형식, 적은 자리까지 포함 (`"10:35"` = 10:35:59.999999 까지). 없으면 끝까지
+    pub end: Option<String>,
+    /// 라인 타임스탬프 추출 설정 (`TimeIndexer` 와 같은 설정을 써야 `window_ranges` 와 결과가 같음)
+    pub timestamps: TimeSpec,
+}
+
+/// 범위 경계
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Bound {
+    /// 하루 중 시각 (로그 현지 시각, 마이크로초) - 날짜와 상관없이 매일 적용
+    Clock(i64),
+    /// epoch 마이크로초
+    Instant(i64),
+}
+
+/// 컴파일된 시간 범위 (양 끝 포함)
+///
+/// 시각만 준 경계끼리 `start > end` 면 자정을 넘는 범위입니다. (`23:50` ~ `00:10`)
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TimeWindow {
+    start: Option<Bound>,
+    end: Option<Bound>,
+    offset_micros: i64,
+}
+
+impl TimeWindow {
+    /// 경계가 둘 다 비어 있으면 `None` (= 조건 없음)
+    pub fn compile(range: &TimeRange) -> Result<Option<Self>, FilterError> {
+        let offset_micros = range.timestamps.utc_offset_minutes as i64 * 60 * MICROS_PER_SEC;
+        let start = parse_bound(range.start.as_deref(), false, offset_micros)?;
+        let end = parse_bound(range.end.as_deref(), true, offset_micros)?;
+        if start.is_none() && end.is_none() {
+            return Ok(None);
+        }
+        Ok(Some(TimeWindow { start, end, offset_micros }))
+    }
+
+    /// `t` 가 범위 안인지 (`NO_TIME` 은 항상 밖)
+    pub fn contains(&self, t: i64) -> bool {
+        if t == NO_TIME {
+            return false;
+        }
+        let clock = (t + self.offset_micros).rem_euclid(MICROS_PER_DAY);
+        let after = |b: &Bound| match *b {
+            Bound::Clock(c) => clock >= c,
+            Bound::Instant(i) => t >= i,
+        };
+        let before = |b: &Bound| match *b {
+            Bound::Clock(c) => clock <= c,
+            Bound::Instant(i) => t <= i,
+        };
+        match (&self.start, &self.end) {
+            (Some(Bound::Clock(s)), Some(Bound::Clock(e))) if s > e => clock >= *s || clock <= *e,
+            (start, end) => start.as_ref().is_none_or(after) && end.as_ref().is_none_or(before),
+        }
+    }
+
+    /// `[lo, hi]` 안에서 범위에 들어가는 epoch 구간들 (오름차순). 시각 경계는 날짜마다 구간 하나
+    fn intervals(&self, lo: i64, hi: i64) -> Vec<(i64, i64)> {
+        let lo = match self.start {
+            Some(Bound::Instant(i)) => lo.max(i),
+            _ => lo,
+        };
+        let hi = match self.end {
+            Some(Bound::Instant(i)) => hi.min(i),
+            _ => hi,
+        };
+        let clock_start = match self.start {
+            Some(Bound::Clock(c)) => Some(c),
+            _ => None,
+        };
+        let clock_end = match self.end {
+            Some(Bound::Clock(c)) => Some(c),
+            _ => None,
+        };
+        if lo > hi {
+            return Vec::new();
+        }
+        if clock_start.is_none() && clock_end.is_none() {
+            return vec![(lo, hi)];
+        }
+
+        let from = clock_start.unwrap_or(0);
+        let to = clock_end.unwrap_or(MICROS_PER_DAY - 1);
+        // 자정을 넘는 범위는 다음 날까지 이어짐
+        let to = if from <= to { to } else { to + MICROS_PER_DAY };
+        let first_day = (lo + self.offset_micros).div_euclid(MICROS_PER_DAY) - 1;
+        let last_day = (hi + self.offset_micros).div_euclid(MICROS_PER_DAY);
+        (first_day..=last_day)
+            .map(|day| day * MICROS_PER_DAY - self.offset_micros)
+            .map(|midnight| ((midnight + from).max(lo), (midnight + to).min(hi)))
+            .filter(|(start, end)| start <= end)
+            .collect()
+    }
+}
+
+/// `HH:mm[:ss[.S..]]` 또는 `YYYY-MM-DD[ HH:mm[:ss[.S..]]]` (날짜와 시각 사이는 공백이나 `T`)
+///
+/// 끝 경계는 적은 자리의 마지막 순간까지 포함합니다. (`10:35` → 10:35:59.999999, 날짜만 → 그날 23:59:59.999999)
+fn parse_bound(text: Option<&str>, is_end: bool, offset_micros: i64) -> Result<Option<Bound>, FilterError> {
+    let text = match text.map(str::trim) {
+        Some(text) if !text.is_empty() => text,
+        _ => return Ok(None),
+    };
+    let invalid = || FilterError::TimeFormat {
+        format: text.to_string(),
+        message: "expected HH:mm[:ss[.SSS]] or YYYY-MM-DD [HH:mm[:ss[.SSS]]]".to_string(),
+    };
+
+    let b = text.as_bytes();
+    let (date, clock) = match text.get(..10).and_then(time::parse_date) {
+        Some(date) if b.len() == 10 => (Some(date), &b[10..]),
+        Some(date) if matches!(b[10], b' ' | b'T') => (Some(date), &b[11..]),
+        Some(_) => return Err(invalid()),
+        None => (None, b),
+    };
+
+    let (micros, unit) = if clock.is_empty() {
+        (0, MICROS_PER_DAY)
+    } else {
+        parse_clock(clock).ok_or_else(invalid)?
+    };
+    let micros = if is_end { micros + unit - 1 } else { micros };
+    Ok(Some(match date {
+        Some((y, m, d)) => Bound::Instant(time::days_from_civil(y, m, d) * MICROS_PER_DAY + micros - offset_micros),
+        None => Bound::Clock(micros),
+    }))
+}
+
+/// `HH:mm[:ss[.S..]]` → (하루 중 마이크로초, 마지막 자리 단위)
+fn parse_clock(s: &[u8]) -> Option<(i64, i64)> {
+    let hour = time::digits(s, 0, 2)?;
+    let minute = (s.get(2) == Some(&b':')).then(|| time::digits(s, 3, 2))??;
+    let (second, micros, unit) = match s.get(5) {
+        None => (0, 0, 60 * MICROS_PER_SEC),
+        Some(b':') => {
+            let second = time::digits(s, 6, 2)?;
+            match s.get(8) {
+                None => (second, 0, MICROS_PER_SEC),
+                Some(b'.' | b',') => {
+                    let fraction = &s[9..];
+                    if fraction.is_empty() || fraction.len() > 6 || !fraction.iter().all(u8::is_ascii_digit) {
+                        return None;
+                    }
+                    (second, time::fraction_micros(fraction), 10i64.pow(6 - fraction.len() as u32))
+                }
+                Some(_) => return None,
+            }
+        }
+        Some(_) => return None,
+    };
+    if hour >= 24 || minute >= 60 || second >= 60 {
+        return None;
+    }
+    let secs = (hour * 3600 + minute * 60 + second) as i64;
+    Some((secs * MICROS_PER_SEC + micros as i64, unit))
+}
+
+/// 필터 호출 한 번에서 라인 시각을 어디서 가져올지
+#[derive(Clone, Copy, Debug)]
+pub enum LineTimes<'a> {
+    /// 라인에서 읽습니다. 값 = 청크 첫 라인 바로 앞 라인의 시각 (`NO_TIME` = 파일 처음/모름)
+    Parse(i64),
+    /// `TimeColumn::times` 를 라인 인덱스(`base_index + i`)로 찾습니다. 아직 컬럼에 없는 라인만 라인에서 읽습니다.
+    Column(&'a [i64]),
+}
+
+impl LineTimes<'_> {
+    /// `base_index` 라인 바로 앞 라인의 시각
+    pub fn seed(&self, base_index: i32) -> i64 {
+        match *self {
+            LineTimes::Parse(seed) => seed,
+            LineTimes::Column(times) => Self::column_time(times, base_index as i64 - 1).unwrap_or(NO_TIME),
+        }
+    }
+
+    /// `line_index` 라인의 시각 (`None` = 라인에서 읽어야 함)
+    pub fn get(&self, line_index: i32) -> Option<i64> {
+        match *self {
+            LineTimes::Parse(_) => None,
+            LineTimes::Column(times) => Self::column_time(times, line_index as i64),
+        }
+    }
+
+    fn column_time(times: &[i64], line_index: i64) -> Option<i64> {
+        usize::try_from(line_index).ok().and_then(|i| times.get(i)).copied()
+    }
+}
+
+/// 룰 안의 시간 범위 판정기
+///
+/// 라인마다 타임스탬프를 읽고, 없는 라인은 바로 앞 라인의 시각을 물려받습니다. (`TimeColumn` 과 같은 규칙)
+/// 물려받는 상태는 `begin` 으로 필터 호출마다 새로 시작합니다. 호출 사이에는 이어지지 않으므로
+/// 청크 첫 라인 앞의 시각은 호출하는 쪽이 `seed` 로 넘기거나, `accepts_at` 으로 `TimeColumn` 의 시각을 직접 씁니다.
+pub struct TimeFilter {
+    window: TimeWindow,
+    parser: TimeParser,
+    last: i64,
+    /// `parser` 가 `last` 부터 이어 읽도록 맞춰져 있는지 (`accepts_at` 뒤에는 다시 맞춤)
+    synced: bool,
+}
+
+impl TimeFilter {
+    pub fn compile(range: &TimeRange) -> Result<Option<Self>, FilterError> {
+        let window = match TimeWindow::compile(range)? {
+            Some(window) => window,
+            None => return Ok(None),
+        };
+        Ok(Some(TimeFilter { window, parser: TimeParser::new(&range.timestamps)?, last: NO_TIME, synced: false }))
+    }
+
+    pub fn window(&self) -> &TimeWindow {
+        &self.window
+    }
+
+    /// 필터 호출 시작. `seed` = 첫 라인 바로 앞 라인의 시각 (`NO_TIME` = 파일 처음/모름)
+    pub fn begin(&mut self, seed: i64) {
+        self.last = seed;
+        self.synced = false;
+    }
+
+    /// 마지막으로 판정한 라인의 시각 (다음 청크의 `seed`)
+    pub fn last(&self) -> i64 {
+        self.last
+    }
+
+    /// 라인에서 타임스탬프를 읽어 판정
+    pub fn accepts(&mut self, line: &[u8]) -> bool {
+        if !self.synced {
+            self.parser.seed(self.last);
+            self.synced = true;
+        }
+        let stamp = self.parser.parse_line(line);
+        if stamp != NO_TIME {
+            self.last = stamp;
+        }
+        self.window.contains(self.last)
+    }
+
+    /// 이미 아는 시각(`TimeColumn::times`, 물려받은 값 포함)으로 판정
+    pub fn accepts_at(&mut self, time: i64) -> bool {
+        self.last = time;
+        self.synced = false;
+        self.window.contains(time)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::level::LogLevel;
+
+    fn column(data: &[u8]) -> TimeColumn {
+        let mut column = TimeColumn::new(&TimeSpec::default()).unwrap();
+        column.append(data);
+        column.finish();
+        column
+    }
+
+    fn window(start: Option<&str>, end: Option<&str>, offset_minutes: i32) -> Result<Option<TimeWindow>, FilterError> {
+        TimeWindow::compile(&TimeRange {
+            start: start.map(str::to_string),
+            end: end.map(str::to_string),
+            timestamps: TimeSpec { utc_offset_minutes: offset_minutes, ..TimeSpec::default() },
+        })
+    }
+
+    fn at(day: u32, hour: i64, minute: i64) -> i64 {
+        (time::days_from_civil(2024, 3, day) * 86_400 + hour * 3600 + minute * 60) * MICROS_PER_SEC
+    }
+
+    /// 2024-03-01 부터 37분 간격, 세 라인마다 타임스탬프 없는 줄. `rewind` 면 열 라인마다 5시간 전 시각
+    fn timed_log(rewind: bool) -> Vec<u8> {
+        let mut out = b"boot banner\n".to_vec();
+        for i in 0..200i64 {
+            let minutes = i * 37 - if rewind && i % 10 == 9 { 300 } else { 0 };
+            let (day, hour, minute) = (1 + minutes / 1440, minutes % 1440 / 60, minutes % 60);
+            out.extend_from_slice(format!("2024-03-{:02} {:02}:{:02}:00.000 I/T: {}\n", day, hour, minute, i).as_bytes());
+            if i % 3 == 0 {
+                out.extend_from_slice(b"  continued\n");
+            }
+        }
+        out
+    }
+
+    #[test]
+    fn window_ranges_match_a_per_line_scan() {
+        let windows = [
+            (Some("10:00"), Some("12:30")),
+            (Some("23:00"), Some("01:15")),
+            (Some("2024-03-02 05:00"), None),
+            (None, Some("2024-03-02")),
+            (Some("2024-03-01 20:00"), Some("03:00")),
+            (Some("22:00"), Some("2024-03-03 02:00")),
+            (Some("2024-03-05"), None),
+        ];
+        for rewind in [false, true] {
+            let column = column(&timed_log(rewind));
+            assert_eq!(column.sorted, !rewind);
+            for (start, end) in windows {
+                let window = window(start, end, 0).unwrap().unwrap();
+                let expected: Vec<usize> = (0..column.line_count()).filter(|&l| window.contains(column.times()[l])).collect();
+                let ranges = column.window_ranges(&window);
+                assert!(ranges.windows(2).all(|w| w[0].end < w[1].start), "{:?} {:?}", (start, end), ranges);
+                let got: Vec<usize> = ranges.into_iter().flatten().collect();
+                assert_eq!(got, expected, "rewind={} {:?}", rewind, (start, end));
+            }
+        }
+    }
+
+    #[test]
+    fn clock_windows_cross_midnight_in_log_local_time() {
+        let w = window(Some("23:00"), Some("01:15"), 0).unwrap().unwrap();
+        assert!(w.contains(at(1, 23, 0)) && w.contains(at(2, 0, 30)) && w.contains(at(2, 1, 15)));
+        assert!(!w.contains(at(2, 1, 16)) && !w.contains(at(1, 12, 0)) && !w.contains(NO_TIME));
+        assert_eq!(w.intervals(at(1, 0, 0), at(2, 23, 59)), [
+            (at(1, 0, 0), at(1, 1, 15) + 60 * MICROS_PER_SEC - 1),
+            (at(1, 23, 0), at(2, 1, 15) + 60 * MICROS_PER_SEC - 1),
+            (at(2, 23, 0), at(2, 23, 59)),
+        ]);
+
+        // KST 23:30 = UTC 14:30
+        let kst = window(Some("23:00"), Some("01:15"), 540).unwrap().unwrap();
+        assert!(kst.contains(at(1, 14, 30)) && !kst.contains(at(1, 23, 30)));
+        // 날짜 경계 + 시각 경계: 날짜 이후의 매일 03:00 까지
+        let mixed = window(Some("2024-03-02 00:00"), Some("03:00"), 0).unwrap().unwrap();
+        assert!(mixed.contains(at(2, 2, 0)) && mixed.contains(at(3, 3, 0)));
+        assert!(!mixed.contains(at(1, 2, 0)) && !mixed.contains(at(2, 4, 0)));
+    }
+
+    #[test]
+    fn bounds_include_the_whole_last_unit() {
+        let end = |text: &str| parse_bound(Some(text), true, 0).unwrap().unwrap();
+        let second = MICROS_PER_SEC;
+        assert_eq!(end("10:35"), Bound::Clock((10 * 3600 + 36 * 60) * second - 1));
+        assert_eq!(end("10:35:10"), Bound::Clock((10 * 3600 + 35 * 60 + 11) * second - 1));
+        assert_eq!(end("10:35:10.5"), Bound::Clock((10 * 3600 + 35 * 60 + 10) * second + 599_999));
+        assert_eq!(end("2024-03-01"), Bound::Instant(at(2, 0, 0) - 1));
+        assert_eq!(parse_bound(Some("2024-03-01T10:00"), false, 0).unwrap(), Some(Bound::Instant(at(1, 10, 0))));
+        assert_eq!(parse_bound(Some("2024-03-01 10:00"), false, 540 * 60 * MICROS_PER_SEC).unwrap(), Some(Bound::Instant(at(1, 1, 0))));
+        assert_eq!(parse_bound(Some("  "), false, 0).unwrap(), None);
+        assert!(window(None, Some(""), 0).unwrap().is_none());
+    }
+
+    #[test]
+    fn malformed_bounds_are_time_format_errors() {
+        for bad in ["25:00", "10:60", "10:00:60", "10", "10:5", "10:00:00.", "10:00:00.1234567", "10:00 x", "10:00:00x",
+            "2024-13-01", "2024-03-01x10:00", "2024-03-01 24:00", "noon"]
+        {
+            assert!(matches!(window(Some(bad), None, 0), Err(FilterError::TimeFormat { .. })), "{}", bad);
+            assert!(matches!(window(None, Some(bad), 0), Err(FilterError::TimeFormat { .. })), "{}", bad);
+        }
+    }
+
+    #[test]
+    fn levels_come_from_the_cleaned_header() {
+        let column = column(
+            b"\x1B[31m01-01 10:00:00.000 E/Net( 1): red\x1B[0m\r\n\
+              01-01 10:00:01.000 I/Net( 1): E inside message\r\n\
+              \x1B[1mW\x1B[0m/Tag( 2): bold level\n\
+              no level at all\n",
+        );
+        let levels: Vec<u8> = column.levels.iter().map(|l| l & !HAS_TIME).collect();
+        let slot = |level: LogLevel| level as u8 + 1;
+        assert_eq!(levels, [slot(LogLevel::Error), slot(LogLevel::Info), slot(LogLevel::Warn), 0]);
+        assert_eq!(column.own_time(0), Some(column.times()[0]));
+        assert_eq!(column.own_time(2), None);
+        assert_eq!(column.times()[3], column.times()[1]);
+    }
+
+    #[test]
+    fn histogram_counts_levels_per_bucket() {
+        let column = column(b"01-01 10:00:00.000 E/A( 1): x\n01-01 10:00:10.000 W/A( 1): y\ntrace\n01-01 10:00:20.000 E/A( 1): z\n");
+        let h = column.histogram(None, 2, None);
+        assert_eq!(h.counts, [1, 2]);
+        assert_eq!(h.bucketed_count, 3);
+        assert_eq!(h.total_count, 4);
+        assert_eq!(h.level_counts[LEVEL_SLOTS + LogLevel::Error as usize + 1], 1);
+        assert_eq!(h.level_counts[LogLevel::Error as usize + 1], 1);
+        assert_eq!(h.first_positions, [0, 1]);
+
+        // i64 전체 구간도 넘치지 않음
+        let h = column.histogram(None, 4, Some((i64::MIN, i64::MAX)));
+        assert_eq!(h.bucketed_count, 3);
+        assert_eq!(h.counts.iter().sum::<u32>(), 3);
+        assert!(h.bucket_width > 0.0);
+    }
+}